@@ -2,13 +2,14 @@ use actix_web::dev::Server;
 use actix_web::web::Data;
 use actix_web::{guard, web, App, HttpServer, Scope};
 use heos_api::HeosDriver;
+use heos_axum::art::ArtCache;
 use std::net::TcpListener;
 use tracing_actix_web::TracingLogger;
 // TODO NOT The Tokio one!?
 use crate::configuration::Settings;
-use crate::routers::{api, music_source};
+use crate::routers::{api, music_source, policies};
 use crate::routers::{
-    health_check, home, main_css, zone::details, zone::edit_zone_members_form,
+    health_check, home, main_css, metrics, show_art, zone::details, zone::edit_zone_members_form,
     zone::list as list_zones, zone::new as new_zone,
 };
 
@@ -24,11 +25,22 @@ impl Application {
             "{}:{}",
             configuration.application.host, configuration.application.port
         );
-        let heos_address = format!("{}:{}", configuration.heos.host, 1255);
+        let heos_address = format!("{}:{}", configuration.heos.host, configuration.heos.port);
         let listener = TcpListener::bind(&address)?;
         let driver = heos_api::HeosDriver::new(heos_address).await?;
+        driver.set_volume_policies(configuration.heos.volume_policies.clone());
+        let art = ArtCache::open(
+            &configuration.art.directory,
+            configuration.art.max_megabytes * 1024 * 1024,
+        )?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(listener, configuration.application.base_url, driver.clone()).await?;
+        let server = run(
+            listener,
+            configuration.application.base_url,
+            driver.clone(),
+            art,
+        )
+        .await?;
         Ok(Self {
             port,
             server,
@@ -49,8 +61,10 @@ async fn run(
     listener: TcpListener,
     base_url: String,
     driver: HeosDriver,
+    art: ArtCache,
 ) -> Result<Server, anyhow::Error> {
     let driver = Data::new(driver);
+    let art = Data::new(art);
     let server = HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
@@ -63,6 +77,7 @@ async fn run(
                     .route(web::get().to(details)),
             )
             .route("/music_sources", web::get().to(music_source::list))
+            .route("/policies", web::get().to(policies::list))
             .service(
                 web::resource("/zones/{zone_id}/edit_members")
                     .name("edit_members")
@@ -78,8 +93,11 @@ async fn run(
             //.route("/zones/{zone_id}/edit_members", web::get().to(edit_zone_members_form))
             .route("/statics/style.scss", web::get().to(main_css))
             .route("/health_check", web::get().to(health_check))
+            .route("/metrics", web::get().to(metrics))
+            .route("/art/{hash}", web::get().to(show_art))
             .app_data(base_url.clone())
             .app_data(driver.clone())
+            .app_data(art.clone())
     })
     .listen(listener)?
     .run();
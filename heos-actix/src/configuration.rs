@@ -1,14 +1,43 @@
+use heos_api::VolumePolicy;
+use std::path::PathBuf;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 #[derive(serde::Deserialize, Clone)]
 pub struct Settings {
     pub application: ApplicationSettings,
     pub heos: HeosSettings,
+    #[serde(default)]
+    pub art: ArtSettings,
 }
 
 #[derive(serde::Deserialize, Clone)]
 pub struct HeosSettings {
     pub host: String,
+    #[serde(default = "default_heos_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub volume_policies: Vec<VolumePolicy>,
+}
+
+fn default_heos_port() -> u16 {
+    1255
+}
+
+/// The album art cache.
+#[derive(serde::Deserialize, Clone)]
+pub struct ArtSettings {
+    pub directory: PathBuf,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_megabytes: u64,
+}
+
+impl Default for ArtSettings {
+    fn default() -> Self {
+        ArtSettings {
+            directory: "art-cache".into(),
+            max_megabytes: 100,
+        }
+    }
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -5,13 +5,13 @@ use heos_api::HeosDriver;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoneMember {
     pub id: PlayerId,
     pub name: String,
     pub volume: Level,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Zone {
     pub leader: HeosPlayer,
     pub members: Vec<ZoneMember>,
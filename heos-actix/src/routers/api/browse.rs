@@ -8,10 +8,11 @@ use actix_web::web::Path;
 use actix_web::{web, Either, Error, HttpMessage, HttpRequest, HttpResponse};
 use heos_api::error::HeosError;
 use heos_api::types::browse::{BroseSourceItem, MusicSource};
-use heos_api::types::{Range, SourceId};
+use heos_api::types::{ContainerId, Range, SourceId};
 use heos_api::HeosDriver;
 use maud::{html, Markup};
 use rust_hall::{HalResource, Link};
+use serde_derive::Deserialize;
 
 type RegisterResult = Either<HttpResponse, Result<&'static str, Error>>;
 
@@ -22,7 +23,7 @@ pub async fn list(req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpRespon
 
 pub async fn details(
     req: HttpRequest,
-    path: Path<i64>,
+    path: Path<SourceId>,
     driver: web::Data<HeosDriver>,
 ) -> Result<HttpResponse, InternalError<HeosError>> {
     let source_id: SourceId = path.into_inner();
@@ -34,16 +35,25 @@ pub async fn details(
     Ok(music_sources.to_response(&req))
 }
 
-// here it gets ugly!
+const PAGE_SIZE: u16 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct PageParams {
+    /// first item, starting with 0
+    start: Option<u16>,
+}
+
 pub async fn container(
     req: HttpRequest,
-    path: Path<(i64, String)>,
+    path: Path<(SourceId, ContainerId)>,
+    params: web::Query<PageParams>,
     driver: web::Data<HeosDriver>,
-) -> HttpResponse {
+) -> Result<HttpResponse, InternalError<HeosError>> {
     let (source_id, container_id) = path.into_inner();
-    let music_sources = driver
-        .browse_music_containers(&source_id, &container_id, &Range::default())
+    let range = Range::page(params.start.unwrap_or(0), PAGE_SIZE);
+    let page = driver
+        .browse_page(&source_id, &container_id, &range)
         .await
-        .unwrap();
-    BrowseContainerResource::new(source_id, container_id, music_sources).to_response(&req)
+        .map_err(|heos_err| InternalError::new(heos_err, StatusCode::INTERNAL_SERVER_ERROR))?;
+    Ok(BrowseContainerResource::new(source_id, container_id, page).to_response(&req))
 }
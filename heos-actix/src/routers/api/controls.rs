@@ -0,0 +1,146 @@
+use actix_web::error::InternalError;
+use actix_web::http::StatusCode;
+use actix_web::web::{Json, Path};
+use actix_web::{web, HttpResponse};
+use heos_api::error::HeosError;
+use heos_api::types::player::PlayState;
+use heos_api::types::{Level, OnOrOff, PlayMode, PlayerId, Repeat, Shuffle};
+use heos_api::HeosDriver;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::domain::zone::Zone;
+
+// The bodies of the controls. These are advertised as HAL-FORMS templates on the zones.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayStateForm {
+    pub state: PlayState,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VolumeForm {
+    pub level: Level,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MuteForm {
+    pub state: OnOrOff,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayModeForm {
+    pub repeat: Repeat,
+    pub shuffle: Shuffle,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MembersForm {
+    #[serde(default)]
+    pub members: Vec<PlayerId>,
+}
+
+type ControlResult = Result<HttpResponse, InternalError<HeosError>>;
+
+pub(crate) fn heos_error(err: HeosError) -> InternalError<HeosError> {
+    let status = match &err {
+        HeosError::InvalidCommand { eid, .. } => {
+            StatusCode::from_u16(eid.http_status()).unwrap_or(StatusCode::BAD_GATEWAY)
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    InternalError::new(err, status)
+}
+
+fn find_zone(driver: &HeosDriver, zone_id: PlayerId) -> Option<Zone> {
+    Zone::get_zones(driver)
+        .into_iter()
+        .find(|zone| zone.id() == zone_id)
+}
+
+pub async fn set_play_state(
+    path: Path<PlayerId>,
+    form: Json<PlayStateForm>,
+    driver: web::Data<HeosDriver>,
+) -> ControlResult {
+    let zone_id = path.into_inner();
+    if find_zone(&driver, zone_id).is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    driver
+        .set_play_state(zone_id, form.state)
+        .await
+        .map_err(heos_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn set_volume(
+    path: Path<PlayerId>,
+    form: Json<VolumeForm>,
+    driver: web::Data<HeosDriver>,
+) -> ControlResult {
+    let zone_id = path.into_inner();
+    match find_zone(&driver, zone_id) {
+        Some(zone) if zone.members.is_empty() => {
+            driver.set_volume(zone_id, form.level).await
+        }
+        Some(_) => driver.set_group_volume(zone_id.group(), form.level).await,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    }
+    .map_err(heos_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn set_mute(
+    path: Path<PlayerId>,
+    form: Json<MuteForm>,
+    driver: web::Data<HeosDriver>,
+) -> ControlResult {
+    let zone_id = path.into_inner();
+    match find_zone(&driver, zone_id) {
+        Some(zone) if zone.members.is_empty() => driver.set_mute(zone_id, form.state).await,
+        Some(_) => driver.set_group_mute(zone_id.group(), form.state).await,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    }
+    .map_err(heos_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn set_play_mode(
+    path: Path<PlayerId>,
+    form: Json<PlayModeForm>,
+    driver: web::Data<HeosDriver>,
+) -> ControlResult {
+    let zone_id = path.into_inner();
+    if find_zone(&driver, zone_id).is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let form = form.into_inner();
+    let mode = PlayMode {
+        repeat: form.repeat,
+        shuffle: form.shuffle,
+    };
+    driver
+        .set_play_mode(zone_id, mode)
+        .await
+        .map_err(heos_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn set_members(
+    path: Path<PlayerId>,
+    form: Json<MembersForm>,
+    driver: web::Data<HeosDriver>,
+) -> ControlResult {
+    let zone_id = path.into_inner();
+    if find_zone(&driver, zone_id).is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let members = form.into_inner().members;
+    if members.is_empty() {
+        driver.delete_group(zone_id).await
+    } else {
+        driver.create_group(zone_id, members).await
+    }
+    .map_err(heos_error)?;
+    Ok(HttpResponse::NoContent().finish())
+}
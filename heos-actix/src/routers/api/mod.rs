@@ -1,8 +1,11 @@
 use actix_web::{guard, web, Scope};
 
 mod browse;
+mod controls;
 mod zones;
 
+pub(crate) use zones::zone_resource;
+
 pub fn routes() -> Scope {
     web::scope("/api")
         .service(
@@ -18,6 +21,36 @@ pub fn routes() -> Scope {
                         .name("zone")
                         .guard(guard::Get())
                         .to(zones::details),
+                )
+                .service(
+                    web::resource("/{zone_id}/play_state")
+                        .name("zone_play_state")
+                        .route(web::put().to(controls::set_play_state))
+                        .route(web::post().to(controls::set_play_state)),
+                )
+                .service(
+                    web::resource("/{zone_id}/volume")
+                        .name("zone_volume")
+                        .route(web::put().to(controls::set_volume))
+                        .route(web::post().to(controls::set_volume)),
+                )
+                .service(
+                    web::resource("/{zone_id}/mute")
+                        .name("zone_mute")
+                        .route(web::put().to(controls::set_mute))
+                        .route(web::post().to(controls::set_mute)),
+                )
+                .service(
+                    web::resource("/{zone_id}/play_mode")
+                        .name("zone_play_mode")
+                        .route(web::put().to(controls::set_play_mode))
+                        .route(web::post().to(controls::set_play_mode)),
+                )
+                .service(
+                    web::resource("/{zone_id}/members")
+                        .name("zone_members")
+                        .route(web::put().to(controls::set_members))
+                        .route(web::post().to(controls::set_members)),
                 ),
         )
         .service(
@@ -1,35 +1,84 @@
 use actix_web::web::Path;
-use actix_web::{guard, web, HttpRequest, HttpResponse, Resource, Scope};
+use actix_web::{web, HttpRequest, HttpResponse};
+use heos_api::types::player::PlayState;
+use heos_api::types::{PlayMode, PlayerId, Repeat, Shuffle};
 use heos_api::HeosDriver;
-use rust_hall::{HalContext, HalResource, Link};
-use std::collections::BTreeMap;
-use std::fmt::{format, Display};
+use rust_hall::{HalForm, HalResource, Property};
 
 use crate::domain::zone::Zone;
-use serde_derive::{Deserialize, Serialize};
-use serde_json::{json, Error, Value};
 
-const NO_ARGS: Option<&str> = None;
+const JSON: &str = "application/json";
 
-pub struct HeosContext(HttpRequest);
-
-impl Into<HalResource> for Zone {
-    fn into(self) -> HalResource {
-        HalResource::with_self(format!("/zones{}", self.id())).add_object(self)
-    }
+fn control(req: &HttpRequest, name: &str, zone: &Zone, title: &str) -> HalForm {
+    let target = req.url_for(name, &[zone.id().to_string()]).unwrap();
+    HalForm::new("PUT")
+        .with_title(title)
+        .with_target(target)
+        .with_content_type(JSON)
 }
 
-fn get_zone_links(zone: &Zone, request: &HttpRequest) -> Vec<(String, Link)> {
-    let self_link = request.url_for("zone", &[zone.id().to_string()]).unwrap();
-    vec![("self".to_string(), self_link.into())]
+/// The zone with links and the controls as HAL-FORMS templates.
+pub fn zone_resource(zone: Zone, req: &HttpRequest) -> HalResource {
+    let self_link = req.url_for("zone", &[zone.id().to_string()]).unwrap();
+    let volume = zone.group_volume.unwrap_or(zone.leader.volume);
+    let mode = zone.leader.mode.clone().unwrap_or_else(PlayMode::default);
+    let players = zone
+        .members
+        .iter()
+        .map(|member| member.id)
+        .collect::<Vec<_>>();
+
+    let play_state = control(req, "zone_play_state", &zone, "Play, pause or stop")
+        .with_property(
+            Property::new("state")
+                .required()
+                .with_value(zone.leader.play_state)
+                .with_inline_options([PlayState::Play, PlayState::Pause, PlayState::Stop]),
+        );
+    let volume = control(req, "zone_volume", &zone, "Change the volume").with_property(
+        Property::new("level")
+            .required()
+            .with_type("range")
+            .with_range(0, 100)
+            .with_value(volume),
+    );
+    let mute = control(req, "zone_mute", &zone, "Mute").with_property(
+        Property::new("state")
+            .required()
+            .with_value(zone.leader.mute)
+            .with_inline_options(["on", "off"]),
+    );
+    let play_mode = control(req, "zone_play_mode", &zone, "Repeat and shuffle")
+        .with_property(
+            Property::new("repeat")
+                .required()
+                .with_value(&mode.repeat)
+                .with_inline_options([Repeat::Off, Repeat::OnOne, Repeat::OnAll]),
+        )
+        .with_property(
+            Property::new("shuffle")
+                .required()
+                .with_value(&mode.shuffle)
+                .with_inline_options([Shuffle::Off, Shuffle::On]),
+        );
+    let members = control(req, "zone_members", &zone, "Group players").with_property(
+        Property::new("members")
+            .with_prompt("pids of the members, empty to remove the group")
+            .with_value(players),
+    );
+
+    HalResource::with_self(self_link)
+        .with_template("default", play_state)
+        .with_template("volume", volume)
+        .with_template("mute", mute)
+        .with_template("play_mode", play_mode)
+        .with_template("members", members)
+        .add_object(zone)
 }
 
 pub async fn list(req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpResponse {
     let zones: Vec<Zone> = Zone::get_zones(&driver);
-    let zones = zones.into_iter().map(|zone| {
-        HalResource::with_self(req.url_for("zone", &[zone.id().to_string()]).unwrap())
-            .add_object(zone)
-    });
+    let zones = zones.into_iter().map(|zone| zone_resource(zone, &req));
 
     let zones_resource = HalResource::with_self(req.url_for_static("zones").unwrap());
     HttpResponse::Ok().json(zones_resource.with_resources("zones", zones))
@@ -37,16 +86,13 @@ pub async fn list(req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpRespon
 
 pub async fn details(
     req: HttpRequest,
-    path: Path<i64>,
+    path: Path<PlayerId>,
     driver: web::Data<HeosDriver>,
 ) -> HttpResponse {
     let player_id = path.into_inner();
     let zones = Zone::get_zones(&driver);
     if let Some(zone) = zones.into_iter().find(|p| p.id() == player_id) {
-        HttpResponse::Ok().json(
-            HalResource::with_self(req.url_for("zone", &[zone.id().to_string()]).unwrap())
-                .add_object(zone),
-        )
+        HttpResponse::Ok().json(zone_resource(zone, &req))
     } else {
         HttpResponse::NotFound().finish()
     }
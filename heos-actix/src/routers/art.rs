@@ -0,0 +1,41 @@
+use actix_web::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use actix_web::{web, HttpRequest, HttpResponse};
+use heos_axum::art::{self, ArtCache};
+use serde_derive::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ArtParams {
+    size: Option<u32>,
+}
+
+/// The album art behind `/art/{hash}`, see [`art::art_url`].
+pub async fn show_art(
+    req: HttpRequest,
+    hash: web::Path<String>,
+    params: web::Query<ArtParams>,
+    cache: web::Data<ArtCache>,
+) -> HttpResponse {
+    let etag = art::etag(&art::art_name(&hash, params.size));
+    if req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes())
+    {
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, etag))
+            .insert_header((CACHE_CONTROL, art::CACHE_CONTROL))
+            .finish();
+    }
+    match cache.get(&hash, params.size).await {
+        Ok(Some(art)) => HttpResponse::Ok()
+            .content_type(art.content_type)
+            .insert_header((ETAG, art.etag))
+            .insert_header((CACHE_CONTROL, art::CACHE_CONTROL))
+            .body(art.bytes),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => {
+            tracing::warn!("Failed to get art {}: {:#}", hash, err);
+            HttpResponse::BadGateway().finish()
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use actix_web::HttpResponse;
+
+/// The metrics of heos-api for Prometheus.
+pub async fn metrics() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(heos_api::metrics::CONTENT_TYPE)
+        .body(heos_api::metrics::gather())
+}
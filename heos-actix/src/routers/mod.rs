@@ -1,12 +1,17 @@
+mod art;
 mod health_check;
 mod home;
+mod metrics;
 mod style;
 pub(crate) mod zone;
 
+pub use art::*;
 pub use health_check::*;
 pub use home::*;
+pub use metrics::*;
 pub use style::*;
 
 pub(crate) mod music_source;
+pub(crate) mod policies;
 
 pub mod api;
@@ -0,0 +1,12 @@
+use crate::views;
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpRequest, HttpResponse};
+use heos_api::HeosDriver;
+
+pub async fn list(_req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpResponse {
+    let policies = driver.volume_policies();
+    let html = views::policies::policies_page(policies.iter(), chrono::Local::now().time());
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(html.into_string())
+}
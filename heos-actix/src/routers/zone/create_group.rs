@@ -19,7 +19,7 @@ impl ZoneEditForm {
         for (pid, on_or_off) in &self.member_ids {
             info!("Pid: `{}` :: `{}`", &pid, &on_or_off);
             if on_or_off == "on" {
-                if let Ok(pid) = pid.parse::<i64>() {
+                if let Ok(pid) = pid.parse::<PlayerId>() {
                     pids.push(pid);
                 }
             }
@@ -29,7 +29,7 @@ impl ZoneEditForm {
 }
 
 pub async fn new(
-    path: web::Path<i64>,
+    path: web::Path<PlayerId>,
     params: web::Form<ZoneEditForm>,
     driver: web::Data<HeosDriver>,
 ) -> HttpResponse {
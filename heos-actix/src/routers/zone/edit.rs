@@ -12,7 +12,7 @@ pub struct ZoneMemberModel {
     pub selected: bool,
 }
 pub async fn edit_zone_members_form(
-    path: Path<i64>,
+    path: Path<PlayerId>,
     driver: web::Data<HeosDriver>,
 ) -> HttpResponse {
     let zone_id = path.into_inner();
@@ -51,7 +51,7 @@ pub async fn edit_zone_members_form(
                     @for member in members {
                         div style="margin-left: 2em;" {
                             label for="{{member.id}}" { (member.name) }
-                            input type="checkbox" id=(member.id) name=(member.id) checked?[member.selected] {}
+                            input type="checkbox" id=(member.id.to_string()) name=(member.id.to_string()) checked?[member.selected] {}
                         }
                     }
                 }
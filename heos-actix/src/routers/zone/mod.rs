@@ -3,30 +3,26 @@ mod create_group;
 mod edit;
 
 use crate::domain::zone::Zone;
-use crate::views::zone::{zone_detail_page, zone_page};
+use crate::views::zone::{zone_detail_page, ZonesResource};
+use crate::views::ToHttpResponse;
 use actix_web::http::header::ContentType;
 use actix_web::web::Path;
 use actix_web::{web, HttpRequest, HttpResponse};
 pub use create_group::*;
 pub use edit::*;
-use heos_api::types::Range;
+use heos_api::types::{PlayerId, Range};
 use heos_api::HeosDriver;
 
-pub async fn list(_req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpResponse {
+pub async fn list(req: HttpRequest, driver: web::Data<HeosDriver>) -> HttpResponse {
     // let edit_link = req.url_for(
     //     "edit_members", [])// format!("/zones{}/edit", zone.id());
     let zones = Zone::get_zones(&driver);
-    let sources = driver.music_sources();
-
-    let html = zone_page(zones, sources);
-    HttpResponse::Ok()
-        .content_type(ContentType::html())
-        .body(html.into_string())
+    ZonesResource::new(zones).to_response(&req)
 }
 
 pub async fn details(
     _req: HttpRequest,
-    path: Path<i64>,
+    path: Path<PlayerId>,
     driver: web::Data<HeosDriver>,
 ) -> HttpResponse {
     let player_id = path.into_inner();
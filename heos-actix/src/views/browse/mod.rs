@@ -1,7 +1,8 @@
 use crate::views::ToHttpResponse;
 use actix_web::{HttpRequest, HttpResponse};
 use heos_api::types::browse::{BroseSourceItem, BrowsableMedia, HeosService, MusicSource};
-use heos_api::types::{ContainerId, MediaId, SourceId};
+use heos_api::types::{ContainerId, Page, Range, SourceId};
+use heos_axum::art::art_url;
 use maud::{html, Markup};
 use rust_hall::HalResource;
 
@@ -27,7 +28,7 @@ pub fn render_browsable_media(media: &BrowsableMedia, parent_id: SourceId) -> Ma
     let url = media_url(&media, &parent_id);
     html!({
         li {
-            img src=( media.image_url) height="64px" {}
+            img src=(art_url(&media.image_url, 128)) height="64px" {}
             a href=(url) {
                 ( media.name )
             }
@@ -47,7 +48,7 @@ fn source_li(source: &MusicSource, req: &HttpRequest) -> Markup {
     let url = req.url_for("browse", &[source.sid.to_string()]).unwrap();
     html!({
         li {
-            img src=(source.image_url) height="64px"  {}
+            img src=(art_url(&source.image_url, 128)) height="64px"  {}
             a href = (url.to_string()) { ( source.name ) }
         }
     })
@@ -74,7 +75,7 @@ impl ToHttpResponse for BrowseMusicSourcesResource {
             .body(body.into_string())
     }
 
-    fn to_json(&self, req: &HttpRequest) -> HttpResponse {
+    fn to_hal(&self, req: &HttpRequest) -> HttpResponse {
         let mut resource = HalResource::with_self(req.url_for_static("music_sources").unwrap());
         let response = self.0.iter().cloned().fold(resource, |hal, music_source| {
             let embedded = HalResource::with_self(
@@ -86,6 +87,10 @@ impl ToHttpResponse for BrowseMusicSourcesResource {
         });
         HttpResponse::Ok().json(response)
     }
+
+    fn to_json(&self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(&self.0)
+    }
 }
 
 pub struct MusicSourceContentsResource(SourceId, Vec<BroseSourceItem>);
@@ -130,7 +135,7 @@ impl ToHttpResponse for MusicSourceContentsResource {
             .body(body.into_string())
     }
 
-    fn to_json(&self, req: &HttpRequest) -> HttpResponse {
+    fn to_hal(&self, req: &HttpRequest) -> HttpResponse {
         let mut resource =
             HalResource::with_self(req.url_for("music_sources", [self.0.to_string()]).unwrap());
         let parent_id = self.0.clone();
@@ -141,51 +146,88 @@ impl ToHttpResponse for MusicSourceContentsResource {
         });
         HttpResponse::Ok().json(response)
     }
+
+    fn to_json(&self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(&self.1)
+    }
 }
 
 pub struct BrowseContainerResource {
     pub source_id: SourceId,
     pub container_id: ContainerId,
-    pub media: Vec<BrowsableMedia>,
+    pub page: Page<BrowsableMedia>,
 }
 
 impl BrowseContainerResource {
     pub fn new(
         source_id: SourceId,
         container_id: ContainerId,
-        media: Vec<BrowsableMedia>,
+        page: Page<BrowsableMedia>,
     ) -> BrowseContainerResource {
         Self {
             source_id,
             container_id,
-            media,
+            page,
         }
     }
+
+    fn page_url(&self, req: &HttpRequest, range: Range) -> String {
+        let url = req
+            .url_for(
+                "browse_container",
+                [self.source_id.to_string(), self.container_id.to_string()],
+            )
+            .unwrap();
+        format!("{}?start={}", url, range.start)
+    }
 }
 
 impl ToHttpResponse for BrowseContainerResource {
     fn to_html(&self, req: &HttpRequest) -> HttpResponse {
         let body = html!({
             ul {
-                @for media in &self.media {
+                @for media in &self.page.items {
                     li {
                         ( media.name )
                     }
                 }
             }
+            nav class="pagination" {
+                @if let Some(previous) = self.page.previous() {
+                    a href=(self.page_url(req, previous)) { ( "prev" ) }
+                }
+                @if !self.page.items.is_empty() {
+                    span class="pagination__position" {
+                        ( format!("{}-{} of {}",
+                            self.page.range.start as usize + 1,
+                            self.page.range.start as usize + self.page.items.len(),
+                            self.page.total) )
+                    }
+                }
+                @if let Some(next) = self.page.next() {
+                    a href=(self.page_url(req, next)) { ( "next" ) }
+                }
+            }
         });
         HttpResponse::Ok()
             .content_type(mime::TEXT_HTML_UTF_8)
             .body(body.into_string())
     }
 
-    fn to_json(&self, req: &HttpRequest) -> HttpResponse {
+    fn to_hal(&self, req: &HttpRequest) -> HttpResponse {
         let mut resource = HalResource::with_self(
             req.url_for("music_sources", [self.source_id.to_string()])
                 .unwrap(),
         );
+        if let Some(previous) = self.page.previous() {
+            resource = resource.add_link("prev", self.page_url(req, previous));
+        }
+        if let Some(next) = self.page.next() {
+            resource = resource.add_link("next", self.page_url(req, next));
+        }
         let response = self
-            .media
+            .page
+            .items
             .iter()
             .cloned()
             .fold(resource, |hal, music_source| {
@@ -194,4 +236,15 @@ impl ToHttpResponse for BrowseContainerResource {
             });
         HttpResponse::Ok().json(response)
     }
+
+    fn to_json(&self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(serde_json::json!({
+            "sid": self.source_id,
+            "cid": self.container_id,
+            "start": self.page.range.start,
+            "end": self.page.range.end,
+            "count": self.page.total,
+            "items": self.page.items,
+        }))
+    }
 }
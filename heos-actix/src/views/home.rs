@@ -2,6 +2,7 @@ use crate::domain::zone::Zone;
 use crate::views::page;
 use crate::views::zone::zone_list_item;
 use heos_api::types::browse::MusicSource;
+use heos_axum::art::art_url;
 use maud::{html, Markup};
 
 pub fn home(zones: Vec<Zone>, music_sources: Vec<MusicSource>) -> Markup {
@@ -18,7 +19,7 @@ pub fn home(zones: Vec<Zone>, music_sources: Vec<MusicSource>) -> Markup {
                 @for source in music_sources {
                     div {
                         (source.name)
-                        img src=(source.image_url) width="128px" {}
+                        img src=(art_url(&source.image_url, 128)) width="128px" {}
                     }
                 }
             }
@@ -1,10 +1,13 @@
+use actix_web::http::header;
 use actix_web::{HttpRequest, HttpResponse};
 use maud::{html, Markup, DOCTYPE};
+use rust_hall::negotiate::{negotiate, Representation, HAL_JSON};
 
 pub mod zone;
 
 pub mod browse;
 pub mod home;
+pub mod policies;
 pub mod sources;
 
 /// A basic header with a dynamic `page_title`.
@@ -25,6 +28,20 @@ fn header(page_title: &str) -> Markup {
 ///
 /// Additionally takes a `greeting_box` that's `Markup`, not `&str`.
 pub fn page(title: &str, name: String, contents: Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html {
+            // Add the header markup to the page
+            (header(title))
+            body {
+                (main(name, contents))
+            }
+        }
+    }
+}
+
+/// The tabs and the contents without `head`, what htmx swaps into `#main`.
+pub fn main(name: String, contents: Markup) -> Markup {
     let tabs = vec![
         ("Zones".to_string(), "/zones".to_string(), name == "Zones"),
         (
@@ -32,20 +49,18 @@ pub fn page(title: &str, name: String, contents: Markup) -> Markup {
             "/music_sources".to_string(),
             name == "Music Sources",
         ),
+        (
+            "Lautstärke".to_string(),
+            "/policies".to_string(),
+            name == "Volume Limits",
+        ),
     ];
     html! {
-        (DOCTYPE)
-        html {
-            // Add the header markup to the page
-            (header(title))
-            body {
-                div id="main" style="margin: 1em;"
-                hx-swap="outerHtml" {
-                    (render_tabs(tabs))
-                    div class="tab-content" {
-                        (contents)
-                    }
-                }
+        div id="main" style="margin: 1em;"
+        hx-swap="outerHtml" {
+            (render_tabs(tabs))
+            div class="tab-content" {
+                (contents)
             }
         }
     }
@@ -66,13 +81,45 @@ pub fn render_tabs(tabs: Vec<(String, String, bool)>) -> Markup {
     }
 }
 
+/// A resource that can be rendered as HAL, as plain JSON, as page or as htmx
+/// fragment.
+///
+/// `to_response` picks the representation from the `Accept` and `HX-Request`
+/// headers and answers with 406 if the client accepts none of them.
 pub trait ToHttpResponse {
     fn to_response(&self, request: &HttpRequest) -> HttpResponse {
-        match request.head().headers().get("ACCEPT") {
-            Some(header) if header == "application/json" => self.to_json(&request),
-            _ => self.to_html(&request),
-        }
+        let headers = request.head().headers();
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok());
+        let htmx = headers.contains_key("HX-Request");
+        let mut response = match negotiate(accept, htmx, &Representation::ALL) {
+            Some(Representation::HalJson) => {
+                let mut response = self.to_hal(request);
+                response.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static(HAL_JSON),
+                );
+                response
+            }
+            Some(Representation::Json) => self.to_json(request),
+            Some(Representation::Html) => self.to_html(request),
+            Some(Representation::HtmxFragment) => self.to_fragment(request),
+            None => HttpResponse::NotAcceptable().finish(),
+        };
+        response.headers_mut().insert(
+            header::VARY,
+            header::HeaderValue::from_static("Accept, HX-Request"),
+        );
+        response
     }
     fn to_html(&self, req: &HttpRequest) -> HttpResponse;
+    /// The resource with its links and embedded resources.
+    fn to_hal(&self, req: &HttpRequest) -> HttpResponse;
+    /// The bare state of the resource, without links.
     fn to_json(&self, req: &HttpRequest) -> HttpResponse;
+    /// The part of the page htmx swaps in, the whole page by default.
+    fn to_fragment(&self, req: &HttpRequest) -> HttpResponse {
+        self.to_html(req)
+    }
 }
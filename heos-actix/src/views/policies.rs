@@ -0,0 +1,40 @@
+use chrono::NaiveTime;
+use heos_api::VolumePolicy;
+use maud::{html, Markup};
+
+use crate::views::page;
+
+pub fn policies_page<'a, P: Iterator<Item = &'a VolumePolicy>>(
+    policies: P,
+    now: NaiveTime,
+) -> Markup {
+    page(
+        "H E O S - Volume Limits",
+        "Volume Limits".to_string(),
+        html! {
+            div class="volume-policies" {
+                @for policy in policies {
+                    div class="volume-policies__policy" {
+                        h3 { (policy.player) }
+                        @if let Some(max) = policy.max_volume {
+                            p { "max: " (max) }
+                        }
+                        @if let Some(min) = policy.min_volume {
+                            p { "min: " (min) }
+                        }
+                        @for quiet_hours in &policy.quiet_hours {
+                            p class="volume-policies__quiet-hours" {
+                                (quiet_hours.from.format("%H:%M").to_string()) " - "
+                                (quiet_hours.until.format("%H:%M").to_string()) ": "
+                                (quiet_hours.max_volume)
+                            }
+                        }
+                        @if let Some(max) = policy.max_volume_at(now) {
+                            p { "right now: " (max) }
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
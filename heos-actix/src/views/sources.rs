@@ -1,4 +1,5 @@
 use heos_api::types::browse::MusicSource;
+use heos_axum::art::art_url;
 use maud::{html, Markup};
 
 use crate::views::page;
@@ -12,7 +13,7 @@ pub fn sources_page(music_sources: Vec<MusicSource>) -> Markup {
                 @for source in music_sources {
                     div {
                         (source.name)
-                        img src=(source.image_url) width="128px" {}
+                        img src=(art_url(&source.image_url, 128)) width="128px" {}
                     }
                 }
             }
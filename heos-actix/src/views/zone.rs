@@ -1,27 +1,63 @@
 use crate::domain::zone::Zone;
-use crate::views::page;
+use crate::routers::api::zone_resource;
+use crate::views::{main, page, ToHttpResponse};
+use actix_web::http::header::ContentType;
+use actix_web::{HttpRequest, HttpResponse};
 use heos_api::types::browse::MusicSource;
 use heos_api::types::player::{NowPlayingMedia, QueueEntry};
+use heos_axum::art::art_url;
 use maud::{html, Markup};
+use rust_hall::HalResource;
 
-pub fn zone_page(zones: Vec<Zone>, _music_sources: Vec<MusicSource>) -> Markup {
-    page(
-        "H E O S - Zones",
-        "Zones".to_string(),
-        html! {
-            div class="zones" id="zones" {
-                @for zone in &zones {
-                    (zone_list_item(&zone))
-                }
+fn zone_list(zones: &[Zone]) -> Markup {
+    html! {
+        div class="zones" id="zones" {
+            @for zone in zones {
+                (zone_list_item(zone))
             }
-        },
-    )
+        }
+    }
+}
+
+/// The zones page, also served as HAL and as htmx fragment.
+pub struct ZonesResource(Vec<Zone>);
+
+impl ZonesResource {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        Self(zones)
+    }
+}
+
+impl ToHttpResponse for ZonesResource {
+    fn to_html(&self, _req: &HttpRequest) -> HttpResponse {
+        let html = page("H E O S - Zones", "Zones".to_string(), zone_list(&self.0));
+        HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(html.into_string())
+    }
+
+    fn to_hal(&self, req: &HttpRequest) -> HttpResponse {
+        let zones = self.0.iter().map(|zone| zone_resource(zone.clone(), req));
+        let resource = HalResource::with_self(req.url_for_static("zones").unwrap());
+        HttpResponse::Ok().json(resource.with_resources("zones", zones))
+    }
+
+    fn to_json(&self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(&self.0)
+    }
+
+    fn to_fragment(&self, _req: &HttpRequest) -> HttpResponse {
+        let html = main("Zones".to_string(), zone_list(&self.0));
+        HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(html.into_string())
+    }
 }
 
 pub fn zone_now_playing(now_playing: &NowPlayingMedia) -> Markup {
     html! {
         div class="zone-list-item__now-playing__icon" {
-            img src=(now_playing.image_url) height="64px" width="64px";
+            img src=(art_url(&now_playing.image_url, 128)) height="64px" width="64px";
         }
         p class="zone-list-item__now-playing__song" {(now_playing.song) }
         p class="zone-list-item__now-playing__album" { (now_playing.album) }
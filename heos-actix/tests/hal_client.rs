@@ -0,0 +1,68 @@
+//! Controls a simulated device through `/api`, following nothing but links and templates.
+
+use serde_json::{json, Value};
+
+use heos_api::simulator::SimulatedDevice;
+use heos_api::types::PlayerId;
+use heosd::application::Application;
+use heosd::configuration::{ApplicationSettings, ArtSettings, HeosSettings, Settings};
+use rust_hall::{HalClient, HalResource, Variables};
+
+async fn start_app(device: &SimulatedDevice) -> String {
+    let settings = Settings {
+        application: ApplicationSettings {
+            port: 0,
+            host: "127.0.0.1".to_string(),
+            base_url: "http://127.0.0.1".to_string(),
+        },
+        heos: HeosSettings {
+            host: device.addr().ip().to_string(),
+            port: device.addr().port(),
+            volume_policies: vec![],
+        },
+        art: ArtSettings {
+            directory: std::env::temp_dir().join("heosd-hal-client-art"),
+            max_megabytes: 1,
+        },
+    };
+    let app = Application::build(settings).await.unwrap();
+    let url = format!("http://127.0.0.1:{}/api/zones", app.port());
+    actix_web::rt::spawn(app.run_until_stopped());
+    url
+}
+
+fn zone<'a>(zones: &'a HalResource, name: &str) -> &'a HalResource {
+    zones
+        .embedded_resources("zones")
+        .iter()
+        .find(|zone| zone.state::<Value>().unwrap()["leader"]["name"] == name)
+        .expect("no such zone")
+}
+
+#[actix_web::test]
+async fn test_controls_a_zone_through_its_templates() {
+    let device = SimulatedDevice::start().await.unwrap();
+    let client = HalClient::new();
+    let zones = client.get(&start_app(&device).await).await.unwrap();
+
+    let kitchen = client
+        .follow(zone(&zones, "Kitchen"), "self", &Variables::new())
+        .await
+        .unwrap();
+    let volume = kitchen.template("volume").unwrap();
+    assert_eq!(volume.properties[0].value, Some(json!(20)));
+    let mute = kitchen.template("mute").unwrap();
+    assert_eq!(mute.properties[0].value, Some(json!("off")));
+
+    client
+        .submit(&kitchen, "volume", &json!({ "level": 35 }))
+        .await
+        .unwrap();
+    client
+        .submit(&kitchen, "mute", &json!({ "state": "on" }))
+        .await
+        .unwrap();
+    let player = &device.state().players[&PlayerId(2)];
+    assert_eq!(player.volume, 35);
+    assert!(player.mute);
+}
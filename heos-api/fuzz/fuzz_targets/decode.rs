@@ -0,0 +1,22 @@
+#![no_main]
+
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::Decoder;
+
+use heos_api::HeosCodec;
+
+// Whatever arrives, in chunks of any size, the decoder must neither panic nor
+// hand out frames longer than the maximum.
+fuzz_target!(|input: (u8, &[u8])| {
+    let (chunk, data) = input;
+    let mut codec = HeosCodec::with_max_length(256);
+    let mut buffer = BytesMut::new();
+    for chunk in data.chunks(usize::from(chunk) + 1) {
+        buffer.extend_from_slice(chunk);
+        while let Some(line) = codec.decode(&mut buffer).unwrap() {
+            assert!(line.text.len() <= codec.max_length());
+        }
+    }
+    let _ = codec.decode_eof(&mut buffer);
+});
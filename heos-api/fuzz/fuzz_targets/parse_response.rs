@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use heos_api::types::browse::BrowseMusicContainerResponse;
+use heos_api::types::event::HeosEvent;
+use heos_api::types::player::{NowPlayingMedia, PlayerInfo, PlayerVolume};
+use heos_api::Frame;
+
+// Any json is a frame or an error, and so is any response for the parsers.
+fuzz_target!(|data: &[u8]| {
+    let json = match serde_json::from_slice(data) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    match Frame::from_json(json) {
+        Ok(Frame::Response(response)) => {
+            let _ = Vec::<PlayerInfo>::try_from(response.clone());
+            let _ = Option::<NowPlayingMedia>::try_from(response.clone());
+            let _ = PlayerVolume::try_from(response.clone());
+            let _ = BrowseMusicContainerResponse::try_from(response);
+        }
+        Ok(Frame::Event(event)) => {
+            let _ = HeosEvent::try_from(event);
+        }
+        _ => {}
+    }
+});
@@ -1,89 +1,168 @@
 use std::fmt::Display;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 
 use tokio::net::ToSocketAddrs;
 use tokio::sync::{mpsc, oneshot};
-use tracing::{error, info};
+use tokio_stream::Stream;
+use tracing::{error, warn, Instrument};
 
 use parsers::*;
+use queue::CommandQueue;
 
-use crate::connection::{CommandResponse, Connection};
-use crate::types::browse::{BroseSourceItem, BrowseMusicContainerResponse, MusicSource};
+use crate::connection::{Command, CommandResponse, Connection};
+use crate::record::Recorder;
+use crate::types::browse::{
+    BroseSourceItem, BrowsableMedia, BrowseMusicContainerResponse, MusicSource, SearchCriteria,
+    SearchResponse,
+};
 use crate::types::event::HeosEvent;
-use crate::types::group::{GroupInfo, GroupVolume};
+use crate::types::group::{GroupInfo, GroupMute, GroupVolume};
 use crate::types::player::{
     NowPlayingMedia, PlayState, PlayerInfo, PlayerMute, PlayerPlayMode, PlayerPlayState,
     PlayerVolume, QueueEntry,
 };
 use crate::types::system::AccountState;
 use crate::types::{
-    ContainerId, GroupId, Level, OnOrOff, PlayMode, PlayerId, Range, SourceId, Success,
+    ContainerId, GroupId, Level, OnOrOff, Page, PlayMode, PlayerId, Range, SearchCriteriaId,
+    SourceId, Success,
 };
 use crate::{HeosError, HeosResult};
 
 mod parsers;
+mod queue;
+mod retry;
+
+pub use retry::RetryPolicy;
+
+// HEOS returns at most 100 items per request, some services fewer.
+pub(crate) const BROWSE_PAGE_SIZE: u16 = 50;
 
 struct ApiCommand(String, oneshot::Sender<HeosResult<CommandResponse>>);
 impl ApiCommand {
     pub fn new(command: String, responder: oneshot::Sender<HeosResult<CommandResponse>>) -> Self {
         ApiCommand(command, responder)
     }
-    async fn execute(self, connection: &mut Connection) {
-        let command_response = connection.execute_command(&self.0).await; // TODO!
-        info!("received response: {:?}", &command_response);
-        let _ = self.1.send(command_response);
-    }
 }
 
 // using a channel to ensure only one command is executed at once
 // Additionally this gives us &mut functions and cheap clone-ability!
 #[derive(Clone, Debug)]
-pub struct HeosApi(mpsc::Sender<ApiCommand>, SocketAddr);
+pub struct HeosApi(
+    mpsc::Sender<ApiCommand>,
+    SocketAddr,
+    Option<Recorder>,
+    Arc<Mutex<RetryPolicy>>,
+);
 
 impl HeosApi {
     pub async fn connect<T: ToSocketAddrs>(addr: T) -> HeosResult<Self> {
         let connection = Connection::connect(addr).await?;
-        Ok(HeosApi::new(connection))
+        Ok(HeosApi::new(connection, None))
     }
 
-    fn new(mut connection: Connection) -> Self {
+    /// Like [`HeosApi::connect`], writing the traffic of all connections to the recorder.
+    pub async fn record<T: ToSocketAddrs>(addr: T, recorder: Recorder) -> HeosResult<Self> {
+        let mut connection = Connection::connect(addr).await?;
+        connection.record(&recorder);
+        Ok(HeosApi::new(connection, Some(recorder)))
+    }
+
+    fn new(mut connection: Connection, recorder: Option<Recorder>) -> Self {
         let (s, mut r) = mpsc::channel::<ApiCommand>(32);
         let peer_addr = connection.ip_addr().clone();
         // this is the only thread that executes the commands by talking to the heos device.
         tokio::spawn(async move {
-            while let Some(command) = r.recv().await {
-                let _ = command.execute(&mut connection).await;
+            let mut queue = CommandQueue::default();
+            loop {
+                if queue.is_empty() {
+                    match r.recv().await {
+                        Some(command) => queue.push(command),
+                        None => break,
+                    }
+                }
+                // everything that arrived while the last command was executed
+                while let Ok(command) = r.try_recv() {
+                    queue.push(command);
+                }
+                if let Some(pending) = queue.pop() {
+                    pending.execute(&mut connection).await;
+                }
             }
         });
-        Self(s, peer_addr)
+        Self(
+            s,
+            peer_addr,
+            recorder,
+            Arc::new(Mutex::new(RetryPolicy::default())),
+        )
     }
+
+    /// Replaces the retry policy of this api and all its clones.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.3.lock().unwrap() = policy;
+    }
+
     async fn execute_command<A, B>(&self, command: A) -> HeosResult<B>
     where
         A: Display + Send,
         B: TryFrom<CommandResponse, Error = HeosError>,
     {
         let command = format!("{}", command);
+        let policy = self.3.lock().unwrap().clone();
+        let span = tracing::debug_span!(
+            "heos_command",
+            command = %command,
+            attempts = tracing::field::Empty
+        );
+        async move {
+            let mut attempt = 1;
+            loop {
+                tracing::Span::current().record("attempts", attempt);
+                match self.send_command(command.clone()).await {
+                    Err(err) if policy.should_retry(&command, &err, attempt) => {
+                        let delay = policy.delay(attempt);
+                        warn!(
+                            "attempt {} failed, retrying in {:?}: {}",
+                            attempt, delay, err
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    response => {
+                        let response = response?;
+                        tracing::debug!("Got Response: {}", &response);
+                        return response.try_into();
+                    }
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn send_command(&self, command: String) -> HeosResult<CommandResponse> {
         tracing::debug!("executing command: {}", &command);
+        #[cfg(feature = "metrics")]
+        let timer = crate::metrics::CommandTimer::start(&command);
         let (s, r) = oneshot::channel();
         let _ = self.0.send(ApiCommand::new(command, s)).await;
-        let response = r.await.expect("Failed to receive response")?;
-        tracing::debug!("Got Response: {}", &response);
-        response.try_into()
+        let response = r.await.expect("Failed to receive response");
+        #[cfg(feature = "metrics")]
+        timer.finish(&response);
+        response
     }
 
     pub async fn login(&self, un: String, pw: String) -> HeosResult<AccountState> {
-        let res: AccountState = self
-            .execute_command(format!("system/sign_in?un={}&pw={}", un, pw))
-            .await?;
-        Ok(res)
+        self.execute_command(Command::SignIn { un, pw }).await
     }
 
     pub async fn get_player_infos(&self) -> HeosResult<Vec<PlayerInfo>> {
-        self.execute_command("player/get_players").await
+        self.execute_command(Command::GetPlayers {}).await
     }
 
     pub async fn get_play_state(&self, player_id: &PlayerId) -> HeosResult<PlayerPlayState> {
-        self.execute_command(format!("player/get_play_state?pid={pid}", pid = player_id))
+        self.execute_command(Command::GetPlayState { pid: *player_id })
             .await
     }
     pub async fn set_play_state(
@@ -91,52 +170,63 @@ impl HeosApi {
         player_id: PlayerId,
         play_state: PlayState,
     ) -> HeosResult<PlayerPlayState> {
-        self.execute_command(format!(
-            "player/set_play_state?pid={pid}&state={state}",
-            pid = player_id,
-            state = play_state
-        ))
+        self.execute_command(Command::SetPlayState {
+            pid: player_id,
+            state: play_state,
+        })
         .await
     }
 
+    pub async fn play_next(&self, player_id: PlayerId) -> HeosResult<()> {
+        let _: Success = self
+            .execute_command(Command::PlayNext { pid: player_id })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn play_previous(&self, player_id: PlayerId) -> HeosResult<()> {
+        let _: Success = self
+            .execute_command(Command::PlayPrevious { pid: player_id })
+            .await?;
+        Ok(())
+    }
+
     // todo this may return nothing.
     pub async fn get_now_playing_media(
         &self,
         player_id: &PlayerId,
     ) -> HeosResult<Option<NowPlayingMedia>> {
-        self.execute_command(format!("player/get_now_playing_media?pid={}", player_id))
+        self.execute_command(Command::GetNowPlayingMedia { pid: *player_id })
             .await
     }
     pub async fn get_music_sources(&self) -> HeosResult<Vec<MusicSource>> {
-        self.execute_command("browse/get_music_sources").await
+        self.execute_command(Command::GetMusicSources {}).await
     }
     pub async fn get_volume(&self, player_id: &PlayerId) -> HeosResult<PlayerVolume> {
-        self.execute_command(format!("player/get_volume?pid={}", player_id))
+        self.execute_command(Command::GetVolume { pid: *player_id })
             .await
     }
     pub async fn set_volume(&self, player_id: PlayerId, level: Level) -> HeosResult<PlayerVolume> {
-        self.execute_command(format!(
-            "player/set_volume?pid={pid}&level={level}",
-            pid = player_id,
-            level = level
-        ))
+        self.execute_command(Command::SetVolume {
+            pid: player_id,
+            level,
+        })
         .await
     }
 
     pub async fn get_mute(&self, player_id: PlayerId) -> HeosResult<PlayerMute> {
-        self.execute_command(format!("player/get_mute?pid={pid}", pid = player_id))
+        self.execute_command(Command::GetMute { pid: player_id })
             .await
     }
     pub async fn set_mute(&self, player_id: PlayerId, state: OnOrOff) -> HeosResult<PlayerMute> {
-        self.execute_command(format!(
-            "player/set_mute?pid={pid}&state={state}",
-            pid = player_id,
-            state = state
-        ))
+        self.execute_command(Command::SetMute {
+            pid: player_id,
+            state,
+        })
         .await
     }
     pub async fn get_play_mode(&self, player_id: &PlayerId) -> HeosResult<PlayerPlayMode> {
-        self.execute_command(format!("player/get_play_mode?pid={pid}", pid = player_id))
+        self.execute_command(Command::GetPlayMode { pid: *player_id })
             .await
     }
 
@@ -145,12 +235,11 @@ impl HeosApi {
         player_id: &PlayerId,
         mode: PlayMode,
     ) -> HeosResult<PlayerPlayMode> {
-        self.execute_command(format!(
-            "player/set_play_mode?pid={pid}&repeat={repeat}&shuffle={shuffle}",
-            pid = player_id,
-            repeat = mode.repeat,
-            shuffle = mode.shuffle
-        ))
+        self.execute_command(Command::SetPlayMode {
+            pid: *player_id,
+            repeat: mode.repeat,
+            shuffle: mode.shuffle,
+        })
         .await
     }
     pub async fn get_queue(
@@ -158,33 +247,25 @@ impl HeosApi {
         player_id: PlayerId,
         range: Range,
     ) -> HeosResult<Vec<QueueEntry>> {
-        self.execute_command(format!(
-            "player/get_queue?pid={pid}&range={start},{end}",
-            pid = player_id,
-            start = range.start,
-            end = range.end
-        ))
+        self.execute_command(Command::GetQueue {
+            pid: player_id,
+            range,
+        })
         .await
     }
 
     pub async fn get_groups(&self) -> HeosResult<Vec<GroupInfo>> {
-        self.execute_command("group/get_groups").await
+        self.execute_command(Command::GetGroups {}).await
     }
     pub async fn set_group(&self, players: Vec<PlayerId>) -> HeosResult<()> {
-        let pids = players
-            .into_iter()
-            .map(|pid| pid.to_string())
-            .collect::<Vec<String>>()
-            .join(",");
-
         let _: Success = self
-            .execute_command(format!("group/set_group?pid={pids}", pids = pids))
+            .execute_command(Command::SetGroup { pid: players })
             .await?;
         Ok(())
     }
 
     pub async fn get_group_volume(&self, group_id: GroupId) -> HeosResult<GroupVolume> {
-        self.execute_command(format!("group/get_volume?gid={}", group_id))
+        self.execute_command(Command::GetGroupVolume { gid: group_id })
             .await
     }
     pub async fn set_group_volume(
@@ -192,19 +273,28 @@ impl HeosApi {
         group_id: GroupId,
         level: Level,
     ) -> HeosResult<GroupVolume> {
-        self.execute_command(format!(
-            "player/set_volume?pid={pid}&level={level}",
-            pid = group_id,
-            level = level
-        ))
+        self.execute_command(Command::SetGroupVolume {
+            gid: group_id,
+            level,
+        })
+        .await
+    }
+
+    pub async fn set_group_mute(&self, group_id: GroupId, state: OnOrOff) -> HeosResult<GroupMute> {
+        self.execute_command(Command::SetGroupMute {
+            gid: group_id,
+            state,
+        })
         .await
     }
 
     pub async fn browse_music_sources(&self, sid: SourceId) -> HeosResult<Vec<BroseSourceItem>> {
-        let music_sources = self
-            .execute_command(format!("browse/browse?sid={}", sid))
-            .await?;
-        Ok(music_sources)
+        self.execute_command(Command::Browse {
+            sid,
+            cid: None,
+            range: None,
+        })
+        .await
     }
 
     pub async fn browse_music_containers(
@@ -213,38 +303,100 @@ impl HeosApi {
         cid: &ContainerId,
         range: &Range,
     ) -> HeosResult<BrowseMusicContainerResponse> {
-        let music_sources = self
-            .execute_command(format!(
-                "browse/browse?sid={}&cid={}&range={},{}",
-                sid, cid, range.start, range.end
-            ))
-            .await?;
-        Ok(music_sources)
+        self.execute_command(Command::Browse {
+            sid: *sid,
+            cid: Some(cid.clone()),
+            range: Some(range.clone()),
+        })
+        .await
+    }
+
+    /// One page of a container, see [`Page`] for the next one.
+    pub async fn browse_page(
+        &self,
+        sid: &SourceId,
+        cid: &ContainerId,
+        range: &Range,
+    ) -> HeosResult<Page<BrowsableMedia>> {
+        Ok(self.browse_music_containers(sid, cid, range).await?.into())
+    }
+
+    /// All items of a container. The pages are fetched as the stream is read,
+    /// an error ends it.
+    pub fn browse_stream(
+        &self,
+        sid: SourceId,
+        cid: ContainerId,
+    ) -> impl Stream<Item = HeosResult<BrowsableMedia>> {
+        let api = self.clone();
+        async_stream::try_stream! {
+            let mut range = Range::page(0, BROWSE_PAGE_SIZE);
+            loop {
+                let page = api.browse_page(&sid, &cid, &range).await?;
+                let next = page.next();
+                for item in page.items {
+                    yield item;
+                }
+                match next {
+                    Some(next) => range = next,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    pub async fn get_search_criteria(&self, sid: SourceId) -> HeosResult<Vec<SearchCriteria>> {
+        self.execute_command(Command::GetSearchCriteria { sid })
+            .await
+    }
+
+    pub async fn search(
+        &self,
+        sid: SourceId,
+        scid: SearchCriteriaId,
+        search: &str,
+        range: &Range,
+    ) -> HeosResult<SearchResponse> {
+        self.execute_command(Command::Search {
+            sid,
+            search: search.to_string(),
+            scid,
+            range: range.clone(),
+        })
+        .await
     }
 
     pub async fn events(&self) -> HeosResult<mpsc::Receiver<HeosEvent>> {
         let mut connection = Connection::connect(self.1).await?;
+        if let Some(recorder) = &self.2 {
+            connection.record(recorder);
+        }
         let _ = connection
-            .execute_command("system/register_for_change_events?enable=on")
+            .execute_command(Command::RegisterForChangeEvents {
+                enable: OnOrOff::On,
+            })
             .await?;
         let (s, r) = mpsc::channel(64);
         // TODO whenever I do have the time make this so that it only create one connection! ;)
         tokio::spawn(async move {
             loop {
-                let event = connection
-                    .read_event()
-                    .await
-                    .and_then(|event| response_to_event(event));
-                match event {
+                let event = match connection.read_event().await {
+                    Ok(event) => event,
+                    Err(e) => {
+                        error!("failed to fetch event. {:?}", e);
+                        break;
+                    }
+                };
+                match response_to_event(event) {
                     Ok(event) => {
+                        #[cfg(feature = "metrics")]
+                        crate::metrics::event(&event);
                         if let Err(_) = s.send(event).await {
                             break;
                         }
                     }
-                    Err(e) => {
-                        error!("failed to fetch event. {:?}", e);
-                        break;
-                    }
+                    // one odd event doesn't end the others
+                    Err(e) => warn!("skipping event. {:?}", e),
                 }
             }
         });
@@ -3,12 +3,15 @@ use qs;
 use serde_json::Value;
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
+use tracing::debug;
 
 use crate::connection::{CommandResponse, EventResponse};
 use crate::error::HeosError;
 use crate::types::browse::*;
 use crate::types::event::*;
-use crate::types::group::{CreateGroupResponse, DeleteGroupResponse, GroupInfo, GroupVolume};
+use crate::types::group::{
+    CreateGroupResponse, DeleteGroupResponse, GroupInfo, GroupMute, GroupVolume,
+};
 use crate::types::player::*;
 use crate::types::system::*;
 use crate::types::*;
@@ -21,6 +24,7 @@ jason_parser!(Vec<GroupInfo>);
 jason_parser!(Vec<BrowsableMedia>);
 jason_parser!(Vec<BroseSourceItem>);
 jason_parser!(Vec<QueueEntry>);
+jason_parser!(Vec<SearchCriteria>);
 json_option_parser!(NowPlayingMedia);
 
 qs_parser!(PlayerPlayState);
@@ -28,6 +32,7 @@ qs_parser!(PlayerVolume);
 qs_parser!(PlayerMute);
 qs_parser!(PlayerPlayMode);
 qs_parser!(GroupVolume);
+qs_parser!(GroupMute);
 qs_parser!(CreateGroupResponse);
 qs_parser!(DeleteGroupResponse);
 
@@ -88,7 +93,7 @@ impl TryFrom<CommandResponse> for BrowseMusicContainerResponse {
         let items = serde_json::from_value(value.payload)
             .with_context(|| format!("failed to parse response: {}", &value.message))?;
         Ok(BrowseMusicContainerResponse {
-            sid: 0,
+            sid: SourceId(0),
             cid: params.cid,
             range: params.range,
             count: params.count,
@@ -97,16 +102,72 @@ impl TryFrom<CommandResponse> for BrowseMusicContainerResponse {
         })
     }
 }
+#[derive(Deserialize, Serialize)]
+struct SearchParameters {
+    pub sid: SourceId,
+    pub search: String,
+    pub scid: SearchCriteriaId,
+    pub count: usize,
+    pub returned: usize,
+}
+
+impl TryFrom<CommandResponse> for SearchResponse {
+    type Error = HeosError;
+
+    fn try_from(value: CommandResponse) -> Result<Self, Self::Error> {
+        let params: SearchParameters = qs::from_str(&value.message)
+            .with_context(|| format!("failed to parse response: {}", &value.message))?;
+        // no results come without payload
+        let items = match value.payload {
+            Value::Null => vec![],
+            payload => serde_json::from_value(payload)
+                .with_context(|| format!("failed to parse response: {}", &value.message))?,
+        };
+        Ok(SearchResponse {
+            sid: params.sid,
+            search: params.search,
+            scid: params.scid,
+            count: params.count,
+            returned: params.returned,
+            items,
+        })
+    }
+}
+
 // event parsing!
 pub fn response_to_event(response: EventResponse) -> crate::HeosResult<HeosEvent> {
-    let json = qs_to_json(&response.event_name, &response.message)?;
-    let event: HeosEvent = serde_json::from_value(json).with_context(|| {
-        format!(
-            "failed to handle event `{}`, qs: `{}`",
-            &response.event_name, &response.message
-        )
-    })?;
-    Ok(event)
+    let event = qs_to_json(&response.event_name, &response.message).and_then(|json| {
+        serde_json::from_value(json)
+            .with_context(|| {
+                format!(
+                    "failed to handle event `{}`, qs: `{}`",
+                    &response.event_name, &response.message
+                )
+            })
+            .map_err(HeosError::from)
+    });
+    match event {
+        Ok(event) => Ok(event),
+        // newer firmware, passed on for whoever knows it
+        Err(err) => {
+            debug!("{:?}", err);
+            let params = qs::from_str(&response.message).with_context(|| {
+                format!("failed to parse event message: `{}`", &response.message)
+            })?;
+            Ok(HeosEvent::Unknown {
+                name: response.event_name,
+                params,
+            })
+        }
+    }
+}
+
+impl TryFrom<EventResponse> for HeosEvent {
+    type Error = HeosError;
+
+    fn try_from(value: EventResponse) -> Result<Self, Self::Error> {
+        response_to_event(value)
+    }
 }
 
 // this is used to collect all possible paameters in heos strange query string format
@@ -154,6 +215,26 @@ mod test {
         let _play_mode: PlayerPlayMode = response.try_into().unwrap();
     }
 
+    #[test]
+    pub fn test_search_response() {
+        let response = CommandResponse {
+            command_name: "browse/search".to_string(),
+            message: "sid=10&search=Fleetwood Mac&scid=1&returned=1&count=1".to_string(),
+            payload: json!([{
+                "container": "yes",
+                "playable": "yes",
+                "type": "artist",
+                "name": "Fleetwood Mac",
+                "image_url": "",
+                "cid": "artist-1"
+            }]),
+            options: Default::default(),
+        };
+        let search: SearchResponse = response.try_into().unwrap();
+        assert_eq!(search.search, "Fleetwood Mac");
+        assert_eq!(search.items.len(), 1);
+    }
+
     #[test]
     pub fn test_various_browse_responses() {
         let heos_json_response = json!(
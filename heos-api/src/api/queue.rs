@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use tokio::sync::oneshot;
+use tracing::{debug, info};
+
+use super::ApiCommand;
+use crate::connection::{CommandResponse, Connection};
+use crate::{HeosError, HeosResult};
+
+// only the latest of these counts, per player or group.
+const COALESCED: &[&str] = &[
+    "player/set_volume",
+    "player/set_mute",
+    "player/set_play_mode",
+    "group/set_volume",
+    "group/set_mute",
+];
+
+// sent before everything else that is waiting, a user pressed a button.
+const TRANSPORT: &[&str] = &[
+    "player/set_play_state",
+    "player/play_next",
+    "player/play_previous",
+    "player/play_queue",
+];
+
+type Responder = oneshot::Sender<HeosResult<CommandResponse>>;
+
+/// A command waiting to be sent, with everyone waiting for its response.
+pub(super) struct Pending {
+    pub command: String,
+    responders: Vec<Responder>,
+}
+
+impl Pending {
+    pub async fn execute(self, connection: &mut Connection) {
+        let command_response = connection.execute_command(&self.command).await;
+        info!("received response: {:?}", &command_response);
+        self.respond(command_response);
+    }
+
+    fn respond(mut self, response: HeosResult<CommandResponse>) {
+        let last = self.responders.pop();
+        for responder in self.responders {
+            let _ = responder.send(duplicate(&response));
+        }
+        if let Some(last) = last {
+            let _ = last.send(response);
+        }
+    }
+}
+
+/// The commands sent to the worker while it was busy talking to HEOS.
+///
+/// Commands of a kind where only the latest counts, e.g. volume changes while
+/// dragging a slider, replace the one still waiting for the same player or
+/// group. Its callers then get the response of the latest. Transport commands
+/// go first.
+#[derive(Default)]
+pub(super) struct CommandQueue {
+    pending: VecDeque<Pending>,
+}
+
+impl CommandQueue {
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn push(&mut self, command: ApiCommand) {
+        let ApiCommand(command, responder) = command;
+        if let Some(key) = coalescing_key(&command) {
+            let waiting = self
+                .pending
+                .iter_mut()
+                .find(|pending| coalescing_key(&pending.command) == Some(key));
+            if let Some(waiting) = waiting {
+                debug!("{} replaces {}", command, waiting.command);
+                waiting.command = command;
+                waiting.responders.push(responder);
+                return;
+            }
+        }
+        self.pending.push_back(Pending {
+            command,
+            responders: vec![responder],
+        });
+    }
+
+    pub fn pop(&mut self) -> Option<Pending> {
+        let transport = self
+            .pending
+            .iter()
+            .position(|pending| TRANSPORT.contains(&name(&pending.command)));
+        match transport {
+            Some(index) => self.pending.remove(index),
+            None => self.pending.pop_front(),
+        }
+    }
+}
+
+fn name(command: &str) -> &str {
+    command.split_once('?').map_or(command, |(name, _)| name)
+}
+
+// the command and its player or group
+fn coalescing_key(command: &str) -> Option<(&str, &str)> {
+    let (name, query) = command.split_once('?')?;
+    if !COALESCED.contains(&name) {
+        return None;
+    }
+    let target = query
+        .split('&')
+        .find(|param| param.starts_with("pid=") || param.starts_with("gid="))?;
+    Some((name, target))
+}
+
+// HeosError is not Clone.
+fn duplicate(response: &HeosResult<CommandResponse>) -> HeosResult<CommandResponse> {
+    match response {
+        Ok(response) => Ok(response.clone()),
+        Err(HeosError::InvalidCommand { command, eid, text }) => Err(HeosError::InvalidCommand {
+            command: command.clone(),
+            eid: *eid,
+            text: text.clone(),
+        }),
+        Err(err) => Err(HeosError::InternalError(anyhow!("{}", err))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Receiver = oneshot::Receiver<HeosResult<CommandResponse>>;
+
+    fn push(queue: &mut CommandQueue, command: &str) -> Receiver {
+        let (s, r) = oneshot::channel();
+        queue.push(ApiCommand::new(command.to_string(), s));
+        r
+    }
+
+    #[test]
+    pub fn test_latest_volume_wins_and_transport_goes_first() {
+        let mut queue = CommandQueue::default();
+        let mut first = push(&mut queue, "player/set_volume?pid=1&level=10");
+        push(&mut queue, "player/set_volume?pid=2&level=10");
+        push(&mut queue, "player/get_players");
+        let mut last = push(&mut queue, "player/set_volume?pid=1&level=30");
+        push(&mut queue, "player/play_next?pid=1");
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop())
+            .map(|pending| {
+                let command = pending.command.clone();
+                if command.ends_with("level=30") {
+                    assert_eq!(pending.responders.len(), 2);
+                    pending.respond(Ok(CommandResponse {
+                        command_name: "player/set_volume".to_string(),
+                        message: "pid=1&level=30".to_string(),
+                        payload: Default::default(),
+                        options: Default::default(),
+                    }));
+                }
+                command
+            })
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "player/play_next?pid=1",
+                "player/set_volume?pid=1&level=30",
+                "player/set_volume?pid=2&level=10",
+                "player/get_players",
+            ]
+        );
+        assert_eq!(first.try_recv().unwrap().unwrap().message, "pid=1&level=30");
+        assert_eq!(last.try_recv().unwrap().unwrap().message, "pid=1&level=30");
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::types::HeosErrorCode;
+use crate::HeosError;
+
+/// When [`crate::HeosApi`] sends a command again that HEOS refused.
+///
+/// Only the errors HEOS answers while it is busy are retried, and only for the
+/// `get_*` commands, which are safe to repeat. Other commands are sent once,
+/// unless they are added with [`RetryPolicy::retrying`].
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// attempts including the first one, 1 never retries
+    pub max_attempts: u32,
+    /// the first delay, doubling with every attempt
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub retryable: Vec<HeosErrorCode>,
+    /// commands retried although they are not `get_*`, see [`RetryPolicy::retrying`]
+    pub also_retried: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+            retryable: vec![
+                HeosErrorCode::ProcessingPreviousCommand,
+                HeosErrorCode::ResourceCurrentlyNotAvailable,
+            ],
+            also_retried: vec![],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn never() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Retries a command that is not idempotent, e.g. `browse/add_to_queue`.
+    pub fn retrying(mut self, command: &str) -> Self {
+        self.also_retried.push(command.to_string());
+        self
+    }
+
+    /// Whether `command`, failed with `err` in its `attempt`, is sent again.
+    pub fn should_retry(&self, command: &str, err: &HeosError, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.is_retryable(err) && self.is_idempotent(command)
+    }
+
+    pub fn is_retryable(&self, err: &HeosError) -> bool {
+        match err {
+            HeosError::InvalidCommand { eid, .. } => self.retryable.contains(eid),
+            _ => false,
+        }
+    }
+
+    pub fn is_idempotent(&self, command: &str) -> bool {
+        let name = command.split_once('?').map_or(command, |(name, _)| name);
+        let name = name.trim_start_matches("heos://");
+        let command = name.rsplit('/').next().unwrap_or_default();
+        command.starts_with("get_") || self.also_retried.iter().any(|retried| retried == name)
+    }
+
+    /// The delay before the next attempt: random, up to the exponential backoff,
+    /// so that clients refused together don't come back together.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let millis = backoff.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn refused(eid: HeosErrorCode) -> HeosError {
+        HeosError::InvalidCommand {
+            command: "player/get_players".to_string(),
+            eid,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    pub fn test_only_busy_get_commands_are_retried() {
+        let policy = RetryPolicy::default();
+        let busy = refused(HeosErrorCode::ProcessingPreviousCommand);
+        assert!(policy.should_retry("player/get_players", &busy, 1));
+        assert!(policy.should_retry("player/get_volume?pid=1", &busy, 3));
+        assert!(!policy.should_retry("player/get_volume?pid=1", &busy, 4));
+        assert!(!policy.should_retry("player/play_next?pid=1", &busy, 1));
+        assert!(!policy.should_retry("browse/add_to_queue?pid=1&sid=2", &busy, 1));
+        let invalid = refused(HeosErrorCode::InvalidId);
+        assert!(!policy.should_retry("player/get_players", &invalid, 1));
+
+        let policy = RetryPolicy::default().retrying("browse/add_to_queue");
+        assert!(policy.should_retry("browse/add_to_queue?pid=1&sid=2", &busy, 1));
+        assert!(!RetryPolicy::never().should_retry("player/get_players", &busy, 1));
+    }
+
+    #[test]
+    pub fn test_delays_back_off() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            assert!(policy.delay(1) <= Duration::from_millis(100));
+            assert!(policy.delay(3) <= Duration::from_millis(400));
+            assert!(policy.delay(30) <= Duration::from_secs(2));
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::io::ErrorKind;
+use std::net::{IpAddr, UdpSocket};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use tracing::info;
+use url::Url;
+
+use crate::discover::HEOS_URN;
+use crate::error::HeosError;
+use crate::HeosResult;
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+
+/// The first heos device answering within 15 seconds.
+pub fn find_heos_devices() -> HeosResult<IpAddr> {
+    info!("Searching for heos devices");
+    search(Duration::from_secs(15), true)?
+        .into_iter()
+        .next()
+        .ok_or(HeosError::NoDeviceFound)
+}
+
+/// All heos devices answering within `timeout`, each only once.
+pub fn discover_heos_devices(timeout: Duration) -> HeosResult<Vec<IpAddr>> {
+    info!("Searching for all heos devices");
+    search(timeout, false)
+}
+
+fn search(timeout: Duration, first: bool) -> HeosResult<Vec<IpAddr>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").context("Failed to query for upnp devices")?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {}\r\n\r\n",
+        SSDP_ADDR, HEOS_URN
+    );
+    socket
+        .send_to(request.as_bytes(), SSDP_ADDR)
+        .context("Failed to query for upnp devices")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut devices = vec![];
+    let mut buffer = [0; 2048];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(devices);
+        }
+        socket
+            .set_read_timeout(Some(remaining))
+            .context("Failed to query for upnp devices")?;
+        let read = match socket.recv(&mut buffer) {
+            Ok(read) => read,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(devices)
+            }
+            Err(err) => {
+                return Err(anyhow!(err)
+                    .context("Failed to query for upnp devices")
+                    .into())
+            }
+        };
+        if let Some(ip) = heos_device_ip(&String::from_utf8_lossy(&buffer[..read]))? {
+            if !devices.contains(&ip) {
+                devices.push(ip);
+            }
+            if first {
+                return Ok(devices);
+            }
+        }
+    }
+}
+
+// the host of the LOCATION of a response for HEOS, answers of other devices are ignored.
+fn heos_device_ip(response: &str) -> HeosResult<Option<IpAddr>> {
+    let header = |name: &str| {
+        response.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    };
+    if header("ST") != Some(HEOS_URN) {
+        info!("Found something else");
+        return Ok(None);
+    }
+    info!("Found a heos device");
+    let location = header("LOCATION").ok_or(anyhow!("Response without location"))?;
+    let url = Url::parse(location).context("UPNP URL not parseable")?;
+    let host = url.host().ok_or(anyhow!("Url without host"))?;
+    let ip = IpAddr::from_str(&host.to_string()).with_context(|| "Failed to parse ip address")?;
+    Ok(Some(ip))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_heos_device_ip() {
+        let response = "HTTP/1.1 200 OK\r\n\
+            CACHE-CONTROL: max-age=180\r\n\
+            Location: http://192.168.178.35:60006/upnp/desc/aios_device/aios_device.xml\r\n\
+            ST: urn:schemas-denon-com:device:ACT-Denon:1\r\n\
+            USN: uuid:ec5aa7e4::urn:schemas-denon-com:device:ACT-Denon:1\r\n\r\n";
+        assert_eq!(
+            heos_device_ip(response).unwrap(),
+            Some(IpAddr::from([192, 168, 178, 35]))
+        );
+        let router = response.replace(
+            "urn:schemas-denon-com:device:ACT-Denon:1",
+            "upnp:rootdevice",
+        );
+        assert_eq!(heos_device_ip(&router).unwrap(), None);
+    }
+}
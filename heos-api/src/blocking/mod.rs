@@ -0,0 +1,423 @@
+//! A client for programs without an async runtime.
+//!
+//! [`HeosClient`] has the methods of [`crate::HeosApi`], each sending its
+//! command and waiting for the response on the calling thread:
+//!
+//! ```no_run
+//! use heos_api::blocking::HeosClient;
+//! use heos_api::types::PlayerId;
+//!
+//! let mut client = HeosClient::find().unwrap();
+//! client.set_volume(PlayerId(1), 20).unwrap();
+//! for event in client.events().unwrap() {
+//!     println!("{:?}", event);
+//! }
+//! ```
+
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::Context;
+use tracing::{debug, warn};
+
+use crate::api::BROWSE_PAGE_SIZE;
+use crate::connection::{BlockingConnection, Command, CommandResponse};
+use crate::record::Recorder;
+use crate::types::browse::{
+    BroseSourceItem, BrowsableMedia, BrowseMusicContainerResponse, MusicSource, SearchCriteria,
+    SearchResponse,
+};
+use crate::types::event::HeosEvent;
+use crate::types::group::{GroupInfo, GroupMute, GroupVolume};
+use crate::types::player::{
+    NowPlayingMedia, PlayState, PlayerInfo, PlayerMute, PlayerPlayMode, PlayerPlayState,
+    PlayerVolume, QueueEntry,
+};
+use crate::types::system::AccountState;
+use crate::types::{
+    ContainerId, GroupId, Level, OnOrOff, Page, PlayMode, PlayerId, Range, SearchCriteriaId,
+    SourceId, Success,
+};
+use crate::{HeosError, HeosResult, RetryPolicy};
+
+pub use discover::{discover_heos_devices, find_heos_devices};
+
+mod discover;
+
+const HEOS_PORT: u16 = 1255;
+
+/// One connection to a HEOS device, see the [module](self) docs.
+#[derive(Debug)]
+pub struct HeosClient {
+    connection: BlockingConnection,
+    addr: SocketAddr,
+    recorder: Option<Recorder>,
+    retry_policy: RetryPolicy,
+}
+
+impl HeosClient {
+    pub fn connect<T: ToSocketAddrs>(addr: T) -> HeosResult<Self> {
+        let connection = BlockingConnection::connect(addr)?;
+        Ok(HeosClient::new(connection, None))
+    }
+
+    /// Like [`HeosClient::connect`], writing the traffic of all connections to the recorder.
+    pub fn record<T: ToSocketAddrs>(addr: T, recorder: Recorder) -> HeosResult<Self> {
+        let mut connection = BlockingConnection::connect(addr)?;
+        connection.record(&recorder);
+        Ok(HeosClient::new(connection, Some(recorder)))
+    }
+
+    /// Connects to the first device found, see [`find_heos_devices`].
+    pub fn find() -> HeosResult<Self> {
+        let ip = find_heos_devices()?;
+        HeosClient::connect((ip, HEOS_PORT))
+    }
+
+    fn new(connection: BlockingConnection, recorder: Option<Recorder>) -> Self {
+        HeosClient {
+            addr: *connection.ip_addr(),
+            connection,
+            recorder,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn ip_addr(&self) -> &SocketAddr {
+        &self.addr
+    }
+
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Commands fail after waiting `timeout` for the response, `None` waits forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> HeosResult<()> {
+        self.connection.set_read_timeout(timeout)
+    }
+
+    fn execute_command<B>(&mut self, command: Command) -> HeosResult<B>
+    where
+        B: TryFrom<CommandResponse, Error = HeosError>,
+    {
+        let command = command.to_string();
+        let mut attempt = 1;
+        loop {
+            match self.send_command(&command) {
+                Err(err) if self.retry_policy.should_retry(&command, &err, attempt) => {
+                    let delay = self.retry_policy.delay(attempt);
+                    warn!(
+                        "attempt {} failed, retrying in {:?}: {}",
+                        attempt, delay, err
+                    );
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                response => {
+                    let response = response?;
+                    debug!("Got Response: {}", &response);
+                    return response.try_into();
+                }
+            }
+        }
+    }
+
+    fn send_command(&mut self, command: &str) -> HeosResult<CommandResponse> {
+        debug!("executing command: {}", command);
+        #[cfg(feature = "metrics")]
+        let timer = crate::metrics::CommandTimer::start(command);
+        let response = self.connection.execute_command(command);
+        #[cfg(feature = "metrics")]
+        timer.finish(&response);
+        response
+    }
+
+    pub fn login(&mut self, un: String, pw: String) -> HeosResult<AccountState> {
+        self.execute_command(Command::SignIn { un, pw })
+    }
+
+    pub fn get_player_infos(&mut self) -> HeosResult<Vec<PlayerInfo>> {
+        self.execute_command(Command::GetPlayers {})
+    }
+
+    pub fn get_play_state(&mut self, player_id: &PlayerId) -> HeosResult<PlayerPlayState> {
+        self.execute_command(Command::GetPlayState { pid: *player_id })
+    }
+
+    pub fn set_play_state(
+        &mut self,
+        player_id: PlayerId,
+        play_state: PlayState,
+    ) -> HeosResult<PlayerPlayState> {
+        self.execute_command(Command::SetPlayState {
+            pid: player_id,
+            state: play_state,
+        })
+    }
+
+    pub fn play_next(&mut self, player_id: PlayerId) -> HeosResult<()> {
+        let _: Success = self.execute_command(Command::PlayNext { pid: player_id })?;
+        Ok(())
+    }
+
+    pub fn play_previous(&mut self, player_id: PlayerId) -> HeosResult<()> {
+        let _: Success = self.execute_command(Command::PlayPrevious { pid: player_id })?;
+        Ok(())
+    }
+
+    pub fn get_now_playing_media(
+        &mut self,
+        player_id: &PlayerId,
+    ) -> HeosResult<Option<NowPlayingMedia>> {
+        self.execute_command(Command::GetNowPlayingMedia { pid: *player_id })
+    }
+
+    pub fn get_music_sources(&mut self) -> HeosResult<Vec<MusicSource>> {
+        self.execute_command(Command::GetMusicSources {})
+    }
+
+    pub fn get_volume(&mut self, player_id: &PlayerId) -> HeosResult<PlayerVolume> {
+        self.execute_command(Command::GetVolume { pid: *player_id })
+    }
+
+    pub fn set_volume(&mut self, player_id: PlayerId, level: Level) -> HeosResult<PlayerVolume> {
+        self.execute_command(Command::SetVolume {
+            pid: player_id,
+            level,
+        })
+    }
+
+    pub fn get_mute(&mut self, player_id: PlayerId) -> HeosResult<PlayerMute> {
+        self.execute_command(Command::GetMute { pid: player_id })
+    }
+
+    pub fn set_mute(&mut self, player_id: PlayerId, state: OnOrOff) -> HeosResult<PlayerMute> {
+        self.execute_command(Command::SetMute {
+            pid: player_id,
+            state,
+        })
+    }
+
+    pub fn get_play_mode(&mut self, player_id: &PlayerId) -> HeosResult<PlayerPlayMode> {
+        self.execute_command(Command::GetPlayMode { pid: *player_id })
+    }
+
+    pub fn set_play_mode(
+        &mut self,
+        player_id: &PlayerId,
+        mode: PlayMode,
+    ) -> HeosResult<PlayerPlayMode> {
+        self.execute_command(Command::SetPlayMode {
+            pid: *player_id,
+            repeat: mode.repeat,
+            shuffle: mode.shuffle,
+        })
+    }
+
+    pub fn get_queue(&mut self, player_id: PlayerId, range: Range) -> HeosResult<Vec<QueueEntry>> {
+        self.execute_command(Command::GetQueue {
+            pid: player_id,
+            range,
+        })
+    }
+
+    pub fn get_groups(&mut self) -> HeosResult<Vec<GroupInfo>> {
+        self.execute_command(Command::GetGroups {})
+    }
+
+    pub fn set_group(&mut self, players: Vec<PlayerId>) -> HeosResult<()> {
+        let _: Success = self.execute_command(Command::SetGroup { pid: players })?;
+        Ok(())
+    }
+
+    pub fn get_group_volume(&mut self, group_id: GroupId) -> HeosResult<GroupVolume> {
+        self.execute_command(Command::GetGroupVolume { gid: group_id })
+    }
+
+    pub fn set_group_volume(&mut self, group_id: GroupId, level: Level) -> HeosResult<GroupVolume> {
+        self.execute_command(Command::SetGroupVolume {
+            gid: group_id,
+            level,
+        })
+    }
+
+    pub fn set_group_mute(&mut self, group_id: GroupId, state: OnOrOff) -> HeosResult<GroupMute> {
+        self.execute_command(Command::SetGroupMute {
+            gid: group_id,
+            state,
+        })
+    }
+
+    pub fn browse_music_sources(&mut self, sid: SourceId) -> HeosResult<Vec<BroseSourceItem>> {
+        self.execute_command(Command::Browse {
+            sid,
+            cid: None,
+            range: None,
+        })
+    }
+
+    pub fn browse_music_containers(
+        &mut self,
+        sid: &SourceId,
+        cid: &ContainerId,
+        range: &Range,
+    ) -> HeosResult<BrowseMusicContainerResponse> {
+        self.execute_command(Command::Browse {
+            sid: *sid,
+            cid: Some(cid.clone()),
+            range: Some(range.clone()),
+        })
+    }
+
+    /// One page of a container, see [`Page`] for the next one.
+    pub fn browse_page(
+        &mut self,
+        sid: &SourceId,
+        cid: &ContainerId,
+        range: &Range,
+    ) -> HeosResult<Page<BrowsableMedia>> {
+        Ok(self.browse_music_containers(sid, cid, range)?.into())
+    }
+
+    /// All items of a container. The pages are fetched as the iterator is
+    /// read, an error ends it.
+    pub fn browse_iter(&mut self, sid: SourceId, cid: ContainerId) -> BrowseIter<'_> {
+        BrowseIter {
+            client: self,
+            sid,
+            cid,
+            next: Some(Range::page(0, BROWSE_PAGE_SIZE)),
+            items: Vec::new().into_iter(),
+        }
+    }
+
+    pub fn get_search_criteria(&mut self, sid: SourceId) -> HeosResult<Vec<SearchCriteria>> {
+        self.execute_command(Command::GetSearchCriteria { sid })
+    }
+
+    pub fn search(
+        &mut self,
+        sid: SourceId,
+        scid: SearchCriteriaId,
+        search: &str,
+        range: &Range,
+    ) -> HeosResult<SearchResponse> {
+        self.execute_command(Command::Search {
+            sid,
+            search: search.to_string(),
+            scid,
+            range: range.clone(),
+        })
+    }
+
+    /// The events of the device, on a connection of their own.
+    pub fn events(&self) -> HeosResult<Events> {
+        let mut connection = BlockingConnection::connect(self.addr)?;
+        if let Some(recorder) = &self.recorder {
+            connection.record(recorder);
+        }
+        connection
+            .execute_command(Command::RegisterForChangeEvents {
+                enable: OnOrOff::On,
+            })
+            .context("Failed to register for events")?;
+        Ok(Events {
+            connection: Some(connection),
+        })
+    }
+}
+
+/// The items of a container, see [`HeosClient::browse_iter`].
+pub struct BrowseIter<'a> {
+    client: &'a mut HeosClient,
+    sid: SourceId,
+    cid: ContainerId,
+    next: Option<Range>,
+    items: std::vec::IntoIter<BrowsableMedia>,
+}
+
+impl Iterator for BrowseIter<'_> {
+    type Item = HeosResult<BrowsableMedia>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(Ok(item));
+            }
+            let range = self.next.take()?;
+            match self.client.browse_page(&self.sid, &self.cid, &range) {
+                Ok(page) => {
+                    self.next = page.next();
+                    self.items = page.items.into_iter();
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// The events of a device, see [`HeosClient::events`].
+///
+/// Blocks until the next event. Events that can't be parsed are skipped, the
+/// iterator ends after the connection failed.
+#[derive(Debug)]
+pub struct Events {
+    connection: Option<BlockingConnection>,
+}
+
+impl Iterator for Events {
+    type Item = HeosResult<HeosEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = match self.connection.as_mut()?.read_event() {
+                Ok(event) => event,
+                Err(err) => {
+                    self.connection = None;
+                    return Some(Err(err));
+                }
+            };
+            match HeosEvent::try_from(event) {
+                Ok(event) => {
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::event(&event);
+                    return Some(Ok(event));
+                }
+                // one odd event doesn't end the others
+                Err(e) => warn!("skipping event. {:?}", e),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod test {
+    use crate::simulator::SimulatedDevice;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_client_against_simulated_device() {
+        let device = SimulatedDevice::start().await.unwrap();
+        let addr = device.addr();
+        let (players, volume, event) = tokio::task::spawn_blocking(move || {
+            let mut client = HeosClient::connect(addr).unwrap();
+            let mut events = client.events().unwrap();
+            let players = client.get_player_infos().unwrap();
+            let volume = client.set_volume(PlayerId(2), 35).unwrap();
+            (players, volume, events.next().unwrap().unwrap())
+        })
+        .await
+        .unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(volume.level, 35);
+        assert!(matches!(
+            event,
+            HeosEvent::PlayerVolumeChanged {
+                player_id: PlayerId(2),
+                level: 35,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::Context;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::protocol::{CommandResponse, EventResponse, Frame, Line, Output, Protocol};
+use crate::record::{ConnectionRecorder, Recorder};
+use crate::HeosResult;
+
+/// [`crate::Connection`] on a `std::net::TcpStream`, for programs without an
+/// async runtime.
+#[derive(Debug)]
+pub struct BlockingConnection {
+    stream: TcpStream,
+    protocol: Protocol,
+    peer_addr: SocketAddr,
+    recorder: Option<ConnectionRecorder>,
+}
+
+impl BlockingConnection {
+    pub fn connect<T: ToSocketAddrs>(addr: T) -> HeosResult<Self> {
+        let stream = TcpStream::connect(addr).context("Could not connect to device")?;
+        BlockingConnection::new(stream)
+    }
+
+    pub fn connect_timeout(addr: &SocketAddr, timeout: Duration) -> HeosResult<Self> {
+        let stream =
+            TcpStream::connect_timeout(addr, timeout).context("Could not connect to device")?;
+        BlockingConnection::new(stream)
+    }
+
+    fn new(stream: TcpStream) -> HeosResult<Self> {
+        let peer_addr = stream.peer_addr().context("Failed to ask remote address")?;
+        info!("connected to device :{:?}", &peer_addr);
+        #[cfg(feature = "metrics")]
+        crate::metrics::connected();
+        Ok(BlockingConnection {
+            stream,
+            protocol: Protocol::new(),
+            peer_addr,
+            recorder: None,
+        })
+    }
+
+    /// Skips frames longer than `max_length` bytes, instead of the default
+    /// [`crate::DEFAULT_MAX_FRAME_LENGTH`].
+    pub fn set_max_frame_length(&mut self, max_length: usize) {
+        self.protocol.set_max_frame_length(max_length);
+    }
+
+    /// Reads fail after waiting `timeout`, `None` waits forever.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> HeosResult<()> {
+        self.stream
+            .set_read_timeout(timeout)
+            .context("Failed to set the read timeout")?;
+        Ok(())
+    }
+
+    /// Writes all traffic of this connection to the recorder from now on.
+    pub fn record(&mut self, recorder: &Recorder) {
+        self.recorder = Some(recorder.connection());
+    }
+
+    pub fn ip_addr(&self) -> &SocketAddr {
+        &self.peer_addr
+    }
+
+    pub fn execute_command<D: Display>(&mut self, command: D) -> HeosResult<CommandResponse> {
+        self.send_command(command)?;
+        self.read_command_response()
+    }
+
+    /// Sends a command without waiting for the response, e.g. `player/get_players`.
+    pub fn send_command<D: Display>(&mut self, command: D) -> HeosResult<()> {
+        let command = command.to_string();
+        self.protocol.send_command(&command)?;
+        info!("Sending command: {}", &command);
+        if let Some(recorder) = &self.recorder {
+            recorder.sent(&command);
+        }
+        self.stream
+            .write_all(&self.protocol.take_outgoing())
+            .with_context(|| format!("Failed to send command '{}'  to device", &command))?;
+        Ok(())
+    }
+
+    pub fn read_event(&mut self) -> HeosResult<EventResponse> {
+        loop {
+            if let Output::Event(event) = self.read_output()? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Reads until the response to the oldest command sent, skipping events.
+    pub fn read_command_response(&mut self) -> HeosResult<CommandResponse> {
+        loop {
+            if let Output::Response(response) = self.read_output()? {
+                return response;
+            }
+        }
+    }
+
+    fn read_output(&mut self) -> HeosResult<Output> {
+        loop {
+            let frame = match self.read_frame() {
+                Ok(frame) => frame.context("Failed to read from Heos. Connection closed")?,
+                Err(err) => {
+                    // e.g. the read timed out, a late response must not be
+                    // taken for the one to the next command.
+                    self.protocol.forget_waiting();
+                    return Err(err);
+                }
+            };
+            if let Some(output) = self.protocol.handle_frame(frame) {
+                return Ok(output);
+            }
+        }
+    }
+
+    /// The next frame, `None` when the device closed the connection.
+    pub fn read_frame(&mut self) -> HeosResult<Option<Frame>> {
+        while let Some(json) = self.read_json()? {
+            match Frame::from_json(json) {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(err) => warn!("Skipping a line that is no HEOS response: {}", err),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next line as it is, `None` when the device closed the
+    /// connection.
+    pub fn read_json(&mut self) -> HeosResult<Option<Value>> {
+        let mut buffer = [0; 4096];
+        loop {
+            if let Some(line) = self.protocol.poll_line()? {
+                return Ok(Some(self.received(line)));
+            }
+            let read = self
+                .stream
+                .read(&mut buffer)
+                .context("Failed to read buffer")?;
+            if read == 0 {
+                let line = self.protocol.poll_line_eof()?;
+                return Ok(line.map(|line| self.received(line)));
+            }
+            self.protocol.receive(&buffer[..read]);
+        }
+    }
+
+    fn received(&self, line: Line) -> Value {
+        if let Some(recorder) = &self.recorder {
+            recorder.received(line.text.as_bytes());
+        }
+        line.json
+    }
+}
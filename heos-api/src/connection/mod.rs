@@ -1,59 +1,38 @@
-use std::fmt::{Display, Formatter};
-use std::io::Cursor;
+use std::fmt::Display;
 use std::net::SocketAddr;
 
-use anyhow::{anyhow, Context};
-use bytes::{Buf, BytesMut};
+use anyhow::Context;
+use bytes::BytesMut;
 use serde_json::Value;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio::net::{TcpStream, ToSocketAddrs};
-use tracing::{debug, info};
+use tracing::{info, warn};
 
-pub use frame::*;
+pub use crate::protocol::*;
 
-use crate::types::HeosErrorCode;
+use crate::record::{ConnectionRecorder, Recorder};
 use crate::HeosResult;
 
-// mod discover;
-mod frame;
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CommandResponse {
-    pub command_name: String,
-    pub message: String,
-    pub payload: Value, // can be Null
-    pub options: Value, // can be Null
-}
-
-impl Display for CommandResponse {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let str = serde_json::to_string_pretty(&self).unwrap();
-        write!(f, "{}", str)
-    }
-}
+#[cfg(feature = "blocking")]
+pub use blocking::BlockingConnection;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ErrorResponse {
-    pub command_name: String,
-    pub eid: HeosErrorCode,
-    pub text: String,
-}
-
-#[derive(Clone, Debug)]
-pub struct EventResponse {
-    pub event_name: String,
-    pub message: String,
-}
+#[cfg(feature = "blocking")]
+mod blocking;
+// mod discover;
 
 // copied pasted from https://docs.rs/crate/mini-redis/0.4.1/source/src/connection.rs
 #[derive(Debug)]
 pub struct Connection {
     stream: BufWriter<TcpStream>,
 
-    // The buffer for reading frames.
+    // The buffer for reading.
     buffer: BytesMut,
 
+    protocol: Protocol,
+
     peer_addr: SocketAddr,
+
+    recorder: Option<ConnectionRecorder>,
 }
 
 #[allow(dead_code)]
@@ -78,14 +57,29 @@ impl Connection {
             .context("Could not connect to device")?;
         let peer_addr = stream.peer_addr().context("Failed to ask remote address")?;
         info!("connected to device :{:?}", &peer_addr);
+        #[cfg(feature = "metrics")]
+        crate::metrics::connected();
         Ok(Connection {
             stream: BufWriter::new(stream),
             // Default to a 4KB read buffer.
             buffer: BytesMut::with_capacity(16 * 1024),
+            protocol: Protocol::new(),
             peer_addr,
+            recorder: None,
         })
     }
 
+    /// Skips frames longer than `max_length` bytes, instead of the default
+    /// [`DEFAULT_MAX_FRAME_LENGTH`].
+    pub fn set_max_frame_length(&mut self, max_length: usize) {
+        self.protocol.set_max_frame_length(max_length);
+    }
+
+    /// Writes all traffic of this connection to the recorder from now on.
+    pub fn record(&mut self, recorder: &Recorder) {
+        self.recorder = Some(recorder.connection());
+    }
+
     pub fn ip_addr(&self) -> &SocketAddr {
         &self.peer_addr
     }
@@ -99,11 +93,20 @@ impl Connection {
         let stream = TcpStream::connect(addr)
             .await
             .context("Failed to connect to device ")?;
+        #[cfg(feature = "metrics")]
+        crate::metrics::connected();
         Ok(Connection {
             stream: BufWriter::new(stream),
             // Default to a 4KB read buffer.
             buffer: BytesMut::with_capacity(16 * 1024),
+            protocol: Protocol::with_codec(HeosCodec::with_max_length(
+                self.protocol.codec().max_length(),
+            )),
             peer_addr: self.peer_addr.clone(),
+            recorder: self
+                .recorder
+                .as_ref()
+                .map(|recorder| recorder.recorder().connection()),
         })
     }
 
@@ -128,53 +131,71 @@ impl Connection {
         &mut self,
         command: D,
     ) -> crate::HeosResult<CommandResponse> {
+        self.send_command(command).await?;
+        self.read_command_response().await
+    }
+
+    /// Sends a command without waiting for the response, e.g. `player/get_players`.
+    pub async fn send_command<D: Display>(&mut self, command: D) -> crate::HeosResult<()> {
         let command = command.to_string();
-        let payload = format!("heos://{}\r\n", &command);
+        self.protocol.send_command(&command)?;
+        let payload = self.protocol.take_outgoing();
         info!("Sending command: {}", &command);
+        if let Some(recorder) = &self.recorder {
+            recorder.sent(&command);
+        }
         let _ = self
             .stream
-            .write_all(payload.as_bytes())
+            .write_all(&payload)
             .await
-            .context(format!("Failed to send command '{}'  to device", &payload))?;
+            .context(format!("Failed to send command '{}'  to device", &command))?;
         let _ = self
             .stream
             .flush()
             .await
-            .context(format!("Failed to send command '{}'  to device", &payload))?;
-        self.read_command_response().await
+            .context(format!("Failed to send command '{}'  to device", &command))?;
+        Ok(())
     }
 
     pub async fn read_event(&mut self) -> crate::HeosResult<EventResponse> {
         loop {
-            let response = self.read_frame().await?;
-            match response {
-                Some(Frame::Event(event)) => return Ok(event),
-                _ => { // nop
+            match self.read_output().await? {
+                Output::Event(event) => return Ok(event),
+                Output::Response(_) => { // nop
                 }
             }
         }
     }
+
+    /// Reads until the response to the oldest command sent, skipping events.
     pub async fn read_command_response(&mut self) -> crate::HeosResult<CommandResponse> {
         loop {
-            let response = self.read_frame().await?;
-            match response {
-                // this has to be upfront!?
-                Some(Frame::UnderProcess(command)) => {
-                    debug!(">> waiting for {} to finish.", &command);
-                }
-                Some(Frame::Response(command)) => return Ok(command),
-                Some(Frame::Error(error)) => return Err(error),
-                _ => { // nop
+            match self.read_output().await? {
+                Output::Response(response) => return response,
+                Output::Event(_) => { // nop
                 }
             }
         }
     }
 
+    async fn read_output(&mut self) -> crate::HeosResult<Output> {
+        loop {
+            let frame = self
+                .read_frame()
+                .await?
+                .context("Failed to read from Heos. Connection closed")?;
+            if let Some(output) = self.protocol.handle_frame(frame) {
+                return Ok(output);
+            }
+        }
+    }
+
     /// Read a single `Frame` value from the underlying stream.
     ///
     /// The function waits until it has retrieved enough data to parse a frame.
     /// Any data remaining in the read buffer after the frame has been parsed is
-    /// kept there for the next call to `read_frame`.
+    /// kept there for the next call to `read_frame`. Lines that are no HEOS
+    /// response are skipped.
     ///
     /// # Returns
     ///
@@ -182,95 +203,47 @@ impl Connection {
     /// is closed in a way that doesn't break a frame in half, it returns
     /// `None`. Otherwise, an error is returned.
     pub async fn read_frame(&mut self) -> crate::HeosResult<Option<Frame>> {
-        loop {
-            // Attempt to parse a frame from the buffered data. If enough data
-            // has been buffered, the frame is returned.
-            if let Some(frame) = self.parse_frame()? {
-                return Ok(Some(frame));
+        while let Some(json) = self.read_json().await? {
+            match Frame::from_json(json) {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(err) => warn!("Skipping a line that is no HEOS response: {}", err),
             }
+        }
+        Ok(None)
+    }
 
-            // There is not enough buffered data to read a frame. Attempt to
-            // read more data from the socket.
-            //
-            // On success, the number of bytes is returned. `0` indicates "end
-            // of stream".
-            if 0 == self
-                .stream
-                .read_buf(&mut self.buffer)
-                .await
-                .context("Failed to read buffer")?
-            {
-                // The remote closed the components.connection. For this to be a clean
-                // shutdown, there should be no data in the read buffer. If
-                // there is, this means that the peer closed the socket while
-                // sending a frame.
-                if self.buffer.is_empty() {
-                    return Ok(None);
-                } else {
-                    return Err(
-                        anyhow!("Failed to read from Heos. Connection reset by peer").into(),
-                    );
-                }
+    /// Reads the next line as it is, for debugging the protocol.
+    ///
+    /// Returns `None` when the device closed the connection.
+    pub async fn read_json(&mut self) -> crate::HeosResult<Option<Value>> {
+        loop {
+            if let Some(line) = self.protocol.poll_line()? {
+                return Ok(Some(self.received(line)));
+            }
+            if !self.read_more().await? {
+                let line = self.protocol.poll_line_eof()?;
+                return Ok(line.map(|line| self.received(line)));
             }
         }
     }
-    fn parse_frame(&mut self) -> crate::HeosResult<Option<Frame>> {
-        // Cursor is used to track the "current" location in the
-        // buffer. Cursor also implements `Buf` from the `bytes` crate
-        // which provides a number of helpful utilities for working
-        // with bytes.
-        let mut buf = Cursor::new(&self.buffer[..]);
-
-        // The first step is to check if enough data has been buffered to parse
-        // a single frame. This step is usually much faster than doing a full
-        // parse of the frame, and allows us to skip allocating data structures
-        // to hold the frame data unless we know the full frame has been
-        // received.
-        match Frame::check(&mut buf) {
-            Ok(_) => {
-                // The `check` function will have advanced the cursor until the
-                // end of the frame. Since the cursor had position set to zero
-                // before `Frame::check` was called, we obtain the length of the
-                // frame by checking the cursor position.
-                let len = buf.position() as usize;
-
-                // Reset the position to zero before passing the cursor to
-                // `Frame::parse`.
-                buf.set_position(0);
-
-                // Parse the frame from the buffer. This allocates the necessary
-                // structures to represent the frame and returns the frame
-                // value.
-                //
-                // If the encoded frame representation is invalid, an error is
-                // returned. This should terminate the **current** components.connection
-                // but should not impact any other connected api.
-                let frame = Frame::parse(&mut buf)?;
-
-                // Discard the parsed data from the read buffer.
-                //
-                // When `advance` is called on the read buffer, all of the data
-                // up to `len` is discarded. The details of how this works is
-                // left to `BytesMut`. This is often done by moving an internal
-                // cursor, but it may be done by reallocating and copying data.
-                self.buffer.advance(len);
 
-                // Return the parsed frame to the caller.
-                Ok(Some(frame))
-            }
-            // There is not enough data present in the read buffer to parse a
-            // single frame. We must wait for more data to be received from the
-            // socket. Reading from the socket will be done in the statement
-            // after this `match`.
-            //
-            // We do not want to return `Err` from here as this "error" is an
-            // expected runtime condition.
-            #[allow(non_snake_case)]
-            Err(_) => Ok(None),
-            // An error was encountered while parsing the frame. The components.connection
-            // is now in an invalid state. Returning `Err` from here will result
-            // in the components.connection being closed.
-            // Err(e) => Err(e.into()),
+    fn received(&self, line: Line) -> Value {
+        if let Some(recorder) = &self.recorder {
+            recorder.received(line.text.as_bytes());
         }
+        line.json
+    }
+
+    // false if the device closed the connection.
+    async fn read_more(&mut self) -> crate::HeosResult<bool> {
+        // On success, the number of bytes is returned. `0` indicates "end
+        // of stream".
+        let read = self
+            .stream
+            .read_buf(&mut self.buffer)
+            .await
+            .context("Failed to read buffer")?;
+        self.protocol.receive(&self.buffer.split());
+        Ok(read > 0)
     }
 }
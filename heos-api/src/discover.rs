@@ -9,36 +9,63 @@ use crate::error::HeosError;
 use crate::HeosResult;
 use url::{Url};
 
+pub(crate) const HEOS_URN: &str = "urn:schemas-denon-com:device:ACT-Denon:1";
+
 pub async fn find_heos_devices() -> HeosResult<IpAddr>{
     info!("Searching for heos devices");
-    let search_target = SearchTarget::from_str("urn:schemas-denon-com:device:ACT-Denon:1").unwrap();
+    let search_target = SearchTarget::from_str(HEOS_URN).unwrap();
     let mut responses = ssdp_client::search(&search_target, Duration::from_secs(15), 2)
         .await
         .context("Failed to query for upnp devices")?;
 
     while let Some(device) = responses.try_next().await
         .context("Failed to query for upnp devices")? {
-        // wow, so much parser nonsense!
-        match device.search_target() {
-            SearchTarget::URN(urn)
-                if urn.domain_name() == "schemas-denon-com" && urn.typ() == "ACT-Denon" => {
-                info!("Found a heos device");
-                let url = Url::parse(device.location())
-                    .context("UPNP URL not parseable")?;
-                let host = url.host().ok_or(anyhow!("Url without host"))?;
-                let ip =  IpAddr::from_str(&host.to_string())
-                    .with_context(||"Failed to parse ip address")?;
-                return Ok(ip)
-            }
-            _ => {
-                info!("Found something else");
-                continue
-            }
-        };
+        if let Some(ip) = heos_device_ip(&device)? {
+            return Ok(ip)
+        }
     }
     Err(HeosError::NoDeviceFound)
 }
 
+/// All heos devices answering within `timeout`, each only once.
+pub async fn discover_heos_devices(timeout: Duration) -> HeosResult<Vec<IpAddr>>{
+    info!("Searching for all heos devices");
+    let search_target = SearchTarget::from_str(HEOS_URN).unwrap();
+    let mut responses = ssdp_client::search(&search_target, timeout, 2)
+        .await
+        .context("Failed to query for upnp devices")?;
+    let mut devices = vec![];
+    while let Some(device) = responses.try_next().await
+        .context("Failed to query for upnp devices")? {
+        if let Some(ip) = heos_device_ip(&device)? {
+            if !devices.contains(&ip) {
+                devices.push(ip);
+            }
+        }
+    }
+    Ok(devices)
+}
+
+fn heos_device_ip(device: &ssdp_client::SearchResponse) -> HeosResult<Option<IpAddr>>{
+    // wow, so much parser nonsense!
+    match device.search_target() {
+        SearchTarget::URN(urn)
+            if urn.domain_name() == "schemas-denon-com" && urn.typ() == "ACT-Denon" => {
+            info!("Found a heos device");
+            let url = Url::parse(device.location())
+                .context("UPNP URL not parseable")?;
+            let host = url.host().ok_or(anyhow!("Url without host"))?;
+            let ip =  IpAddr::from_str(&host.to_string())
+                .with_context(||"Failed to parse ip address")?;
+            Ok(Some(ip))
+        }
+        _ => {
+            info!("Found something else");
+            Ok(None)
+        }
+    }
+}
+
 
 #[cfg(test)]
 mod test {
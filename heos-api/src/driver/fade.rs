@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::time::{sleep, sleep_until, Instant};
+use tracing::{debug, info};
+
+use crate::types::event::HeosEvent;
+use crate::types::{GroupId, HeosErrorCode, Level, PlayerId};
+use crate::{HeosDriver, HeosError, HeosResult};
+
+// HEOS answers "processing previous command" if it gets more than a few
+// volume changes per second. So there is no point in going any faster.
+const MIN_STEP_INTERVAL: Duration = Duration::from_millis(250);
+const BUSY_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BUSY_RETRIES: u32 = 10;
+// the echo of a step may arrive after the one of the next step.
+const ECHO_WINDOW: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FadeTarget {
+    Player(PlayerId),
+    Group(GroupId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum FadeCurve {
+    #[default]
+    #[serde(rename = "linear")]
+    Linear,
+    // starts slow, good for wake-ups.
+    #[serde(rename = "ease_in")]
+    EaseIn,
+    // starts fast, good for fade-outs.
+    #[serde(rename = "ease_out")]
+    EaseOut,
+    #[serde(rename = "s_curve")]
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Maps the elapsed part of the fade (0.0 - 1.0) to the part of the volume change.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EaseIn => t * t,
+            FadeCurve::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            FadeCurve::SCurve => t * t * (3.0 - 2.0 * t),
+        }
+    }
+
+    pub fn level_at(&self, from: Level, to: Level, t: f64) -> Level {
+        let from = from as f64;
+        let to = to as f64;
+        (from + (to - from) * self.apply(t)).round().clamp(0.0, 100.0) as Level
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FadeOutcome {
+    Completed,
+    // somebody changed the volume while we were fading.
+    Cancelled { level: Level },
+}
+
+impl HeosDriver {
+    /// Changes the volume of a player or group from `from` to `to` in small steps.
+    ///
+    /// The fade stops as soon as the volume is changed by someone else, e.g. with
+    /// the HEOS app or the buttons on the speaker.
+    pub async fn fade_volume(
+        &self,
+        target: FadeTarget,
+        from: Level,
+        to: Level,
+        duration: Duration,
+        curve: FadeCurve,
+    ) -> HeosResult<FadeOutcome> {
+        // subscribe first, otherwise we might miss a change.
+        let mut events = self.subscribe();
+        let mut sent_levels = VecDeque::with_capacity(ECHO_WINDOW + 1);
+        let mut last_level = None;
+        let steps = fade_steps(from, to, duration);
+        let start = Instant::now();
+        debug!("fading {:?} from {} to {} in {} steps", &target, from, to, steps);
+
+        for step in 0..=steps {
+            if step > 0 {
+                sleep_until(start + duration.mul_f64(step as f64 / steps as f64)).await;
+            }
+            if let Some(level) = manual_change(&mut events, &target, &sent_levels) {
+                info!("volume of {:?} changed to {}, stopping fade", &target, level);
+                return Ok(FadeOutcome::Cancelled { level });
+            }
+            let t = if steps == 0 {
+                1.0
+            } else {
+                step as f64 / steps as f64
+            };
+            let level = curve.level_at(from, to, t);
+            if last_level == Some(level) {
+                continue;
+            }
+            // the volume policies might not allow the level we asked for.
+            let sent = self.set_target_volume(&target, level).await?;
+            sent_levels.push_back(sent);
+            if sent_levels.len() > ECHO_WINDOW {
+                sent_levels.pop_front();
+            }
+            last_level = Some(level);
+        }
+        Ok(FadeOutcome::Completed)
+    }
+
+    async fn set_target_volume(&self, target: &FadeTarget, level: Level) -> HeosResult<Level> {
+        let mut attempt = 0;
+        loop {
+            let result = match target {
+                FadeTarget::Player(pid) => self.set_volume(*pid, level).await,
+                FadeTarget::Group(gid) => self.set_group_volume(*gid, level).await,
+            };
+            match result {
+                Err(HeosError::InvalidCommand {
+                    eid: HeosErrorCode::ProcessingPreviousCommand,
+                    ..
+                }) if attempt < MAX_BUSY_RETRIES => {
+                    attempt += 1;
+                    sleep(BUSY_BACKOFF * attempt).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn fade_steps(from: Level, to: Level, duration: Duration) -> u32 {
+    let distance = (to as i32 - from as i32).unsigned_abs();
+    let max_steps = (duration.as_millis() / MIN_STEP_INTERVAL.as_millis()) as u32;
+    distance.min(max_steps)
+}
+
+// Returns the new level if the volume was changed by something else than the fade.
+// HEOS echoes our own changes as events as well, those are filtered by the
+// levels of the last steps sent. A level the fade passed before those is a
+// change by someone else.
+fn manual_change(
+    events: &mut broadcast::Receiver<HeosEvent>,
+    target: &FadeTarget,
+    sent_levels: &VecDeque<Level>,
+) -> Option<Level> {
+    loop {
+        match events.try_recv() {
+            Ok(event) => {
+                let level = match (target, event) {
+                    (
+                        FadeTarget::Player(pid),
+                        HeosEvent::PlayerVolumeChanged {
+                            player_id, level, ..
+                        },
+                    ) if *pid == player_id => level,
+                    (
+                        FadeTarget::Group(gid),
+                        HeosEvent::GroupVolumeChanged {
+                            group_id, level, ..
+                        },
+                    ) if *gid == group_id => level,
+                    _ => continue,
+                };
+                if !sent_levels.contains(&level) {
+                    return Some(level);
+                }
+            }
+            Err(TryRecvError::Lagged(_)) => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_curves_start_and_end_at_the_given_levels() {
+        for curve in [
+            FadeCurve::Linear,
+            FadeCurve::EaseIn,
+            FadeCurve::EaseOut,
+            FadeCurve::SCurve,
+        ] {
+            assert_eq!(curve.level_at(10, 40, 0.0), 10);
+            assert_eq!(curve.level_at(10, 40, 1.0), 40);
+            assert_eq!(curve.level_at(40, 10, 1.0), 10);
+        }
+        assert_eq!(FadeCurve::Linear.level_at(0, 100, 0.5), 50);
+        assert!(FadeCurve::EaseIn.level_at(0, 100, 0.5) < 50);
+        assert!(FadeCurve::EaseOut.level_at(0, 100, 0.5) > 50);
+    }
+
+    #[test]
+    pub fn test_fade_steps() {
+        assert_eq!(fade_steps(20, 20, Duration::from_secs(10)), 0);
+        assert_eq!(fade_steps(0, 10, Duration::from_secs(60)), 10);
+        assert_eq!(fade_steps(50, 0, Duration::from_secs(1)), 4);
+    }
+
+    #[test]
+    pub fn test_own_changes_do_not_cancel() {
+        let (sender, mut receiver) = broadcast::channel(8);
+        let target = FadeTarget::Player(PlayerId(1));
+        let _ = sender.send(HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(1),
+            level: 12,
+            mute: crate::types::OnOrOff::Off,
+        });
+        let _ = sender.send(HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(2),
+            level: 80,
+            mute: crate::types::OnOrOff::Off,
+        });
+        let sent = VecDeque::from([11, 12]);
+        assert_eq!(manual_change(&mut receiver, &target, &sent), None);
+        let _ = sender.send(HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(1),
+            level: 60,
+            mute: crate::types::OnOrOff::Off,
+        });
+        assert_eq!(manual_change(&mut receiver, &target, &sent), Some(60));
+        // a level the fade went through before.
+        let _ = sender.send(HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(1),
+            level: 10,
+            mute: crate::types::OnOrOff::Off,
+        });
+        assert_eq!(manual_change(&mut receiver, &target, &sent), Some(10));
+    }
+
+    #[test]
+    pub fn test_late_echoes_do_not_cancel() {
+        let (sender, mut receiver) = broadcast::channel(8);
+        let target = FadeTarget::Group(GroupId(3));
+        // the echo of step 11 arrives after the one of step 12
+        for level in [12, 11] {
+            let _ = sender.send(HeosEvent::GroupVolumeChanged {
+                group_id: GroupId(3),
+                level,
+                mute: crate::types::OnOrOff::Off,
+            });
+        }
+        let sent = VecDeque::from([11, 12]);
+        assert_eq!(manual_change(&mut receiver, &target, &sent), None);
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use tokio::net::ToSocketAddrs;
+use tokio::sync::broadcast;
+use tokio_stream::Stream;
+use tracing::{debug, info};
+
+use crate::record::Recorder;
+use crate::types::browse::{
+    BroseSourceItem, BrowsableMedia, BrowseMusicContainerResponse, MusicSource, SearchCriteria,
+    SearchResponse,
+};
+use crate::types::event::HeosEvent;
+use crate::types::group::{Group, GroupRole};
+use crate::types::player::{HeosPlayer, PlayState, PlayerInfo, QueueEntry};
+use crate::types::system::AccountState;
+use crate::types::{
+    ContainerId, GroupId, Level, OnOrOff, Page, PlayMode, PlayerId, Range, SearchCriteriaId,
+    SourceId,
+};
+use crate::{HeosApi, HeosError, HeosResult, RetryPolicy};
+
+mod fade;
+mod policy;
+
+pub use fade::{FadeCurve, FadeOutcome, FadeTarget};
+pub use policy::{QuietHours, VolumePolicies, VolumePolicy};
+#[cfg(feature = "rules")]
+pub(crate) use policy::time_of_day;
+
+#[derive(Default, Debug)]
+struct DriverState {
+    pub players: BTreeMap<PlayerId, HeosPlayer>,
+    pub groups: BTreeMap<GroupId, Group>,
+    pub music_sources: BTreeMap<SourceId, MusicSource>,
+}
+
+impl DriverState {
+    pub fn new() -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(DriverState::default()))
+    }
+}
+
+#[derive(Clone)]
+pub struct HeosDriver {
+    api: HeosApi,
+    state: Arc<Mutex<DriverState>>,
+    policies: Arc<Mutex<VolumePolicies>>,
+    // every event is forwarded after the state has been updated.
+    events: broadcast::Sender<HeosEvent>,
+}
+
+impl HeosDriver {
+    pub async fn new<T: ToSocketAddrs>(addr: T) -> HeosResult<Self> {
+        HeosDriver::with_api(HeosApi::connect(addr).await?).await
+    }
+
+    /// Like [`HeosDriver::new`], writing all traffic with the device to the recorder.
+    pub async fn record<T: ToSocketAddrs>(addr: T, recorder: Recorder) -> HeosResult<Self> {
+        HeosDriver::with_api(HeosApi::record(addr, recorder).await?).await
+    }
+
+    async fn with_api(api: HeosApi) -> HeosResult<Self> {
+        let state = DriverState::new();
+        let policies = Arc::new(Mutex::new(VolumePolicies::default()));
+        let (events, _) = broadcast::channel(64);
+
+        let driver = Self {
+            api,
+            state,
+            policies,
+            events,
+        };
+        let _ = driver.init().await;
+        let _ = driver.start_event_listener().await;
+        Ok(driver)
+    }
+
+    pub async fn init(&self) -> HeosResult<()> {
+        let players = load_players(&self.api).await?;
+        let groups = load_groups(&self.api).await?;
+        let music_sources = self.api.get_music_sources().await?;
+        {
+            debug!(
+                "Found {} players and {} groups",
+                players.len(),
+                groups.len()
+            );
+            println!(
+                "Found {} players and {} groups",
+                players.len(),
+                groups.len()
+            );
+            let mut state = self.state.lock().unwrap();
+            state.players.clear();
+            state.groups.clear();
+            state.players = players.into_iter().map(|p| (p.player_id, p)).collect();
+            state.groups = groups.into_iter().map(|g| (g.gid, g)).collect();
+            state.music_sources = music_sources.into_iter().map(|g| (g.sid, g)).collect();
+            #[cfg(feature = "metrics")]
+            crate::metrics::observe(state.players.values(), state.groups.values());
+        }
+        Ok(())
+    }
+    pub async fn login(&self, un: String, pw: String) -> HeosResult<AccountState> {
+        self.api.login(un, pw).await
+    }
+
+    pub fn players(&self) -> Vec<HeosPlayer> {
+        let state = self.state.lock().unwrap();
+        let players = state.players.values().cloned().collect();
+        players
+    }
+
+    pub fn groups(&self) -> Vec<Group> {
+        let state = self.state.lock().unwrap();
+        let groups = state.groups.values().cloned().collect();
+        groups
+    }
+
+    pub fn music_sources(&self) -> Vec<MusicSource> {
+        let state = self.state.lock().unwrap();
+        let music_sources = state.music_sources.values().cloned().collect();
+        music_sources
+    }
+
+    /// Subscribes to the events of the HEOS system.
+    ///
+    /// Events are sent after the driver has updated its own state.
+    pub fn subscribe(&self) -> broadcast::Receiver<HeosEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn volume_policies(&self) -> VolumePolicies {
+        self.policies.lock().unwrap().clone()
+    }
+
+    pub fn set_volume_policies<P: Into<VolumePolicies>>(&self, policies: P) {
+        *self.policies.lock().unwrap() = policies.into();
+    }
+
+    /// How commands HEOS is too busy for are retried, see [`RetryPolicy`].
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        self.api.set_retry_policy(policy);
+    }
+
+    /// Sets the volume of a player within the limits of the volume policies.
+    ///
+    /// Returns the level that was actually sent to the player.
+    pub async fn set_volume(&self, player_id: PlayerId, level: Level) -> HeosResult<Level> {
+        let level = clamp_player_volume(&self.state, &self.policies, player_id, level);
+        let _ = self.api.set_volume(player_id, level).await?;
+        Ok(level)
+    }
+
+    /// Sets the volume of a group within the limits of the volume policies of its members.
+    ///
+    /// Returns the level that was actually sent to the group.
+    pub async fn set_group_volume(&self, group_id: GroupId, level: Level) -> HeosResult<Level> {
+        let level = clamp_group_volume(&self.state, &self.policies, group_id, level);
+        let _ = self.api.set_group_volume(group_id, level).await?;
+        Ok(level)
+    }
+
+    pub async fn set_play_state(&self, player_id: PlayerId, state: PlayState) -> HeosResult<()> {
+        let _ = self.api.set_play_state(player_id, state).await?;
+        Ok(())
+    }
+
+    pub async fn play_next(&self, player_id: PlayerId) -> HeosResult<()> {
+        self.api.play_next(player_id).await
+    }
+
+    pub async fn play_previous(&self, player_id: PlayerId) -> HeosResult<()> {
+        self.api.play_previous(player_id).await
+    }
+
+    pub async fn set_mute(&self, player_id: PlayerId, state: OnOrOff) -> HeosResult<()> {
+        let _ = self.api.set_mute(player_id, state).await?;
+        Ok(())
+    }
+
+    pub async fn set_group_mute(&self, group_id: GroupId, state: OnOrOff) -> HeosResult<()> {
+        let _ = self.api.set_group_mute(group_id, state).await?;
+        Ok(())
+    }
+
+    pub async fn set_play_mode(&self, player_id: PlayerId, mode: PlayMode) -> HeosResult<()> {
+        let _ = self.api.set_play_mode(&player_id, mode).await?;
+        Ok(())
+    }
+
+    pub async fn get_player_queue(
+        &self,
+        pid: PlayerId,
+        range: Range,
+    ) -> HeosResult<Vec<QueueEntry>> {
+        self.api.get_queue(pid, range).await
+    }
+
+    pub async fn create_group<C: IntoIterator<Item = PlayerId>>(
+        &self,
+        leader: PlayerId,
+        members: C,
+    ) -> HeosResult<()> {
+        let members: BTreeSet<PlayerId> = members.into_iter().collect();
+        // check if we do a valid request first
+        // otherwise HEOS will borg!
+        if let Some(group) = self.groups().iter().find(|g| g.gid == leader.group()) {
+            let members_in_group: BTreeSet<PlayerId> = group
+                .players
+                .iter()
+                .filter_map(|p| {
+                    if p.role == GroupRole::Member {
+                        Some(p.pid.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            if members == members_in_group {
+                return Ok(());
+            }
+        } else if members.is_empty() {
+            return Ok(());
+        }
+        let mut group = vec![leader];
+        group.extend(members);
+        let _ = self.api.set_group(group).await?;
+        let groups = load_groups(&self.api).await?;
+        let mut state = self.state.lock().unwrap();
+        state.groups = groups.into_iter().map(|g| (g.gid, g)).collect();
+        Ok(())
+    }
+
+    /// Removes all members from the group led by `leader`.
+    pub async fn delete_group(&self, leader: PlayerId) -> HeosResult<()> {
+        self.api.set_group(vec![leader]).await?;
+        let groups = load_groups(&self.api).await?;
+        let mut state = self.state.lock().unwrap();
+        state.groups = groups.into_iter().map(|g| (g.gid, g)).collect();
+        Ok(())
+    }
+
+    pub async fn browse(&self, sid: SourceId) -> HeosResult<Vec<BroseSourceItem>> {
+        self.api.browse_music_sources(sid).await
+    }
+    pub async fn browse_music_containers(
+        &self,
+        sid: &SourceId,
+        container_id: &ContainerId,
+        range: &Range,
+    ) -> HeosResult<BrowseMusicContainerResponse> {
+        self.api
+            .browse_music_containers(sid, container_id, range)
+            .await
+    }
+
+    pub async fn browse_page(
+        &self,
+        sid: &SourceId,
+        cid: &ContainerId,
+        range: &Range,
+    ) -> HeosResult<Page<BrowsableMedia>> {
+        self.api.browse_page(sid, cid, range).await
+    }
+
+    /// All items of a container, fetched page by page as the stream is read.
+    pub fn browse_stream(
+        &self,
+        sid: SourceId,
+        cid: ContainerId,
+    ) -> impl Stream<Item = HeosResult<BrowsableMedia>> {
+        self.api.browse_stream(sid, cid)
+    }
+
+    pub async fn get_search_criteria(&self, sid: SourceId) -> HeosResult<Vec<SearchCriteria>> {
+        self.api.get_search_criteria(sid).await
+    }
+
+    pub async fn search(
+        &self,
+        sid: SourceId,
+        scid: SearchCriteriaId,
+        search: &str,
+        range: &Range,
+    ) -> HeosResult<SearchResponse> {
+        self.api.search(sid, scid, search, range).await
+    }
+
+    async fn start_event_listener(&self) -> HeosResult<()> {
+        let mut events = self.api.events().await?;
+        let event_api = self.api.clone();
+        let state = self.state.clone();
+        let policies = self.policies.clone();
+        let subscribers = self.events.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let _ =
+                    HeosDriver::handle_event(event.clone(), &event_api, &state, &policies).await;
+                #[cfg(feature = "metrics")]
+                {
+                    let state = state.lock().unwrap();
+                    crate::metrics::observe(state.players.values(), state.groups.values());
+                }
+                // nobody listening is fine.
+                let _ = subscribers.send(event);
+            }
+        });
+        Ok(())
+    }
+
+    async fn handle_event(
+        event: HeosEvent,
+        connection: &HeosApi,
+        driver_state: &Arc<Mutex<DriverState>>,
+        policies: &Arc<Mutex<VolumePolicies>>,
+    ) -> HeosResult<()> {
+        match event {
+            HeosEvent::SourcesChanged => {
+                let _ = connection.get_music_sources().await.map(|sources| {
+                    let mut state = driver_state.lock().unwrap();
+                    state.music_sources = sources.into_iter().map(|s| (s.sid, s)).collect();
+                });
+            }
+            HeosEvent::PlayersChanged => {
+                let _ = load_players(&connection).await.map(|players| {
+                    let mut state = driver_state.lock().unwrap();
+                    state.players = players.into_iter().map(|s| (s.player_id, s)).collect();
+                });
+            }
+            HeosEvent::GroupChanged => {
+                let _ = load_groups(connection).await.map(|groups| {
+                    let mut state = driver_state.lock().unwrap();
+                    state.groups = groups.into_iter().map(|s| (s.gid, s)).collect();
+                });
+                let _ = load_players(connection).await.map(|players| {
+                    let mut state = driver_state.lock().unwrap();
+                    state.players = players.into_iter().map(|p| (p.player_id, p)).collect();
+                });
+            }
+            HeosEvent::PlayerStateChanged { player_id, state } => {
+                let mut driver_state = driver_state.lock().unwrap();
+                if let Some(player) = driver_state.players.get_mut(&player_id) {
+                    player.play_state = state
+                }
+            }
+            HeosEvent::PlayerNowPlayingChanged { player_id } => {
+                let _ =
+                    connection
+                        .get_now_playing_media(&player_id)
+                        .await
+                        .map(|now_playing_media| {
+                            let mut state = driver_state.lock().unwrap();
+                            if let Some(player) = state.players.get_mut(&player_id) {
+                                player.now_playing = now_playing_media
+                            }
+                        });
+            }
+            HeosEvent::PlayerNowPlayingProgress { .. } => {}
+            HeosEvent::PlayerPlaybackError { .. } => {}
+            HeosEvent::PlayerVolumeChanged {
+                player_id,
+                level,
+                mute,
+            } => {
+                if let Some(player) = driver_state.lock().unwrap().players.get_mut(&player_id) {
+                    player.volume = level;
+                    player.mute = mute;
+                }
+                // somebody used the app or the buttons on the device.
+                let allowed = clamp_player_volume(driver_state, policies, player_id, level);
+                if allowed != level {
+                    info!(
+                        "volume {} of player {} violates the volume policy, resetting to {}",
+                        level, player_id, allowed
+                    );
+                    let _ = connection.set_volume(player_id, allowed).await?;
+                }
+            }
+            HeosEvent::PlayerQueueChanged { .. } => {}
+            HeosEvent::PlayerRepeatModeChanged { .. } => {}
+            HeosEvent::PlayerShuffleModeChanged { .. } => {}
+            HeosEvent::GroupVolumeChanged {
+                group_id, level, ..
+            } => {
+                if let Some(group) = driver_state.lock().unwrap().groups.get_mut(&group_id) {
+                    group.volume = level;
+                }
+                let allowed = clamp_group_volume(driver_state, policies, group_id, level);
+                if allowed != level {
+                    info!(
+                        "volume {} of group {} violates the volume policy, resetting to {}",
+                        level, group_id, allowed
+                    );
+                    let _ = connection.set_group_volume(group_id, allowed).await?;
+                }
+            }
+            HeosEvent::UserChanged { .. } => {}
+            HeosEvent::Unknown { name, .. } => debug!("unknown event {}", name),
+        };
+        Ok(())
+    }
+}
+
+fn clamp_player_volume(
+    state: &Arc<Mutex<DriverState>>,
+    policies: &Arc<Mutex<VolumePolicies>>,
+    player_id: PlayerId,
+    level: Level,
+) -> Level {
+    let state = state.lock().unwrap();
+    match state.players.get(&player_id) {
+        Some(player) => {
+            let now = chrono::Local::now().time();
+            policies.lock().unwrap().clamp_player(player, level, now)
+        }
+        None => level,
+    }
+}
+
+fn clamp_group_volume(
+    state: &Arc<Mutex<DriverState>>,
+    policies: &Arc<Mutex<VolumePolicies>>,
+    group_id: GroupId,
+    level: Level,
+) -> Level {
+    let state = state.lock().unwrap();
+    match state.groups.get(&group_id) {
+        Some(group) => {
+            let members = group
+                .players
+                .iter()
+                .filter_map(|member| state.players.get(&member.pid));
+            let now = chrono::Local::now().time();
+            policies.lock().unwrap().clamp_group(members, level, now)
+        }
+        None => level,
+    }
+}
+
+pub async fn load_groups(channel: &HeosApi) -> HeosResult<Vec<Group>> {
+    let mut groups = vec![];
+    let group_infos = channel.get_groups().await?;
+    for group_info in group_infos {
+        let volume = channel.get_group_volume(group_info.gid).await?;
+        groups.push(Group {
+            name: group_info.name,
+            gid: group_info.gid,
+            volume: volume.level,
+            players: group_info.players,
+        });
+    }
+    Ok(groups)
+}
+
+pub async fn load_players(channel: &HeosApi) -> Result<Vec<HeosPlayer>, HeosError> {
+    let mut players = vec![];
+    let player_infos = channel.get_player_infos().await?;
+    for info in player_infos {
+        players.push(fetch_player(channel, info).await?)
+    }
+    Ok(players)
+}
+
+async fn fetch_player(channel: &HeosApi, info: PlayerInfo) -> HeosResult<HeosPlayer> {
+    let volume = channel.get_volume(&info.pid).await?.level;
+    let mute = channel.get_mute(info.pid).await?.state;
+    let state = channel.get_play_state(&info.pid).await?.state;
+    let now_playing = channel.get_now_playing_media(&info.pid).await?;
+    let mode = Some(channel.get_play_mode(&info.pid).await?.mode);
+
+    Ok(HeosPlayer {
+        player_id: info.pid,
+        name: info.name,
+        volume,
+        mute,
+        now_playing,
+        mode,
+        play_state: state,
+        in_group: info.gid,
+    })
+}
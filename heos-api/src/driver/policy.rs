@@ -0,0 +1,186 @@
+use chrono::NaiveTime;
+
+use crate::types::player::HeosPlayer;
+use crate::types::Level;
+
+/// Volume limits for a single player.
+///
+/// ```yaml
+/// - player: Bedroom
+///   max_volume: 60
+///   min_volume: 5
+///   quiet_hours:
+///     - from: "20:00"
+///       until: "07:00"
+///       max_volume: 20
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct VolumePolicy {
+    // the name or the pid of the player
+    pub player: String,
+    #[serde(default)]
+    pub max_volume: Option<Level>,
+    #[serde(default)]
+    pub min_volume: Option<Level>,
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietHours>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct QuietHours {
+    #[serde(with = "time_of_day")]
+    pub from: NaiveTime,
+    #[serde(with = "time_of_day")]
+    pub until: NaiveTime,
+    pub max_volume: Level,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.until {
+            self.from <= time && time < self.until
+        } else {
+            // over midnight
+            time >= self.from || time < self.until
+        }
+    }
+}
+
+impl VolumePolicy {
+    pub fn applies_to(&self, player: &HeosPlayer) -> bool {
+        self.player == player.name || self.player == player.player_id.to_string()
+    }
+
+    pub fn max_volume_at(&self, time: NaiveTime) -> Option<Level> {
+        self.quiet_hours
+            .iter()
+            .filter(|quiet_hours| quiet_hours.contains(time))
+            .map(|quiet_hours| quiet_hours.max_volume)
+            .chain(self.max_volume)
+            .min()
+    }
+
+    // the maximum wins over the minimum. Quiet is quiet.
+    pub fn clamp(&self, level: Level, time: NaiveTime) -> Level {
+        let level = match self.min_volume {
+            Some(min) => level.max(min),
+            None => level,
+        };
+        match self.max_volume_at(time) {
+            Some(max) => level.min(max),
+            None => level,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct VolumePolicies(Vec<VolumePolicy>);
+
+impl VolumePolicies {
+    pub fn new(policies: Vec<VolumePolicy>) -> Self {
+        Self(policies)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, VolumePolicy> {
+        self.0.iter()
+    }
+
+    pub fn clamp_player(&self, player: &HeosPlayer, level: Level, time: NaiveTime) -> Level {
+        self.0
+            .iter()
+            .filter(|policy| policy.applies_to(player))
+            .fold(level, |level, policy| policy.clamp(level, time))
+    }
+
+    // a group is only as loud as its most restricted member allows.
+    pub fn clamp_group<'a, P>(&self, members: P, level: Level, time: NaiveTime) -> Level
+    where
+        P: IntoIterator<Item = &'a HeosPlayer>,
+    {
+        members.into_iter().fold(level, |level, player| {
+            self.clamp_player(player, level, time)
+        })
+    }
+}
+
+impl From<Vec<VolumePolicy>> for VolumePolicies {
+    fn from(policies: Vec<VolumePolicy>) -> Self {
+        VolumePolicies(policies)
+    }
+}
+
+pub(crate) mod time_of_day {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%H:%M";
+
+    pub fn serialize<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&time.format(FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(&s, FORMAT)
+            .or_else(|_| NaiveTime::parse_from_str(&s, "%H:%M:%S"))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn bedroom() -> VolumePolicy {
+        serde_json::from_value(serde_json::json!({
+            "player": "Bedroom",
+            "max_volume": 60,
+            "min_volume": 5,
+            "quiet_hours": [{ "from": "20:00", "until": "07:00", "max_volume": 20 }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    pub fn test_quiet_hours_over_midnight() {
+        let policy = bedroom();
+        assert_eq!(policy.clamp(90, time(12, 0)), 60);
+        assert_eq!(policy.clamp(90, time(20, 0)), 20);
+        assert_eq!(policy.clamp(90, time(3, 30)), 20);
+        assert_eq!(policy.clamp(90, time(7, 0)), 60);
+        assert_eq!(policy.clamp(0, time(12, 0)), 5);
+    }
+
+    #[test]
+    pub fn test_policies_match_name_or_pid() {
+        let policies = VolumePolicies::new(vec![
+            bedroom(),
+            VolumePolicy {
+                player: "42".to_string(),
+                max_volume: Some(30),
+                min_volume: None,
+                quiet_hours: vec![],
+            },
+        ]);
+        let bedroom = HeosPlayer::test_player(1, "Bedroom");
+        let kitchen = HeosPlayer::test_player(42, "Kitchen");
+        let living = HeosPlayer::test_player(2, "Living");
+        assert_eq!(policies.clamp_player(&bedroom, 80, time(12, 0)), 60);
+        assert_eq!(policies.clamp_player(&kitchen, 80, time(12, 0)), 30);
+        assert_eq!(policies.clamp_player(&living, 80, time(12, 0)), 80);
+        assert_eq!(
+            policies.clamp_group([&bedroom, &kitchen, &living], 80, time(12, 0)),
+            30
+        );
+    }
+}
@@ -16,6 +16,13 @@ pub enum HeosError {
     #[error("No HOES devices found in local network")]
     NoDeviceFound
 }
+// for the codec, tokio_util wants to return io errors.
+impl From<std::io::Error> for HeosError {
+    fn from(err: std::io::Error) -> Self {
+        HeosError::InternalError(err.into())
+    }
+}
+
 // We are still using a bespoke implementation of `Debug`
 // to get a nice report using the error source chain
 impl std::fmt::Debug for HeosError {
@@ -11,19 +11,37 @@ use crate::error::HeosError;
 pub(crate) mod macros;
 
 mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod connection;
 pub mod error;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod protocol;
+pub mod record;
+#[cfg(feature = "rules")]
+pub mod rules;
+#[cfg(feature = "simulator")]
+pub mod simulator;
 pub mod types;
 pub type HeosResult<T> = Result<T, HeosError>;
 
-pub use api::HeosApi;
+pub use api::{HeosApi, RetryPolicy};
+pub use protocol::{CommandSpec, COMMANDS};
+pub use connection::{CommandResponse, Connection, EventResponse, Frame, HeosCodec, Line};
+#[cfg(feature = "blocking")]
+pub use connection::BlockingConnection;
 
 mod driver;
 
-pub use driver::HeosDriver;
+pub use driver::{
+    FadeCurve, FadeOutcome, FadeTarget, HeosDriver, QuietHours, VolumePolicies, VolumePolicy,
+};
 
 mod discover;
 
+pub use discover::{discover_heos_devices, find_heos_devices};
+
 pub async fn find_driver() -> HeosResult<HeosDriver>{
     let addr = discover::find_heos_devices().await?;
     HeosDriver::new((addr, 1255)).await
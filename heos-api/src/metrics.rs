@@ -0,0 +1,213 @@
+//! Prometheus metrics of the commands, events and players, see [`gather`].
+//!
+//! Everything is registered in the default registry of the `prometheus` crate,
+//! so an app can add its own metrics and serve them all together.
+
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_gauge_vec, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    Encoder, GaugeVec, HistogramVec, IntCounter, IntCounterVec, TextEncoder,
+};
+
+use crate::error::HeosError;
+use crate::types::event::HeosEvent;
+use crate::types::group::Group;
+use crate::types::player::{HeosPlayer, PlayState};
+use crate::HeosResult;
+
+/// The content type of [`gather`].
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+lazy_static! {
+    static ref COMMANDS: IntCounterVec = register_int_counter_vec!(
+        "heos_commands_total",
+        "Commands sent to the device",
+        &["command"]
+    )
+    .unwrap();
+    static ref COMMAND_SECONDS: HistogramVec = register_histogram_vec!(
+        "heos_command_duration_seconds",
+        "Time until the device answered a command",
+        &["command"],
+        vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap();
+    static ref COMMAND_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "heos_command_errors_total",
+        "Failed commands by HEOS error code, or `internal` for network and parse errors",
+        &["command", "error"]
+    )
+    .unwrap();
+    static ref CONNECTIONS: IntCounter = register_int_counter!(
+        "heos_connections_total",
+        "Connections opened to the device, more than two per driver mean it reconnected"
+    )
+    .unwrap();
+    static ref EVENTS: IntCounterVec = register_int_counter_vec!(
+        "heos_events_total",
+        "Change events received from the device",
+        &["event"]
+    )
+    .unwrap();
+    static ref VOLUME: GaugeVec = register_gauge_vec!(
+        "heos_player_volume",
+        "The volume of a player",
+        &["pid", "name"]
+    )
+    .unwrap();
+    static ref PLAY_STATE: GaugeVec = register_gauge_vec!(
+        "heos_player_play_state",
+        "1 for the current play state of a player, 0 for the others",
+        &["pid", "name", "state"]
+    )
+    .unwrap();
+    static ref GROUP: GaugeVec = register_gauge_vec!(
+        "heos_player_group",
+        "1 for each player in a group",
+        &["pid", "name", "gid", "role"]
+    )
+    .unwrap();
+}
+
+/// All metrics in the Prometheus text format.
+pub fn gather() -> String {
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("metrics can always be encoded");
+    String::from_utf8(buffer).expect("metrics are always utf-8")
+}
+
+/// Times a command from sending until the response.
+pub(crate) struct CommandTimer {
+    name: String,
+    started: Instant,
+}
+
+impl CommandTimer {
+    pub fn start(command: &str) -> Self {
+        let name = command.split('?').next().unwrap_or_default().to_string();
+        COMMANDS.with_label_values(&[&name]).inc();
+        CommandTimer {
+            name,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn finish<T>(self, result: &HeosResult<T>) {
+        COMMAND_SECONDS
+            .with_label_values(&[&self.name])
+            .observe(self.started.elapsed().as_secs_f64());
+        let error = match result {
+            Ok(_) => return,
+            Err(HeosError::InvalidCommand { eid, .. }) => format!("{:?}", eid),
+            Err(_) => "internal".to_string(),
+        };
+        COMMAND_ERRORS
+            .with_label_values(&[&self.name, &error])
+            .inc();
+    }
+}
+
+pub(crate) fn connected() {
+    CONNECTIONS.inc();
+}
+
+pub(crate) fn event(event: &HeosEvent) {
+    EVENTS.with_label_values(&[event.name()]).inc();
+}
+
+/// Sets the gauges to the state of the driver.
+pub(crate) fn observe<'a, P, G>(players: P, groups: G)
+where
+    P: IntoIterator<Item = &'a HeosPlayer>,
+    G: IntoIterator<Item = &'a Group>,
+{
+    // reset first, so players that went away disappear.
+    VOLUME.reset();
+    PLAY_STATE.reset();
+    GROUP.reset();
+    for player in players {
+        let pid = player.player_id.to_string();
+        VOLUME
+            .with_label_values(&[&pid, &player.name])
+            .set(player.volume as f64);
+        for state in [PlayState::Play, PlayState::Pause, PlayState::Stop] {
+            let current = if player.play_state == state { 1.0 } else { 0.0 };
+            PLAY_STATE
+                .with_label_values(&[&pid, &player.name, &state.to_string()])
+                .set(current);
+        }
+    }
+    for group in groups {
+        let gid = group.gid.to_string();
+        for member in &group.players {
+            let role = serde_json::to_value(member.role)
+                .ok()
+                .and_then(|role| role.as_str().map(str::to_string))
+                .unwrap_or_default();
+            GROUP
+                .with_label_values(&[&member.pid.to_string(), &member.name, &gid, &role])
+                .set(1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::group::{GroupMember, GroupRole};
+    use crate::types::HeosErrorCode;
+    use crate::types::{GroupId, OnOrOff, PlayerId};
+
+    use super::*;
+
+    #[test]
+    pub fn test_metrics() {
+        let timer = CommandTimer::start("test/set_volume?pid=1&level=120");
+        timer.finish::<()>(&Err(HeosError::InvalidCommand {
+            command: "test/set_volume".to_string(),
+            eid: HeosErrorCode::ParameterOutOfRange,
+            text: "Parameter out of range".to_string(),
+        }));
+        event(&HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(1),
+            level: 20,
+            mute: OnOrOff::Off,
+        });
+        let player = HeosPlayer {
+            player_id: PlayerId(1),
+            name: "Kitchen".to_string(),
+            volume: 20,
+            mute: OnOrOff::Off,
+            now_playing: None,
+            play_state: PlayState::Pause,
+            in_group: Some(GroupId(1)),
+            mode: None,
+        };
+        let group = Group {
+            name: "Kitchen".to_string(),
+            gid: GroupId(1),
+            volume: 20,
+            players: vec![GroupMember {
+                name: "Kitchen".to_string(),
+                pid: PlayerId(1),
+                role: GroupRole::Leader,
+            }],
+        };
+        observe([&player], [&group]);
+
+        let metrics = gather();
+        assert!(metrics.contains("heos_commands_total{command=\"test/set_volume\"} 1"));
+        assert!(metrics.contains(
+            "heos_command_errors_total{command=\"test/set_volume\",error=\"ParameterOutOfRange\"} 1"
+        ));
+        assert!(metrics.contains("heos_events_total{event=\"player_volume_changed\"}"));
+        assert!(metrics.contains("heos_player_volume{name=\"Kitchen\",pid=\"1\"} 20"));
+        assert!(metrics
+            .contains("heos_player_play_state{name=\"Kitchen\",pid=\"1\",state=\"pause\"} 1"));
+        assert!(metrics
+            .contains("heos_player_group{gid=\"1\",name=\"Kitchen\",pid=\"1\",role=\"leader\"} 1"));
+    }
+}
@@ -111,6 +111,7 @@ pub async fn create_heos_driver(mut api: HeosApi) -> HeosResult<HeosDriver> {
                     HeosEvent::PlayerShuffleModeChanged { .. } => {}
                     HeosEvent::GroupVolumeChanged { .. } => {}
                     HeosEvent::UserChanged { .. } => {}
+                    HeosEvent::Unknown { .. } => {}
                 };
             }
         });
@@ -0,0 +1,278 @@
+//! The framing of the CLI protocol: HEOS sends one json object per frame,
+//! ended by `\r\n`, commands go out as `heos://<command>\r\n`.
+//!
+//! Frames may contain `\n` themselves, e.g. after
+//! `system/prettify_json_response?enable=on`.
+//!
+//! The [`Decoder`] and [`Encoder`] impls are for `tokio_util`, without tokio
+//! use [`HeosCodec::decode_line`] and [`HeosCodec::encode_command`].
+
+use anyhow::anyhow;
+use bytes::{BufMut, BytesMut};
+use serde_json::Value;
+use tokio_util::codec::{Decoder, Encoder};
+use tracing::warn;
+
+use crate::error::HeosError;
+
+const DELIMITER: &[u8] = b"\r\n";
+
+/// Large enough for a queue or a container of a few hundred items.
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 1024 * 1024;
+
+/// A frame HEOS sent, as it came and as json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    pub json: Value,
+}
+
+/// Splits what HEOS sends into frames of json and writes commands.
+///
+/// Frames that are not json or longer than the maximum are skipped, so one
+/// garbled response does not cost the connection.
+#[derive(Debug, Clone)]
+pub struct HeosCodec {
+    max_length: usize,
+    // where to continue looking for the delimiter
+    next_index: usize,
+    // skipping the rest of a frame that is too long
+    discarding: bool,
+}
+
+impl HeosCodec {
+    pub fn new() -> Self {
+        HeosCodec::with_max_length(DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    pub fn with_max_length(max_length: usize) -> Self {
+        HeosCodec {
+            max_length,
+            next_index: 0,
+            discarding: false,
+        }
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// The next frame in `src`, `None` until one is complete.
+    pub fn decode_line(&mut self, src: &mut BytesMut) -> Result<Option<Line>, HeosError> {
+        loop {
+            let end = match self.find_delimiter(src) {
+                Some(end) => end,
+                None if src.len() > self.max_length => {
+                    if !self.discarding {
+                        warn!("Skipping a frame longer than {} bytes", self.max_length);
+                    }
+                    self.discarding = true;
+                    // keep a \r, it may start the delimiter
+                    let keep = usize::from(src.ends_with(b"\r"));
+                    let _ = src.split_to(src.len() - keep);
+                    self.next_index = 0;
+                    return Ok(None);
+                }
+                None => {
+                    // the \r of the delimiter may have arrived already
+                    self.next_index = src.len().saturating_sub(DELIMITER.len() - 1);
+                    return Ok(None);
+                }
+            };
+            let frame = src.split_to(end + DELIMITER.len());
+            self.next_index = 0;
+            if std::mem::take(&mut self.discarding) {
+                continue;
+            }
+            let frame = &frame[..end];
+            if frame.len() > self.max_length {
+                warn!("Skipping a frame of {} bytes", frame.len());
+                continue;
+            }
+            if frame.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match parse_line(frame) {
+                Ok(line) => return Ok(Some(line)),
+                Err(err) => warn!(
+                    "Skipping a frame that is not json: {} {:?}",
+                    err,
+                    String::from_utf8_lossy(frame)
+                ),
+            }
+        }
+    }
+
+    /// Like [`HeosCodec::decode_line`] once the connection was closed, an
+    /// incomplete frame is an error.
+    pub fn decode_line_eof(&mut self, src: &mut BytesMut) -> Result<Option<Line>, HeosError> {
+        match self.decode_line(src)? {
+            Some(line) => Ok(Some(line)),
+            None if src.is_empty() || self.discarding => Ok(None),
+            None => Err(anyhow!("Failed to read from Heos. Connection reset by peer").into()),
+        }
+    }
+
+    /// Writes `command` as `heos://<command>\r\n`, it may start with `heos://`.
+    pub fn encode_command(&mut self, command: &str, dst: &mut BytesMut) -> Result<(), HeosError> {
+        let command = command.strip_prefix("heos://").unwrap_or(command);
+        // a line break would send a second command
+        if command.contains(['\r', '\n']) {
+            return Err(anyhow!("A command can't contain line breaks: {:?}", command).into());
+        }
+        dst.reserve("heos://".len() + command.len() + DELIMITER.len());
+        dst.put_slice(b"heos://");
+        dst.put_slice(command.as_bytes());
+        dst.put_slice(DELIMITER);
+        Ok(())
+    }
+
+    fn find_delimiter(&self, src: &[u8]) -> Option<usize> {
+        src[self.next_index..]
+            .windows(DELIMITER.len())
+            .position(|window| window == DELIMITER)
+            .map(|position| self.next_index + position)
+    }
+}
+
+impl Default for HeosCodec {
+    fn default() -> Self {
+        HeosCodec::new()
+    }
+}
+
+impl Decoder for HeosCodec {
+    type Item = Line;
+    type Error = HeosError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Line>, HeosError> {
+        self.decode_line(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Line>, HeosError> {
+        self.decode_line_eof(src)
+    }
+}
+
+fn parse_line(frame: &[u8]) -> anyhow::Result<Line> {
+    let text = std::str::from_utf8(frame)?;
+    let json = serde_json::from_str(text)?;
+    Ok(Line {
+        text: text.to_string(),
+        json,
+    })
+}
+
+impl<T: AsRef<str>> Encoder<T> for HeosCodec {
+    type Error = HeosError;
+
+    fn encode(&mut self, command: T, dst: &mut BytesMut) -> Result<(), HeosError> {
+        self.encode_command(command.as_ref(), dst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+
+    fn decode_all(codec: &mut HeosCodec, chunks: &[&[u8]]) -> Vec<Value> {
+        let mut buffer = BytesMut::new();
+        let mut lines = vec![];
+        for chunk in chunks {
+            buffer.extend_from_slice(chunk);
+            while let Some(line) = codec.decode(&mut buffer).unwrap() {
+                lines.push(line.json);
+            }
+        }
+        if let Ok(Some(line)) = codec.decode_eof(&mut buffer) {
+            lines.push(line.json);
+        }
+        lines
+    }
+
+    fn frame(n: u32) -> Value {
+        json!({"heos": {"command": "event/players_changed", "message": format!("n={}", n)}})
+    }
+
+    #[test]
+    pub fn test_bad_frames_are_skipped() {
+        let mut codec = HeosCodec::with_max_length(70);
+        let long = format!("{{\"text\": \"{}\"}}\r\n", "x".repeat(100));
+        let stream = format!(
+            "{}\r\n\r\nnot json\r\n{}\r\n{{\"a\":\n 1}}\r\n{}{}\r\n",
+            frame(1),
+            frame(2),
+            long,
+            frame(3)
+        );
+        let lines = decode_all(&mut codec, &[stream.as_bytes()]);
+        assert_eq!(lines, vec![frame(1), frame(2), json!({"a": 1}), frame(3)]);
+
+        // too long before its end arrived
+        let (start, end) = long.split_at(80);
+        let end = format!("{}{}\r\n", end, frame(3));
+        let lines = decode_all(&mut codec, &[start.as_bytes(), end.as_bytes()]);
+        assert_eq!(lines, vec![frame(3)]);
+        let mut buffer = BytesMut::from(&b"{}"[..]);
+        assert!(HeosCodec::new().decode(&mut buffer).unwrap().is_none());
+        assert!(HeosCodec::new().decode_eof(&mut buffer).is_err());
+        assert!(HeosCodec::new()
+            .decode_eof(&mut BytesMut::new())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    pub fn test_encode() {
+        let mut buffer = BytesMut::new();
+        let mut codec = HeosCodec::new();
+        codec.encode("player/get_players", &mut buffer).unwrap();
+        codec
+            .encode("heos://system/heart_beat", &mut buffer)
+            .unwrap();
+        assert_eq!(
+            &buffer[..],
+            b"heos://player/get_players\r\nheos://system/heart_beat\r\n"
+        );
+        assert!(codec.encode("a\r\nplayer/play_next", &mut buffer).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_frames_survive_any_chunking(
+            ns in prop::collection::vec(any::<u32>(), 0..8),
+            cuts in prop::collection::vec(any::<prop::sample::Index>(), 0..8),
+        ) {
+            let frames: Vec<Value> = ns.into_iter().map(frame).collect();
+            let stream: Vec<u8> = frames
+                .iter()
+                .flat_map(|frame| format!("{}\r\n", frame).into_bytes())
+                .collect();
+            let mut cuts: Vec<usize> = cuts.iter().map(|cut| cut.index(stream.len() + 1)).collect();
+            cuts.sort_unstable();
+            let mut chunks = vec![];
+            let mut start = 0;
+            for cut in cuts.into_iter().chain([stream.len()]) {
+                chunks.push(&stream[start..cut]);
+                start = cut;
+            }
+            prop_assert_eq!(decode_all(&mut HeosCodec::new(), &chunks), frames);
+        }
+
+        #[test]
+        fn test_garbage_is_skipped(
+            garbage in prop::collection::vec(any::<u8>(), 0..200),
+            max_length in 100usize..300,
+        ) {
+            let mut codec = HeosCodec::with_max_length(max_length);
+            let mut stream = garbage;
+            stream.extend_from_slice(b"\r\n");
+            stream.extend_from_slice(format!("{}\r\n", frame(7)).as_bytes());
+            let lines = decode_all(&mut codec, &[&stream]);
+            prop_assert_eq!(lines.last(), Some(&frame(7)));
+        }
+    }
+}
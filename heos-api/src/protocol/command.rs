@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::types::player::PlayState;
+use crate::types::{
+    ContainerId, GroupId, Level, OnOrOff, PlayerId, Range, Repeat, SearchCriteriaId, Shuffle,
+    SourceId,
+};
+
+// declares `Command` with one variant per command, the fields are the
+// parameters and named like them.
+macro_rules! commands {
+    ($(
+        $(#[$doc:meta])*
+        $variant:ident = $name:literal { $($param:ident: $ty:ty),* $(,)? }
+    ),* $(,)?) => {
+        /// A command of the CLI protocol, `Display` writes it the way HEOS
+        /// expects it, e.g. `player/set_volume?pid=1&level=20`.
+        ///
+        /// [`crate::HeosApi`] and the blocking client send these, so both
+        /// speak the same protocol.
+        #[derive(Debug, Clone)]
+        pub enum Command {
+            $( $(#[$doc])* $variant { $($param: $ty),* }, )*
+        }
+
+        impl Command {
+            /// e.g. `player/set_volume`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Command::$variant { .. } => $name, )*
+                }
+            }
+
+            /// The parameters as they are sent, encoded where needed. Those
+            /// left out are missing.
+            pub fn params(&self) -> Vec<(&'static str, String)> {
+                match self {
+                    $( Command::$variant { $($param),* } => {
+                        let params: Vec<Option<(&'static str, String)>> = vec![$(
+                            Param::encode($param).map(|value| (stringify!($param), value))
+                        ),*];
+                        params.into_iter().flatten().collect()
+                    } )*
+                }
+            }
+        }
+
+        /// The commands of [`Command`] and the names of their parameters.
+        pub const COMMANDS: &[CommandSpec] = &[
+            $( CommandSpec { name: $name, params: &[$(stringify!($param)),*] }, )*
+        ];
+    };
+}
+
+/// A command of the HEOS CLI protocol and the names of its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// e.g. `player/set_volume`
+    pub name: &'static str,
+    pub params: &'static [&'static str],
+}
+
+impl CommandSpec {
+    pub fn find(name: &str) -> Option<&'static CommandSpec> {
+        COMMANDS.iter().find(|command| command.name == name)
+    }
+}
+
+commands! {
+    RegisterForChangeEvents = "system/register_for_change_events" { enable: OnOrOff },
+    SignIn = "system/sign_in" { un: String, pw: String },
+    GetPlayers = "player/get_players" {},
+    GetPlayState = "player/get_play_state" { pid: PlayerId },
+    SetPlayState = "player/set_play_state" { pid: PlayerId, state: PlayState },
+    GetNowPlayingMedia = "player/get_now_playing_media" { pid: PlayerId },
+    GetVolume = "player/get_volume" { pid: PlayerId },
+    SetVolume = "player/set_volume" { pid: PlayerId, level: Level },
+    GetMute = "player/get_mute" { pid: PlayerId },
+    SetMute = "player/set_mute" { pid: PlayerId, state: OnOrOff },
+    GetPlayMode = "player/get_play_mode" { pid: PlayerId },
+    SetPlayMode = "player/set_play_mode" { pid: PlayerId, repeat: Repeat, shuffle: Shuffle },
+    GetQueue = "player/get_queue" { pid: PlayerId, range: Range },
+    PlayNext = "player/play_next" { pid: PlayerId },
+    PlayPrevious = "player/play_previous" { pid: PlayerId },
+    GetGroups = "group/get_groups" {},
+    /// Groups the players, the first one leads. One player ungroups it.
+    SetGroup = "group/set_group" { pid: Vec<PlayerId> },
+    GetGroupVolume = "group/get_volume" { gid: GroupId },
+    SetGroupVolume = "group/set_volume" { gid: GroupId, level: Level },
+    SetGroupMute = "group/set_mute" { gid: GroupId, state: OnOrOff },
+    GetMusicSources = "browse/get_music_sources" {},
+    /// The top level of a source without `cid`, or a page of a container.
+    Browse = "browse/browse" { sid: SourceId, cid: Option<ContainerId>, range: Option<Range> },
+    GetSearchCriteria = "browse/get_search_criteria" { sid: SourceId },
+    Search = "browse/search" { sid: SourceId, search: String, scid: SearchCriteriaId, range: Range },
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())?;
+        for (i, (key, value)) in self.params().iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(f, "{}{}={}", separator, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+// how a parameter is written into a command, `None` leaves it out.
+trait Param {
+    fn encode(&self) -> Option<String>;
+}
+
+macro_rules! display_param {
+    ($($t:ty),*) => {
+        $(impl Param for $t {
+            fn encode(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+        })*
+    };
+}
+
+display_param!(
+    PlayerId,
+    GroupId,
+    SourceId,
+    ContainerId,
+    SearchCriteriaId,
+    Level,
+    OnOrOff,
+    PlayState,
+    Repeat,
+    Shuffle
+);
+
+// HEOS wants only these encoded in free text, everything else goes as is.
+impl Param for String {
+    fn encode(&self) -> Option<String> {
+        Some(
+            self.replace('%', "%25")
+                .replace('&', "%26")
+                .replace('=', "%3D"),
+        )
+    }
+}
+
+impl Param for Range {
+    fn encode(&self) -> Option<String> {
+        Some(format!("{},{}", self.start, self.end))
+    }
+}
+
+impl Param for Vec<PlayerId> {
+    fn encode(&self) -> Option<String> {
+        Some(
+            self.iter()
+                .map(|pid| pid.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+}
+
+impl<T: Param> Param for Option<T> {
+    fn encode(&self) -> Option<String> {
+        self.as_ref().and_then(Param::encode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_commands_are_formatted() {
+        assert_eq!(Command::GetPlayers {}.to_string(), "player/get_players");
+        assert_eq!(
+            Command::SetPlayMode {
+                pid: PlayerId(1),
+                repeat: Repeat::OnAll,
+                shuffle: Shuffle::Off,
+            }
+            .to_string(),
+            "player/set_play_mode?pid=1&repeat=on_all&shuffle=off"
+        );
+        assert_eq!(
+            Command::SetGroup {
+                pid: vec![PlayerId(1), PlayerId(2)],
+            }
+            .to_string(),
+            "group/set_group?pid=1,2"
+        );
+        assert_eq!(
+            Command::Browse {
+                sid: SourceId(1024),
+                cid: None,
+                range: None,
+            }
+            .to_string(),
+            "browse/browse?sid=1024"
+        );
+        assert_eq!(
+            Command::Search {
+                sid: SourceId(10),
+                search: "AC/DC & 100%".to_string(),
+                scid: SearchCriteriaId(1),
+                range: Range::page(0, 50),
+            }
+            .to_string(),
+            "browse/search?sid=10&search=AC/DC %26 100%25&scid=1&range=0,49"
+        );
+    }
+
+    #[test]
+    pub fn test_commands_are_listed_once() {
+        for (i, spec) in COMMANDS.iter().enumerate() {
+            assert_eq!(CommandSpec::find(spec.name), Some(&COMMANDS[i]));
+        }
+        assert_eq!(
+            CommandSpec::find("player/set_volume").unwrap().params,
+            &["pid", "level"]
+        );
+    }
+}
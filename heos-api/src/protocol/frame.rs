@@ -1,11 +1,8 @@
-use std::io::Cursor;
-
-use anyhow::{anyhow, Context};
-use bytes::Buf;
+use anyhow::Context;
 use serde_json::{Value as Json, Value};
 
-use crate::connection::{CommandResponse, EventResponse};
 use crate::error::HeosError;
+use crate::protocol::{CommandResponse, EventResponse};
 
 #[derive(Debug)]
 pub enum Frame {
@@ -15,51 +12,12 @@ pub enum Frame {
     Error(HeosError), // TODO add structure
 }
 
-pub struct Incomplete;
-
 impl Frame {
-    // checks only for a line. Could also check json but that would mean duplicate parsing?
-    pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Incomplete> {
-        if !src.has_remaining() {
-            return Err(Incomplete);
-        }
-        let _ = get_line(src)?;
-        Ok(())
-    }
-    pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame, HeosError> {
-        if let Ok(line) = get_line(src) {
-            let json = serde_json::from_slice::<Json>(line)
-                .context("Failed to parse heos response as json")?;
-            Frame::from_json(json)
-        } else {
-            Err(anyhow!("Connection reset by peer").into())
-        }
-    }
     pub fn from_json(value: Value) -> Result<Self, HeosError> {
         parsers::parse_response(value)
     }
 }
 
-// /// Find a line
-fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Incomplete> {
-    // Scan the bytes directly
-    let start = src.position() as usize;
-    // Scan to the second to last byte
-    let end = src.get_ref().len() - 1;
-
-    for i in start..end {
-        if src.get_ref()[i] == b'\r' && src.get_ref()[i + 1] == b'\n' {
-            // We found a line, update the position to be *after* the \n
-            src.set_position((i + 2) as u64);
-
-            // Return the line
-            return Ok(&src.get_ref()[start..i]);
-        }
-    }
-
-    Err(Incomplete)
-}
-
 mod parsers {
     use crate::error::HeosError::InvalidCommand;
     use crate::types::HeosErrorCode;
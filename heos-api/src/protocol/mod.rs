@@ -0,0 +1,454 @@
+//! The CLI protocol without any I/O.
+//!
+//! [`Protocol`] turns commands into bytes to send and the bytes received into
+//! responses and events, matching each response to the command it answers.
+//! Reading and writing the socket is up to the caller, see
+//! [`crate::Connection`] for tokio and `BlockingConnection` of the `blocking`
+//! feature for `std::net`. A minimal loop looks like this:
+//!
+//! ```
+//! use heos_api::protocol::{Output, Protocol};
+//!
+//! let mut protocol = Protocol::new();
+//! protocol.send_command("player/get_players").unwrap();
+//! let to_send = protocol.take_outgoing();
+//! assert_eq!(&to_send[..], b"heos://player/get_players\r\n");
+//!
+//! protocol.receive(br#"{"heos": {"command": "player/get_players", "result": "success", "message": ""}, "payload": []}"#);
+//! protocol.receive(b"\r\n");
+//! match protocol.poll_output().unwrap() {
+//!     Some(Output::Response(response)) => assert_eq!(response.unwrap().command_name, "player/get_players"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use bytes::BytesMut;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::error::HeosError;
+use crate::types::HeosErrorCode;
+use crate::HeosResult;
+
+pub use codec::*;
+pub use command::*;
+pub use frame::*;
+
+mod codec;
+mod command;
+mod frame;
+
+const REGISTER_FOR_CHANGE_EVENTS: &str = "system/register_for_change_events";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommandResponse {
+    pub command_name: String,
+    pub message: String,
+    pub payload: Value, // can be Null
+    pub options: Value, // can be Null
+}
+
+impl Display for CommandResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = serde_json::to_string_pretty(&self).unwrap();
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub command_name: String,
+    pub eid: HeosErrorCode,
+    pub text: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct EventResponse {
+    pub event_name: String,
+    pub message: String,
+}
+
+/// What the protocol made of the frames received.
+#[derive(Debug)]
+pub enum Output {
+    /// The response to a command sent, or the error HEOS answered with.
+    Response(HeosResult<CommandResponse>),
+    Event(EventResponse),
+}
+
+/// Whether HEOS sends events on this connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventState {
+    Off,
+    /// `system/register_for_change_events` was sent, HEOS did not answer yet.
+    Registering {
+        enable: bool,
+    },
+    On,
+}
+
+/// The state of one connection to HEOS, see the [module](self) docs.
+#[derive(Debug)]
+pub struct Protocol {
+    codec: HeosCodec,
+    incoming: BytesMut,
+    outgoing: BytesMut,
+    // the commands sent and not answered yet, oldest first
+    waiting: VecDeque<Waiting>,
+    events: EventState,
+}
+
+impl Protocol {
+    pub fn new() -> Self {
+        Protocol::with_codec(HeosCodec::new())
+    }
+
+    pub fn with_codec(codec: HeosCodec) -> Self {
+        Protocol {
+            codec,
+            incoming: BytesMut::with_capacity(16 * 1024),
+            outgoing: BytesMut::new(),
+            waiting: VecDeque::new(),
+            events: EventState::Off,
+        }
+    }
+
+    pub fn codec(&self) -> &HeosCodec {
+        &self.codec
+    }
+
+    /// Skips frames longer than `max_length` bytes from now on.
+    pub fn set_max_frame_length(&mut self, max_length: usize) {
+        self.codec = HeosCodec::with_max_length(max_length);
+    }
+
+    pub fn events(&self) -> EventState {
+        self.events
+    }
+
+    /// The number of commands sent and not answered yet.
+    pub fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Queues a command, e.g. `player/get_players`, take the bytes to send
+    /// with [`Protocol::take_outgoing`].
+    pub fn send_command(&mut self, command: &str) -> HeosResult<()> {
+        self.codec.encode_command(command, &mut self.outgoing)?;
+        let waiting = Waiting::new(command);
+        if waiting.name == REGISTER_FOR_CHANGE_EVENTS {
+            self.events = EventState::Registering {
+                enable: command.contains("enable=on"),
+            };
+        }
+        self.waiting.push_back(waiting);
+        Ok(())
+    }
+
+    /// Stops waiting for the commands sent, e.g. after a read timed out.
+    /// Their late responses are skipped instead of being taken for the ones
+    /// to the commands sent next.
+    pub fn forget_waiting(&mut self) {
+        for command in self.waiting.drain(..) {
+            debug!("Not waiting for {} anymore", command.name);
+        }
+    }
+
+    /// The bytes of the commands sent since the last call, empty if none.
+    pub fn take_outgoing(&mut self) -> BytesMut {
+        self.outgoing.split()
+    }
+
+    /// Adds bytes received from HEOS.
+    pub fn receive(&mut self, data: &[u8]) {
+        self.incoming.extend_from_slice(data);
+    }
+
+    /// The next frame received as it is, `None` until one is complete.
+    ///
+    /// Pass it on to [`Protocol::handle_frame`], or use
+    /// [`Protocol::poll_output`] for both.
+    pub fn poll_line(&mut self) -> HeosResult<Option<Line>> {
+        self.codec.decode_line(&mut self.incoming)
+    }
+
+    /// Like [`Protocol::poll_line`] after HEOS closed the connection, an
+    /// incomplete frame is an error.
+    pub fn poll_line_eof(&mut self) -> HeosResult<Option<Line>> {
+        self.codec.decode_line_eof(&mut self.incoming)
+    }
+
+    /// The next response or event received, `None` until there is one.
+    pub fn poll_output(&mut self) -> HeosResult<Option<Output>> {
+        while let Some(line) = self.poll_line()? {
+            if let Some(output) = self.handle_line(line) {
+                return Ok(Some(output));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Handles a line from [`Protocol::poll_line`].
+    pub fn handle_line(&mut self, line: Line) -> Option<Output> {
+        match Frame::from_json(line.json) {
+            Ok(frame) => self.handle_frame(frame),
+            Err(err) => {
+                warn!("Skipping a line that is no HEOS response: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Matches a response to the command it answers and keeps track of the
+    /// events. Responses to no command sent are skipped.
+    pub fn handle_frame(&mut self, frame: Frame) -> Option<Output> {
+        match frame {
+            Frame::UnderProcess(command) => {
+                debug!(">> waiting for {} to finish.", &command);
+                None
+            }
+            Frame::Event(event) => Some(Output::Event(event)),
+            Frame::Response(response) => {
+                if !self.answered(&response.command_name, &response.message) {
+                    return None;
+                }
+                if response.command_name == REGISTER_FOR_CHANGE_EVENTS {
+                    self.events = if response.message.contains("enable=on") {
+                        EventState::On
+                    } else {
+                        EventState::Off
+                    };
+                }
+                Some(Output::Response(Ok(response)))
+            }
+            Frame::Error(error) => {
+                let command = match &error {
+                    HeosError::InvalidCommand { command, .. } => command.clone(),
+                    _ => self.waiting.front()?.name.clone(),
+                };
+                // the parameters of a failed command are not kept
+                if !self.answered(&command, "") {
+                    return None;
+                }
+                if command == REGISTER_FOR_CHANGE_EVENTS {
+                    self.events = EventState::Off;
+                }
+                Some(Output::Response(Err(error)))
+            }
+        }
+    }
+
+    // removes the oldest command waiting this message answers, the ones sent
+    // before it won't get an answer anymore.
+    fn answered(&mut self, name: &str, message: &str) -> bool {
+        let params = params(message);
+        match self
+            .waiting
+            .iter()
+            .position(|waiting| waiting.answered_by(name, &params))
+        {
+            Some(position) => {
+                for command in self.waiting.drain(..position) {
+                    warn!("HEOS did not answer {}", command.name);
+                }
+                self.waiting.pop_front();
+                true
+            }
+            None => {
+                warn!(
+                    "Skipping a response to {}?{}, it was not asked for",
+                    name, message
+                );
+                false
+            }
+        }
+    }
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol::new()
+    }
+}
+
+// A command sent and not answered yet.
+#[derive(Debug)]
+struct Waiting {
+    // `player/get_volume` of `heos://player/get_volume?pid=1`
+    name: String,
+    params: Vec<(String, String)>,
+}
+
+impl Waiting {
+    fn new(command: &str) -> Self {
+        let command = command.strip_prefix("heos://").unwrap_or(command);
+        let (name, query) = command.split_once('?').unwrap_or((command, ""));
+        Waiting {
+            name: name.to_string(),
+            params: params(query),
+        }
+    }
+
+    // HEOS echoes the parameters of a command in the message of its
+    // response, one with another value answers another command.
+    fn answered_by(&self, name: &str, message: &[(String, String)]) -> bool {
+        self.name == name
+            && message.iter().all(|(key, value)| {
+                self.params
+                    .iter()
+                    .all(|(sent_key, sent)| sent_key != key || sent == value)
+            })
+    }
+}
+
+fn params(query: &str) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn receive(protocol: &mut Protocol, json: Value) {
+        protocol.receive(format!("{}\r\n", json).as_bytes());
+    }
+
+    fn response(command: &str, message: &str) -> Value {
+        json!({"heos": {"command": command, "result": "success", "message": message}})
+    }
+
+    #[test]
+    pub fn test_responses_are_matched() {
+        let mut protocol = Protocol::new();
+        protocol.send_command("player/get_volume?pid=1").unwrap();
+        protocol
+            .send_command("heos://player/get_mute?pid=1")
+            .unwrap();
+        assert_eq!(
+            &protocol.take_outgoing()[..],
+            b"heos://player/get_volume?pid=1\r\nheos://player/get_mute?pid=1\r\n"
+        );
+        assert!(protocol.take_outgoing().is_empty());
+
+        // a response from before, an event and one still in process
+        receive(&mut protocol, response("player/get_players", ""));
+        receive(
+            &mut protocol,
+            json!({"heos": {"command": "event/players_changed", "message": ""}}),
+        );
+        receive(
+            &mut protocol,
+            response("player/get_volume", "command under process&pid=1"),
+        );
+        receive(
+            &mut protocol,
+            response("player/get_volume", "pid=1&level=5"),
+        );
+        match protocol.poll_output().unwrap() {
+            Some(Output::Event(event)) => assert_eq!(event.event_name, "event/players_changed"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match protocol.poll_output().unwrap() {
+            Some(Output::Response(Ok(response))) => {
+                assert_eq!(response.message, "pid=1&level=5")
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(protocol.poll_output().unwrap().is_none());
+        assert_eq!(protocol.waiting(), 1);
+
+        receive(
+            &mut protocol,
+            json!({"heos": {"command": "player/get_mute", "result": "fail", "message": "eid=2&text=ID Not Valid"}}),
+        );
+        match protocol.poll_output().unwrap() {
+            Some(Output::Response(Err(HeosError::InvalidCommand { eid, .. }))) => {
+                assert_eq!(eid, HeosErrorCode::InvalidId)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(protocol.waiting(), 0);
+    }
+
+    #[test]
+    pub fn test_late_responses_are_skipped() {
+        let mut protocol = Protocol::new();
+        protocol
+            .send_command("player/set_volume?pid=1&level=5")
+            .unwrap();
+        // the read timed out, the caller went on with the next command
+        protocol.forget_waiting();
+        protocol
+            .send_command("player/set_volume?pid=1&level=10")
+            .unwrap();
+        assert_eq!(protocol.waiting(), 1);
+
+        receive(&mut protocol, response("player/set_volume", "pid=1&level=5"));
+        assert!(protocol.poll_output().unwrap().is_none());
+        assert_eq!(protocol.waiting(), 1);
+
+        receive(&mut protocol, response("player/set_volume", "pid=1&level=10"));
+        match protocol.poll_output().unwrap() {
+            Some(Output::Response(Ok(response))) => {
+                assert_eq!(response.message, "pid=1&level=10")
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(protocol.waiting(), 0);
+    }
+
+    #[test]
+    pub fn test_responses_are_matched_by_their_parameters() {
+        let mut protocol = Protocol::new();
+        protocol.send_command("player/get_volume?pid=1").unwrap();
+        protocol.send_command("player/get_volume?pid=2").unwrap();
+        protocol
+            .send_command("browse/search?sid=10&search=a%26b&scid=1")
+            .unwrap();
+
+        // HEOS dropped the first one
+        receive(&mut protocol, response("player/get_volume", "pid=2&level=7"));
+        assert!(protocol.poll_output().unwrap().is_some());
+        assert_eq!(protocol.waiting(), 1);
+
+        receive(
+            &mut protocol,
+            response("browse/search", "sid=10&search=a%26b&scid=1&returned=0&count=0"),
+        );
+        assert!(protocol.poll_output().unwrap().is_some());
+        assert_eq!(protocol.waiting(), 0);
+    }
+
+    #[test]
+    pub fn test_event_state() {
+        let mut protocol = Protocol::new();
+        assert_eq!(protocol.events(), EventState::Off);
+        protocol
+            .send_command("system/register_for_change_events?enable=on")
+            .unwrap();
+        assert_eq!(protocol.events(), EventState::Registering { enable: true });
+        receive(
+            &mut protocol,
+            response("system/register_for_change_events", "enable=on"),
+        );
+        assert!(protocol.poll_output().unwrap().is_some());
+        assert_eq!(protocol.events(), EventState::On);
+
+        protocol
+            .send_command("system/register_for_change_events?enable=off")
+            .unwrap();
+        receive(
+            &mut protocol,
+            response("system/register_for_change_events", "enable=off"),
+        );
+        assert!(protocol.poll_output().unwrap().is_some());
+        assert_eq!(protocol.events(), EventState::Off);
+    }
+}
@@ -0,0 +1,291 @@
+//! Recording the traffic with a device and serving it back.
+//!
+//! A [`Recorder`] writes every command sent and every line received to a JSONL
+//! file, one [`Record`] per line. [`Replay`] plays such a file back on a local
+//! port, so [`crate::HeosApi`] and [`crate::HeosDriver`] can run against it like
+//! against the device the recording came from. This turns bug reports into tests.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::HeosResult;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    #[serde(rename = "sent")]
+    Sent,
+    #[serde(rename = "received")]
+    Received,
+}
+
+/// One line of a recording.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// milliseconds since the unix epoch
+    pub time: u64,
+    /// the connections are numbered in the order they were opened
+    pub connection: usize,
+    pub direction: Direction,
+    /// the command without `heos://`, or the json received
+    pub line: String,
+}
+
+/// Writes the traffic of all connections it is given to one file.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(path: P) -> HeosResult<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        Ok(Recorder {
+            file: Arc::new(Mutex::new(file)),
+            connections: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub(crate) fn connection(&self) -> ConnectionRecorder {
+        ConnectionRecorder {
+            recorder: self.clone(),
+            connection: self.connections.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+
+    fn write(&self, record: &Record) {
+        let line = serde_json::to_string(record).expect("records are always valid json");
+        let mut file = self.file.lock().unwrap();
+        // flushed right away, the recording is most useful when something crashed.
+        if let Err(err) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            warn!("Failed to record traffic: {}", err);
+        }
+    }
+}
+
+/// The part of a [`Recorder`] for one connection.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionRecorder {
+    recorder: Recorder,
+    connection: usize,
+}
+
+impl ConnectionRecorder {
+    pub fn sent(&self, command: &str) {
+        self.record(Direction::Sent, command.to_string());
+    }
+
+    pub fn received(&self, line: &[u8]) {
+        self.record(
+            Direction::Received,
+            String::from_utf8_lossy(line).into_owned(),
+        );
+    }
+
+    pub fn recorder(&self) -> &Recorder {
+        &self.recorder
+    }
+
+    fn record(&self, direction: Direction, line: String) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or_default();
+        self.recorder.write(&Record {
+            time,
+            connection: self.connection,
+            direction,
+            line,
+        });
+    }
+}
+
+/// Reads a recording written by a [`Recorder`].
+pub fn read_records<P: AsRef<Path>>(path: P) -> HeosResult<Vec<Record>> {
+    let path = path.as_ref();
+    let file =
+        File::open(path).with_context(|| format!("Failed to open recording {}", path.display()))?;
+    let mut records = vec![];
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.context("Failed to read recording")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid record in line {}", number + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+struct ReplayState {
+    records: Vec<Record>,
+    // the index of the next record to play, across all connections.
+    cursor: AtomicUsize,
+    advanced: Notify,
+    connections: AtomicUsize,
+    errors: Mutex<Vec<String>>,
+}
+
+impl ReplayState {
+    // false if the replay was aborted.
+    async fn wait_for(&self, index: usize) -> bool {
+        loop {
+            let advanced = self.advanced.notified();
+            let cursor = self.cursor.load(Ordering::SeqCst);
+            if cursor >= index {
+                return cursor == index;
+            }
+            advanced.await;
+        }
+    }
+
+    fn advance(&self) {
+        self.cursor.fetch_add(1, Ordering::SeqCst);
+        self.advanced.notify_waiters();
+    }
+
+    // the other connections stop too, instead of waiting forever.
+    fn abort(&self, error: String) {
+        warn!("Replay: {}", error);
+        self.errors.lock().unwrap().push(error);
+        self.cursor.store(usize::MAX, Ordering::SeqCst);
+        self.advanced.notify_waiters();
+    }
+}
+
+/// Serves a recording on a local port. It stops when dropped.
+///
+/// The records are played in their recorded order across all connections,
+/// ignoring the time, so a replay is deterministic. A command that differs from
+/// the recorded one is an error and closes its connection.
+pub struct Replay {
+    addr: SocketAddr,
+    state: Arc<ReplayState>,
+    listener: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for Replay {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+impl Replay {
+    pub async fn open<P: AsRef<Path>>(path: P) -> HeosResult<Self> {
+        Replay::start(read_records(path)?).await
+    }
+
+    pub async fn start(records: Vec<Record>) -> HeosResult<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Failed to start the replay")?;
+        let addr = listener
+            .local_addr()
+            .context("Failed to start the replay")?;
+        let state = Arc::new(ReplayState {
+            records,
+            cursor: AtomicUsize::new(0),
+            advanced: Notify::new(),
+            connections: AtomicUsize::new(0),
+            errors: Mutex::new(vec![]),
+        });
+        let listener = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    let connection = state.connections.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(serve(socket, connection, state.clone()));
+                }
+            })
+        };
+        Ok(Replay {
+            addr,
+            state,
+            listener,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// True once every record has been played without errors.
+    pub fn is_finished(&self) -> bool {
+        self.state.cursor.load(Ordering::SeqCst) == self.state.records.len()
+    }
+
+    /// The commands that did not match the recording.
+    pub fn errors(&self) -> Vec<String> {
+        self.state.errors.lock().unwrap().clone()
+    }
+}
+
+async fn serve(socket: TcpStream, connection: usize, state: Arc<ReplayState>) {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    let records = state
+        .records
+        .iter()
+        .enumerate()
+        .filter(|(_, record)| record.connection == connection);
+    for (index, record) in records {
+        if !state.wait_for(index).await {
+            return;
+        }
+        match record.direction {
+            Direction::Sent => {
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    _ => {
+                        state.abort(format!(
+                            "connection {} closed, expected '{}'",
+                            connection, record.line
+                        ));
+                        return;
+                    }
+                };
+                let command = line.trim().trim_start_matches("heos://");
+                if command != record.line {
+                    state.abort(format!(
+                        "connection {} sent '{}', expected '{}'",
+                        connection, command, record.line
+                    ));
+                    return;
+                }
+            }
+            Direction::Received => {
+                let line = format!("{}\r\n", record.line);
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    state.abort(format!(
+                        "connection {} closed, expected to receive '{}'",
+                        connection, record.line
+                    ));
+                    return;
+                }
+            }
+        }
+        state.advance();
+    }
+    // the recording is over, anything else is unexpected.
+    if let Ok(Some(line)) = lines.next_line().await {
+        state.abort(format!(
+            "connection {} sent '{}' after the end of the recording",
+            connection,
+            line.trim()
+        ));
+    }
+}
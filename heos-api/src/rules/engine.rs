@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::rules::{Command, Evaluator, Occurrence, Rule, Snapshot};
+use crate::types::event::HeosEvent;
+use crate::{HeosDriver, HeosResult};
+
+// the clock of the `at` and `idle` triggers.
+const TICK: Duration = Duration::from_secs(30);
+const LOG_SIZE: usize = 100;
+
+/// A rule that fired.
+#[derive(Debug, Clone)]
+pub struct Firing {
+    pub time: NaiveDateTime,
+    pub rule: String,
+    /// the commands, not sent in a dry run
+    pub commands: Vec<String>,
+    pub dry_run: bool,
+    /// actions without players and failed commands
+    pub errors: Vec<String>,
+}
+
+/// Runs rules against a driver, keeping a log of the rules that fired.
+#[derive(Clone)]
+pub struct RuleEngine {
+    rules: Arc<Vec<Rule>>,
+    dry_run: bool,
+    log: Arc<Mutex<VecDeque<Firing>>>,
+}
+
+impl RuleEngine {
+    /// In a dry run the rules fire and get logged, but send no commands.
+    pub fn new(rules: Vec<Rule>, dry_run: bool) -> Self {
+        RuleEngine {
+            rules: Arc::new(rules),
+            dry_run,
+            log: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// The latest firings first.
+    pub fn log(&self) -> Vec<Firing> {
+        self.log.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Evaluates the rules on every event of the driver and the clock, until the
+    /// driver goes away. The actions of a rule run in a task of their own, so
+    /// slow commands don't hold up the events.
+    pub async fn run(self, driver: HeosDriver) {
+        let mut events = driver.subscribe();
+        let mut evaluator = Evaluator::new(self.rules.to_vec());
+        let mut clock = tokio::time::interval(TICK);
+        clock.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            let event: Option<HeosEvent> = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => Some(event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Rules missed {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                _ = clock.tick() => None,
+            };
+            let occurrence = match &event {
+                Some(event) => Occurrence::Event(event),
+                None => Occurrence::Tick,
+            };
+            let snapshot = snapshot(&driver);
+            let now = Local::now().naive_local();
+            let fired: Vec<Rule> = evaluator
+                .evaluate(occurrence, &snapshot, now)
+                .into_iter()
+                .cloned()
+                .collect();
+            for rule in fired {
+                let engine = self.clone();
+                let driver = driver.clone();
+                let snapshot = snapshot.clone();
+                tokio::spawn(async move {
+                    let firing = engine.fire(&driver, &rule, &snapshot, now).await;
+                    let mut log = engine.log.lock().unwrap();
+                    log.push_front(firing);
+                    log.truncate(LOG_SIZE);
+                });
+            }
+        }
+    }
+
+    async fn fire(
+        &self,
+        driver: &HeosDriver,
+        rule: &Rule,
+        snapshot: &Snapshot,
+        now: NaiveDateTime,
+    ) -> Firing {
+        info!("Rule '{}' fired", rule.name);
+        let mut firing = Firing {
+            time: now,
+            rule: rule.name.clone(),
+            commands: vec![],
+            dry_run: self.dry_run,
+            errors: vec![],
+        };
+        for action in &rule.actions {
+            let commands = match action.commands(snapshot) {
+                Ok(commands) => commands,
+                Err(err) => {
+                    firing.errors.push(err);
+                    continue;
+                }
+            };
+            for command in commands {
+                firing.commands.push(command.to_string());
+                if self.dry_run {
+                    continue;
+                }
+                if let Err(err) = execute(driver, command.clone()).await {
+                    warn!("Rule '{}' failed to {}: {}", rule.name, command, err);
+                    firing.errors.push(format!("{}: {}", command, err));
+                }
+            }
+        }
+        firing
+    }
+}
+
+fn snapshot(driver: &HeosDriver) -> Snapshot {
+    Snapshot {
+        players: driver.players(),
+        groups: driver.groups(),
+        source_names: driver
+            .music_sources()
+            .into_iter()
+            .map(|source| (source.sid, source.name))
+            .collect(),
+    }
+}
+
+async fn execute(driver: &HeosDriver, command: Command) -> HeosResult<()> {
+    match command {
+        Command::SetPlayState(pid, state) => driver.set_play_state(pid, state).await,
+        Command::PlayNext(pid) => driver.play_next(pid).await,
+        Command::PlayPrevious(pid) => driver.play_previous(pid).await,
+        Command::SetVolume(pid, level) => driver.set_volume(pid, level).await.map(|_| ()),
+        Command::SetMute(pid, state) => driver.set_mute(pid, state).await,
+        Command::SetPlayMode(pid, mode) => driver.set_play_mode(pid, mode).await,
+        Command::CreateGroup(leader, members) => driver.create_group(leader, members).await,
+        Command::DeleteGroup(leader) => driver.delete_group(leader).await,
+    }
+}
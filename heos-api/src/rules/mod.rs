@@ -0,0 +1,592 @@
+//! Automation rules: when something happens and some conditions hold, run
+//! driver commands.
+//!
+//! ```yaml
+//! - name: Dining joins Kitchen in the evening
+//!   when: { started: Kitchen }
+//!   if:
+//!     - after: "18:00"
+//!   then:
+//!     - join: { leader: Kitchen, members: [Dining] }
+//! - name: TV volume
+//!   when: { now_playing: Living }
+//!   if:
+//!     - media: { player: Living, contains: "inputs/tv_audio" }
+//!   then:
+//!     - volume: { player: Living, level: 30 }
+//! - name: Stop all when nobody listens
+//!   when: { idle: 2h }
+//!   then:
+//!     - stop: all
+//! ```
+//!
+//! Players are given by name or pid, `all` means every player. The rules are
+//! read with [`read_rules`] and run by a [`RuleEngine`].
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{NaiveDateTime, NaiveTime};
+
+use crate::driver::time_of_day;
+use crate::types::event::HeosEvent;
+use crate::types::group::Group;
+use crate::types::player::{HeosPlayer, PlayState};
+use crate::types::{Level, OnOrOff, PlayMode, PlayerId, Repeat, Shuffle, SourceId};
+use crate::HeosResult;
+
+mod engine;
+
+pub use engine::{Firing, RuleEngine};
+
+// a rule does not fire again right after it fired, its own actions cause events.
+const COOLDOWN_SECONDS: i64 = 5;
+
+/// Reads rules written like the ones above.
+pub fn read_rules<R: Read>(reader: R) -> HeosResult<Vec<Rule>> {
+    // `stop: all` instead of serde_yaml's `!stop all`
+    let rules = serde_yaml::with::singleton_map_recursive::deserialize(
+        serde_yaml::Deserializer::from_reader(reader),
+    )
+    .context("Failed to read rules")?;
+    Ok(rules)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub when: Trigger,
+    #[serde(default, rename = "if")]
+    pub conditions: Vec<Condition>,
+    #[serde(rename = "then")]
+    pub actions: Vec<Action>,
+}
+
+/// The name or pid of a player, or `all`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct PlayerRef(pub String);
+
+impl PlayerRef {
+    pub fn matches(&self, player: &HeosPlayer) -> bool {
+        self.0 == "all" || self.0 == player.name || self.0 == player.player_id.to_string()
+    }
+
+    fn matches_id(&self, player_id: PlayerId, players: &[HeosPlayer]) -> bool {
+        players
+            .iter()
+            .any(|player| player.player_id == player_id && self.matches(player))
+    }
+
+    fn resolve<'a>(&self, players: &'a [HeosPlayer]) -> Vec<&'a HeosPlayer> {
+        players
+            .iter()
+            .filter(|player| self.matches(player))
+            .collect()
+    }
+}
+
+impl fmt::Display for PlayerRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Started(PlayerRef),
+    Paused(PlayerRef),
+    Stopped(PlayerRef),
+    /// the track, station or input changed
+    NowPlaying(PlayerRef),
+    VolumeChanged(PlayerRef),
+    GroupsChanged,
+    /// every day at this time
+    At(#[serde(with = "time_of_day")] NaiveTime),
+    /// nothing played for this long, e.g. `90s`, `30m` or `2h`
+    Idle(#[serde(with = "duration")] Duration),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    After(#[serde(with = "time_of_day")] NaiveTime),
+    Before(#[serde(with = "time_of_day")] NaiveTime),
+    /// one of the players plays
+    Playing(PlayerRef),
+    /// none of the players plays
+    NotPlaying(PlayerRef),
+    Grouped(PlayerRef),
+    NotGrouped(PlayerRef),
+    /// the song, album, artist, station or media id contains the text, ignoring case
+    Media {
+        player: PlayerRef,
+        contains: String,
+    },
+    /// plays from the music source with this name or sid
+    Source {
+        player: PlayerRef,
+        source: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Play(PlayerRef),
+    Pause(PlayerRef),
+    Stop(PlayerRef),
+    Next(PlayerRef),
+    Previous(PlayerRef),
+    Volume {
+        player: PlayerRef,
+        level: Level,
+    },
+    Mute(PlayerRef),
+    Unmute(PlayerRef),
+    PlayMode {
+        player: PlayerRef,
+        repeat: Repeat,
+        shuffle: Shuffle,
+    },
+    Join {
+        leader: PlayerRef,
+        members: Vec<PlayerRef>,
+    },
+    Ungroup(PlayerRef),
+}
+
+/// An action for a single player, ready for the driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    SetPlayState(PlayerId, PlayState),
+    PlayNext(PlayerId),
+    PlayPrevious(PlayerId),
+    SetVolume(PlayerId, Level),
+    SetMute(PlayerId, OnOrOff),
+    SetPlayMode(PlayerId, PlayMode),
+    CreateGroup(PlayerId, Vec<PlayerId>),
+    DeleteGroup(PlayerId),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::SetPlayState(pid, state) => {
+                write!(f, "set play state of {} to {}", pid, state)
+            }
+            Command::PlayNext(pid) => write!(f, "play next on {}", pid),
+            Command::PlayPrevious(pid) => write!(f, "play previous on {}", pid),
+            Command::SetVolume(pid, level) => write!(f, "set volume of {} to {}", pid, level),
+            Command::SetMute(pid, state) => write!(f, "set mute of {} to {}", pid, state),
+            Command::SetPlayMode(pid, mode) => write!(
+                f,
+                "set play mode of {} to repeat {}, shuffle {}",
+                pid, mode.repeat, mode.shuffle
+            ),
+            Command::CreateGroup(leader, members) => {
+                write!(f, "group {:?} with leader {}", members, leader)
+            }
+            Command::DeleteGroup(leader) => write!(f, "ungroup {}", leader),
+        }
+    }
+}
+
+/// The state of the driver the rules see.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub players: Vec<HeosPlayer>,
+    pub groups: Vec<Group>,
+    pub source_names: HashMap<SourceId, String>,
+}
+
+impl Action {
+    /// The commands for the players the action refers to right now.
+    pub fn commands(&self, snapshot: &Snapshot) -> Result<Vec<Command>, String> {
+        let players = |player: &PlayerRef| {
+            let found = player.resolve(&snapshot.players);
+            if found.is_empty() {
+                Err(format!("no player '{}'", player))
+            } else {
+                Ok(found.into_iter().map(|p| p.player_id).collect::<Vec<_>>())
+            }
+        };
+        let each = |player: &PlayerRef, command: &dyn Fn(PlayerId) -> Command| {
+            players(player).map(|pids| pids.into_iter().map(command).collect::<Vec<_>>())
+        };
+        match self {
+            Action::Play(p) => each(p, &|pid| Command::SetPlayState(pid, PlayState::Play)),
+            Action::Pause(p) => each(p, &|pid| Command::SetPlayState(pid, PlayState::Pause)),
+            Action::Stop(p) => each(p, &|pid| Command::SetPlayState(pid, PlayState::Stop)),
+            Action::Next(p) => each(p, &Command::PlayNext),
+            Action::Previous(p) => each(p, &Command::PlayPrevious),
+            Action::Volume { player, level } => {
+                each(player, &|pid| Command::SetVolume(pid, *level))
+            }
+            Action::Mute(p) => each(p, &|pid| Command::SetMute(pid, OnOrOff::On)),
+            Action::Unmute(p) => each(p, &|pid| Command::SetMute(pid, OnOrOff::Off)),
+            Action::PlayMode {
+                player,
+                repeat,
+                shuffle,
+            } => each(player, &|pid| {
+                Command::SetPlayMode(
+                    pid,
+                    PlayMode {
+                        repeat: repeat.clone(),
+                        shuffle: shuffle.clone(),
+                    },
+                )
+            }),
+            Action::Join { leader, members } => {
+                let leader = match players(leader)?.as_slice() {
+                    [leader] => *leader,
+                    _ => return Err(format!("'{}' is not a single player", leader)),
+                };
+                let mut pids = vec![];
+                for member in members {
+                    pids.extend(players(member)?.into_iter().filter(|pid| *pid != leader));
+                }
+                Ok(vec![Command::CreateGroup(leader, pids)])
+            }
+            Action::Ungroup(p) => each(p, &Command::DeleteGroup),
+        }
+    }
+}
+
+/// What the rules are evaluated on.
+#[derive(Debug, Clone, Copy)]
+pub enum Occurrence<'a> {
+    /// after the driver handled the event
+    Event(&'a HeosEvent),
+    /// the clock, for the `at` and `idle` triggers
+    Tick,
+}
+
+impl Trigger {
+    fn fired_by(&self, event: &HeosEvent, players: &[HeosPlayer]) -> bool {
+        match (self, event) {
+            (Trigger::Started(p), HeosEvent::PlayerStateChanged { player_id, state })
+                if *state == PlayState::Play =>
+            {
+                p.matches_id(*player_id, players)
+            }
+            (Trigger::Paused(p), HeosEvent::PlayerStateChanged { player_id, state })
+                if *state == PlayState::Pause =>
+            {
+                p.matches_id(*player_id, players)
+            }
+            (Trigger::Stopped(p), HeosEvent::PlayerStateChanged { player_id, state })
+                if *state == PlayState::Stop =>
+            {
+                p.matches_id(*player_id, players)
+            }
+            (Trigger::NowPlaying(p), HeosEvent::PlayerNowPlayingChanged { player_id }) => {
+                p.matches_id(*player_id, players)
+            }
+            (Trigger::VolumeChanged(p), HeosEvent::PlayerVolumeChanged { player_id, .. }) => {
+                p.matches_id(*player_id, players)
+            }
+            (Trigger::GroupsChanged, HeosEvent::GroupChanged) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, snapshot: &Snapshot, now: NaiveDateTime) -> bool {
+        let players = |player: &PlayerRef| player.resolve(&snapshot.players).into_iter();
+        match self {
+            Condition::After(time) => now.time() >= *time,
+            Condition::Before(time) => now.time() < *time,
+            Condition::Playing(p) => players(p).any(|p| p.play_state == PlayState::Play),
+            Condition::NotPlaying(p) => !players(p).any(|p| p.play_state == PlayState::Play),
+            Condition::Grouped(p) => players(p).any(|p| p.in_group.is_some()),
+            Condition::NotGrouped(p) => !players(p).any(|p| p.in_group.is_some()),
+            Condition::Media { player, contains } => {
+                let contains = contains.to_lowercase();
+                players(player)
+                    .filter_map(|p| p.now_playing.as_ref())
+                    .any(|media| {
+                        [
+                            Some(&media.song),
+                            Some(&media.album),
+                            Some(&media.artist),
+                            media.station.as_ref(),
+                            Some(&media.mid.0),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .any(|text| text.to_lowercase().contains(&contains))
+                    })
+            }
+            Condition::Source { player, source } => players(player)
+                .filter_map(|p| p.now_playing.as_ref())
+                .any(|media| {
+                    media.sid.to_string() == *source
+                        || snapshot.source_names.get(&media.sid) == Some(source)
+                }),
+        }
+    }
+}
+
+/// Decides which rules fire, it keeps the little state the triggers need.
+#[derive(Debug)]
+pub struct Evaluator {
+    rules: Vec<Rule>,
+    last_tick: Option<NaiveDateTime>,
+    last_playing: Option<NaiveDateTime>,
+    idle_fired: HashSet<usize>,
+    last_fired: HashMap<usize, NaiveDateTime>,
+}
+
+impl Evaluator {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Evaluator {
+            rules,
+            last_tick: None,
+            last_playing: None,
+            idle_fired: HashSet::new(),
+            last_fired: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The rules that fire, with their conditions holding.
+    pub fn evaluate(
+        &mut self,
+        occurrence: Occurrence,
+        snapshot: &Snapshot,
+        now: NaiveDateTime,
+    ) -> Vec<&Rule> {
+        let playing = snapshot
+            .players
+            .iter()
+            .any(|player| player.play_state == PlayState::Play);
+        if playing || self.last_playing.is_none() {
+            self.last_playing = Some(now);
+        }
+        if playing {
+            self.idle_fired.clear();
+        }
+        let mut fired = vec![];
+        for (index, rule) in self.rules.iter().enumerate() {
+            let triggered = match (occurrence, &rule.when) {
+                (Occurrence::Event(event), trigger) => trigger.fired_by(event, &snapshot.players),
+                (Occurrence::Tick, Trigger::At(time)) => match self.last_tick {
+                    Some(last_tick) => passed(*time, last_tick, now),
+                    None => false,
+                },
+                (Occurrence::Tick, Trigger::Idle(idle)) => {
+                    let silent = self
+                        .last_playing
+                        .and_then(|last| (now - last).to_std().ok());
+                    !playing
+                        && !self.idle_fired.contains(&index)
+                        && matches!(silent, Some(silent) if silent >= *idle)
+                }
+                (Occurrence::Tick, _) => false,
+            };
+            if !triggered || !rule.conditions.iter().all(|c| c.holds(snapshot, now)) {
+                continue;
+            }
+            if let Some(last_fired) = self.last_fired.get(&index) {
+                if now - *last_fired < chrono::Duration::seconds(COOLDOWN_SECONDS) {
+                    continue;
+                }
+            }
+            if let Trigger::Idle(_) = rule.when {
+                self.idle_fired.insert(index);
+            }
+            self.last_fired.insert(index, now);
+            fired.push(rule);
+        }
+        if let Occurrence::Tick = occurrence {
+            self.last_tick = Some(now);
+        }
+        fired
+    }
+}
+
+// true if `time` of some day lies in (from, until].
+fn passed(time: NaiveTime, from: NaiveDateTime, until: NaiveDateTime) -> bool {
+    let mut day = from.date();
+    while day <= until.date() {
+        let at = day.and_time(time);
+        if from < at && at <= until {
+            return true;
+        }
+        day = match day.succ_opt() {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+    false
+}
+
+mod duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{}s", duration.as_secs()))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        parse(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid duration '{}'", s)))
+    }
+
+    pub fn parse(s: &str) -> Option<Duration> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit())?;
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number.parse().ok()?;
+        let seconds = match unit.trim() {
+            "s" => number,
+            "m" | "min" => number * 60,
+            "h" => number * 60 * 60,
+            "d" => number * 24 * 60 * 60,
+            _ => return None,
+        };
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::types::player::{MediaType, NowPlayingMedia};
+    use crate::types::{MediaId, QueueId};
+
+    use super::*;
+
+    const RULES: &str = r#"
+- name: Dining joins Kitchen in the evening
+  when: { started: Kitchen }
+  if:
+    - after: "18:00"
+  then:
+    - join: { leader: Kitchen, members: [Dining] }
+- name: TV volume
+  when: { now_playing: Living }
+  if:
+    - media: { player: Living, contains: "inputs/tv_audio" }
+  then:
+    - volume: { player: Living, level: 30 }
+- name: Stop all when nobody listens
+  when: { idle: 2h }
+  then:
+    - stop: all
+"#;
+
+    fn player(pid: i64, name: &str, play_state: PlayState) -> HeosPlayer {
+        HeosPlayer {
+            play_state,
+            ..HeosPlayer::test_player(pid, name)
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 11, 20)
+            .and_then(|day| day.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    #[test]
+    pub fn test_rules() {
+        let rules = read_rules(RULES.as_bytes()).unwrap();
+        assert_eq!(rules[2].when, Trigger::Idle(Duration::from_secs(7200)));
+        let mut evaluator = Evaluator::new(rules);
+
+        let mut living = player(3, "Living", PlayState::Play);
+        living.now_playing = Some(NowPlayingMedia {
+            media_type: MediaType::Station,
+            song: "".to_string(),
+            album: "".to_string(),
+            artist: "".to_string(),
+            image_url: "".to_string(),
+            station: Some("TV".to_string()),
+            mid: MediaId::from("inputs/tv_audio"),
+            qid: QueueId(1),
+            sid: SourceId(1027),
+            album_id: "".to_string(),
+            extra: Default::default(),
+        });
+        let mut snapshot = Snapshot {
+            players: vec![
+                player(1, "Kitchen", PlayState::Play),
+                player(2, "Dining", PlayState::Stop),
+                living,
+            ],
+            ..Snapshot::default()
+        };
+        let started = HeosEvent::PlayerStateChanged {
+            player_id: PlayerId(1),
+            state: PlayState::Play,
+        };
+
+        // too early
+        let fired = evaluator.evaluate(Occurrence::Event(&started), &snapshot, at(17, 0));
+        assert!(fired.is_empty());
+        let fired = evaluator.evaluate(Occurrence::Event(&started), &snapshot, at(18, 30));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            fired[0].actions[0].commands(&snapshot).unwrap(),
+            vec![Command::CreateGroup(PlayerId(1), vec![PlayerId(2)])]
+        );
+
+        let now_playing = HeosEvent::PlayerNowPlayingChanged {
+            player_id: PlayerId(3),
+        };
+        let fired = evaluator.evaluate(Occurrence::Event(&now_playing), &snapshot, at(18, 31));
+        assert_eq!(
+            fired[0].actions[0].commands(&snapshot).unwrap(),
+            vec![Command::SetVolume(PlayerId(3), 30)]
+        );
+
+        for player in snapshot.players.iter_mut() {
+            player.play_state = PlayState::Pause;
+        }
+        evaluator.evaluate(Occurrence::Tick, &snapshot, at(19, 0));
+        assert!(evaluator
+            .evaluate(Occurrence::Tick, &snapshot, at(20, 0))
+            .is_empty());
+        let fired = evaluator.evaluate(Occurrence::Tick, &snapshot, at(21, 0));
+        assert_eq!(fired[0].name, "Stop all when nobody listens");
+        assert_eq!(fired[0].actions[0].commands(&snapshot).unwrap().len(), 3);
+        // once per silence
+        assert!(evaluator
+            .evaluate(Occurrence::Tick, &snapshot, at(23, 0))
+            .is_empty());
+    }
+
+    #[test]
+    pub fn test_at_passes_midnight() {
+        let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        assert!(passed(
+            midnight,
+            at(23, 59),
+            at(23, 59) + chrono::Duration::minutes(2)
+        ));
+        assert!(!passed(midnight, at(0, 0), at(0, 1)));
+        assert_eq!(duration::parse("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(duration::parse("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(duration::parse("soon"), None);
+    }
+}
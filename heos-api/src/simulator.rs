@@ -0,0 +1,842 @@
+//! A simulated HEOS device for tests and demos.
+//!
+//! It speaks the CLI protocol on a local tcp port, keeps players, groups,
+//! queues and a small music library in memory and sends change events to
+//! every connection that registered for them, like a real device does.
+//!
+//! ```no_run
+//! # async fn run() -> heos_api::HeosResult<()> {
+//! let device = heos_api::simulator::SimulatedDevice::start().await?;
+//! let driver = heos_api::HeosDriver::new(device.addr()).await?;
+//! device.progress(heos_api::types::PlayerId(1), 42_000, 180_000);
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::types::{GroupId, Level, PlayerId, SourceId};
+use crate::HeosResult;
+
+pub const LIBRARY_SID: SourceId = SourceId(1024);
+
+#[derive(Debug, Clone)]
+pub struct SimTrack {
+    pub song: String,
+    pub album: String,
+    pub artist: String,
+    pub mid: String,
+    pub duration: u64,
+}
+
+impl SimTrack {
+    pub fn new(artist: &str, album: &str, song: &str) -> Self {
+        SimTrack {
+            song: song.to_string(),
+            album: album.to_string(),
+            artist: artist.to_string(),
+            mid: format!("{}-{}", album, song)
+                .to_lowercase()
+                .replace(' ', "-"),
+            duration: 200_000,
+        }
+    }
+
+    fn album_id(&self) -> String {
+        format!("album-{}", self.album.to_lowercase().replace(' ', "-"))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimPlayer {
+    pub pid: PlayerId,
+    pub name: String,
+    pub model: String,
+    pub volume: Level,
+    pub mute: bool,
+    pub state: String,
+    pub repeat: String,
+    pub shuffle: String,
+    pub queue: Vec<SimTrack>,
+    pub position: usize,
+}
+
+impl SimPlayer {
+    pub fn new(pid: PlayerId, name: &str) -> Self {
+        SimPlayer {
+            pid,
+            name: name.to_string(),
+            model: "HEOS 1".to_string(),
+            volume: 20,
+            mute: false,
+            state: "stop".to_string(),
+            repeat: "off".to_string(),
+            shuffle: "off".to_string(),
+            queue: vec![],
+            position: 0,
+        }
+    }
+}
+
+/// Everything the simulated device knows.
+#[derive(Debug, Clone, Default)]
+pub struct SimState {
+    pub players: BTreeMap<PlayerId, SimPlayer>,
+    /// leader -> members, without the leader.
+    pub groups: BTreeMap<GroupId, Vec<PlayerId>>,
+    pub library: Vec<SimTrack>,
+    /// every command received, without `heos://`
+    pub commands: Vec<String>,
+    /// the number of commands still to refuse with eid 13, as a busy device does
+    pub busy: usize,
+}
+
+impl SimState {
+    /// Two players, one of them playing from a small library.
+    pub fn example() -> Self {
+        let library = vec![
+            SimTrack::new("Fleetwood Mac", "Rumours", "Dreams"),
+            SimTrack::new("Fleetwood Mac", "Rumours", "The Chain"),
+            SimTrack::new("Miles Davis", "Kind of Blue", "So What"),
+            SimTrack::new("Miles Davis", "Kind of Blue", "Blue in Green"),
+        ];
+        let mut living_room = SimPlayer::new(PlayerId(1), "Living Room");
+        living_room.state = "play".to_string();
+        living_room.queue = library[..2].to_vec();
+        let kitchen = SimPlayer::new(PlayerId(2), "Kitchen");
+        SimState {
+            players: BTreeMap::from([(PlayerId(1), living_room), (PlayerId(2), kitchen)]),
+            groups: BTreeMap::new(),
+            library,
+            commands: vec![],
+            busy: 0,
+        }
+    }
+
+    fn group_of(&self, pid: PlayerId) -> Option<GroupId> {
+        self.groups
+            .iter()
+            .find(|(gid, members)| **gid == pid.group() || members.contains(&pid))
+            .map(|(gid, _)| *gid)
+    }
+}
+
+/// A running simulated device. It stops when dropped.
+pub struct SimulatedDevice {
+    addr: SocketAddr,
+    state: Arc<Mutex<SimState>>,
+    events: broadcast::Sender<String>,
+    listener: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for SimulatedDevice {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+impl SimulatedDevice {
+    pub async fn start() -> HeosResult<Self> {
+        SimulatedDevice::with_state(SimState::example()).await
+    }
+
+    pub async fn with_state(state: SimState) -> HeosResult<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Failed to start the simulated device")?;
+        let addr = listener
+            .local_addr()
+            .context("Failed to start the simulated device")?;
+        let state = Arc::new(Mutex::new(state));
+        let (events, _) = broadcast::channel(64);
+        let listener = {
+            let state = state.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(serve(socket, state.clone(), events.clone()));
+                }
+            })
+        };
+        Ok(SimulatedDevice {
+            addr,
+            state,
+            events,
+            listener,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn state(&self) -> SimState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Changes the state without events, e.g. to set up a test.
+    pub fn update<F: FnOnce(&mut SimState)>(&self, f: F) {
+        f(&mut self.state.lock().unwrap())
+    }
+
+    /// Sends an event to all registered connections, e.g.
+    /// `emit("event/player_volume_changed", "pid=1&level=5&mute=off")`.
+    pub fn emit(&self, event: &str, message: &str) {
+        let _ = self.events.send(event_line(event, message));
+    }
+
+    pub fn progress(&self, pid: PlayerId, cur_pos: u64, duration: u64) {
+        self.emit(
+            "event/player_now_playing_progress",
+            &format!("pid={}&cur_pos={}&duration={}", pid, cur_pos, duration),
+        );
+    }
+}
+
+async fn serve(socket: TcpStream, state: Arc<Mutex<SimState>>, events: broadcast::Sender<String>) {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscription = events.subscribe();
+    let mut registered = false;
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    _ => return,
+                };
+                let command = line.trim().trim_start_matches("heos://").to_string();
+                if command.is_empty() {
+                    continue;
+                }
+                debug!("simulator received {}", &command);
+                if command.starts_with("system/register_for_change_events") {
+                    registered = command.ends_with("enable=on");
+                }
+                let (response, emitted) = {
+                    let mut state = state.lock().unwrap();
+                    state.commands.push(command.clone());
+                    execute(&mut state, &command)
+                };
+                if writer.write_all(response.as_bytes()).await.is_err() {
+                    return;
+                }
+                for (event, message) in emitted {
+                    let _ = events.send(event_line(&event, &message));
+                }
+            }
+            event = subscription.recv() => {
+                match event {
+                    Ok(event) if registered => {
+                        if writer.write_all(event.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        }
+    }
+}
+
+fn event_line(event: &str, message: &str) -> String {
+    format!(
+        "{}\r\n",
+        json!({"heos": {"command": event, "message": message}})
+    )
+}
+
+type Events = Vec<(String, String)>;
+
+fn success(command: &str, message: String, payload: Option<Value>) -> String {
+    let mut response = json!({
+        "heos": {"command": command, "result": "success", "message": message}
+    });
+    if let Some(payload) = payload {
+        response["payload"] = payload;
+    }
+    format!("{}\r\n", response)
+}
+
+fn failure(command: &str, eid: u8, text: &str) -> String {
+    format!(
+        "{}\r\n",
+        json!({"heos": {
+            "command": command,
+            "result": "fail",
+            "message": format!("eid={}&text={}", eid, text)
+        }})
+    )
+}
+
+// HEOS only encodes these.
+fn decode(value: &str) -> String {
+    value
+        .replace("%26", "&")
+        .replace("%3D", "=")
+        .replace("%25", "%")
+}
+
+fn parse_command(command: &str) -> (&str, BTreeMap<String, String>) {
+    let (name, query) = command.split_once('?').unwrap_or((command, ""));
+    let params = query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_string(), decode(value)))
+        .collect();
+    (name, params)
+}
+
+fn execute(state: &mut SimState, command: &str) -> (String, Events) {
+    let (name, params) = parse_command(command);
+    let message = command.split_once('?').map_or("", |(_, query)| query);
+    let pid = params
+        .get("pid")
+        .and_then(|pid| pid.parse::<PlayerId>().ok());
+    let level = params
+        .get("level")
+        .and_then(|level| level.parse::<Level>().ok());
+    let mut events = vec![];
+
+    if state.busy > 0 && !name.starts_with("system/") {
+        state.busy -= 1;
+        return (failure(name, 13, "Processing previous command"), events);
+    }
+    if name.starts_with("player/") && name != "player/get_players" {
+        match pid {
+            Some(pid) if state.players.contains_key(&pid) => {}
+            _ => return (failure(name, 2, "Invalid ID"), events),
+        }
+    }
+    let response = match name {
+        "system/register_for_change_events"
+        | "system/prettify_json_response"
+        | "system/heart_beat" => success(name, message.to_string(), None),
+        "player/get_players" => {
+            let players: Vec<Value> = state
+                .players
+                .values()
+                .map(|player| {
+                    let mut info = json!({
+                        "name": player.name,
+                        "pid": player.pid,
+                        "model": player.model,
+                        "version": "1.583.147",
+                        "ip": "127.0.0.1",
+                        "network": "wired",
+                        "lineout": 0,
+                    });
+                    if let Some(gid) = state.group_of(player.pid) {
+                        info["gid"] = json!(gid);
+                    }
+                    info
+                })
+                .collect();
+            success(name, String::new(), Some(Value::Array(players)))
+        }
+        "player/get_play_state" | "player/set_play_state" => {
+            let player = state.players.get_mut(&pid.unwrap()).unwrap();
+            if let Some(new_state) = params.get("state") {
+                player.state = new_state.clone();
+                events.push((
+                    "event/player_state_changed".to_string(),
+                    format!("pid={}&state={}", player.pid, player.state),
+                ));
+            }
+            success(
+                name,
+                format!("pid={}&state={}", player.pid, player.state),
+                None,
+            )
+        }
+        "player/get_volume" | "player/set_volume" | "player/get_mute" | "player/set_mute" => {
+            let player = state.players.get_mut(&pid.unwrap()).unwrap();
+            let mut changed = false;
+            if name == "player/set_volume" {
+                match level {
+                    Some(level) if level <= 100 => player.volume = level,
+                    _ => return (failure(name, 9, "Parameter out of range"), events),
+                }
+                changed = true;
+            }
+            if let Some(mute) = params.get("state") {
+                player.mute = mute == "on";
+                changed = true;
+            }
+            let mute = if player.mute { "on" } else { "off" };
+            if changed {
+                events.push((
+                    "event/player_volume_changed".to_string(),
+                    format!("pid={}&level={}&mute={}", player.pid, player.volume, mute),
+                ));
+            }
+            let message = if name.ends_with("mute") {
+                format!("pid={}&state={}", player.pid, mute)
+            } else {
+                format!("pid={}&level={}", player.pid, player.volume)
+            };
+            success(name, message, None)
+        }
+        "player/get_play_mode" | "player/set_play_mode" => {
+            let player = state.players.get_mut(&pid.unwrap()).unwrap();
+            if let Some(repeat) = params.get("repeat") {
+                player.repeat = repeat.clone();
+                events.push((
+                    "event/repeat_mode_changed".to_string(),
+                    format!("pid={}&repeat={}", player.pid, repeat),
+                ));
+            }
+            if let Some(shuffle) = params.get("shuffle") {
+                player.shuffle = shuffle.clone();
+                events.push((
+                    "event/shuffle_mode_changed".to_string(),
+                    format!("pid={}&shuffle={}", player.pid, shuffle),
+                ));
+            }
+            success(
+                name,
+                format!(
+                    "pid={}&repeat={}&shuffle={}",
+                    player.pid, player.repeat, player.shuffle
+                ),
+                None,
+            )
+        }
+        "player/get_now_playing_media" => {
+            let player = &state.players[&pid.unwrap()];
+            let payload = match player.queue.get(player.position) {
+                Some(track) => json!({
+                    "type": "song",
+                    "song": track.song,
+                    "album": track.album,
+                    "artist": track.artist,
+                    "image_url": "",
+                    "mid": track.mid,
+                    "qid": player.position + 1,
+                    "sid": LIBRARY_SID,
+                    "album_id": track.album_id(),
+                }),
+                None => json!({}),
+            };
+            success(name, format!("pid={}", player.pid), Some(payload))
+        }
+        "player/play_next" | "player/play_previous" => {
+            let player = state.players.get_mut(&pid.unwrap()).unwrap();
+            if player.queue.is_empty() {
+                return (failure(name, 4, "Requested data not available"), events);
+            }
+            player.position = if name == "player/play_next" {
+                (player.position + 1) % player.queue.len()
+            } else {
+                player
+                    .position
+                    .checked_sub(1)
+                    .unwrap_or(player.queue.len() - 1)
+            };
+            events.push((
+                "event/player_now_playing_changed".to_string(),
+                format!("pid={}", player.pid),
+            ));
+            success(name, format!("pid={}", player.pid), None)
+        }
+        "player/get_queue" => {
+            let player = &state.players[&pid.unwrap()];
+            let (start, end) = range(&params);
+            let entries: Vec<Value> = player
+                .queue
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take(end.saturating_sub(start) + 1)
+                .map(|(index, track)| {
+                    json!({
+                        "song": track.song,
+                        "album": track.album,
+                        "artist": track.artist,
+                        "image_url": "",
+                        "qid": index + 1,
+                        "mid": track.mid,
+                        "album_id": track.album_id(),
+                    })
+                })
+                .collect();
+            success(name, message.to_string(), Some(Value::Array(entries)))
+        }
+        "group/get_groups" => {
+            let groups: Vec<Value> = state
+                .groups
+                .iter()
+                .map(|(gid, members)| group_json(state, *gid, members))
+                .collect();
+            success(name, String::new(), Some(Value::Array(groups)))
+        }
+        "group/set_group" => {
+            let pids: Vec<PlayerId> = params
+                .get("pid")
+                .map(|pids| pids.split(',').filter_map(|pid| pid.parse().ok()).collect())
+                .unwrap_or_default();
+            if pids.is_empty() || pids.iter().any(|pid| !state.players.contains_key(pid)) {
+                return (failure(name, 2, "Invalid ID"), events);
+            }
+            let leader = pids[0];
+            // every player can only be in one group.
+            for members in state.groups.values_mut() {
+                members.retain(|pid| !pids.contains(pid));
+            }
+            state
+                .groups
+                .retain(|gid, _| !pids[1..].contains(&gid.leader()));
+            state.groups.retain(|_, members| !members.is_empty());
+            if pids.len() > 1 {
+                state.groups.insert(leader.group(), pids[1..].to_vec());
+            } else {
+                state.groups.remove(&leader.group());
+            }
+            events.push(("event/groups_changed".to_string(), String::new()));
+            success(name, message.to_string(), None)
+        }
+        "group/get_volume" | "group/set_volume" | "group/get_mute" | "group/set_mute" => {
+            let gid = params
+                .get("gid")
+                .and_then(|gid| gid.parse::<GroupId>().ok());
+            let members = match gid.and_then(|gid| state.groups.get(&gid)) {
+                Some(members) => members.clone(),
+                None => return (failure(name, 2, "Invalid ID"), events),
+            };
+            let gid = gid.unwrap();
+            let pids: Vec<PlayerId> = std::iter::once(gid.leader()).chain(members).collect();
+            if name == "group/set_volume" {
+                match level {
+                    Some(level) if level <= 100 => {
+                        for pid in &pids {
+                            state.players.get_mut(pid).unwrap().volume = level;
+                        }
+                    }
+                    _ => return (failure(name, 9, "Parameter out of range"), events),
+                }
+            }
+            if let Some(mute) = params.get("state") {
+                for pid in &pids {
+                    state.players.get_mut(pid).unwrap().mute = mute == "on";
+                }
+            }
+            let leader = &state.players[&gid.leader()];
+            let mute = if leader.mute { "on" } else { "off" };
+            if name.starts_with("group/set") {
+                events.push((
+                    "event/group_volume_changed".to_string(),
+                    format!("gid={}&level={}&mute={}", gid, leader.volume, mute),
+                ));
+            }
+            let message = if name.ends_with("mute") {
+                format!("gid={}&state={}", gid, mute)
+            } else {
+                format!("gid={}&level={}", gid, leader.volume)
+            };
+            success(name, message, None)
+        }
+        "browse/get_music_sources" => success(
+            name,
+            String::new(),
+            Some(json!([
+                {"name": "Local Music", "image_url": "", "type": "heos_server",
+                 "sid": LIBRARY_SID, "available": "true"},
+                {"name": "Playlists", "image_url": "", "type": "heos_service",
+                 "sid": 1025, "available": "true"},
+            ])),
+        ),
+        "browse/browse" => browse(state, name, &params),
+        "browse/get_search_criteria" => success(
+            name,
+            message.to_string(),
+            Some(json!([
+                {"name": "Artist", "scid": 1, "wildcard": "no"},
+                {"name": "Album", "scid": 2, "wildcard": "no"},
+                {"name": "Track", "scid": 3, "wildcard": "no"},
+            ])),
+        ),
+        "browse/search" => {
+            let search = params.get("search").cloned().unwrap_or_default();
+            let scid = params.get("scid").cloned().unwrap_or_default();
+            let needle = search.to_lowercase();
+            let found: Vec<Value> = state
+                .library
+                .iter()
+                .filter(|track| {
+                    let field = match scid.as_str() {
+                        "1" => &track.artist,
+                        "2" => &track.album,
+                        _ => &track.song,
+                    };
+                    field.to_lowercase().contains(&needle)
+                })
+                .map(song_json)
+                .collect();
+            success(
+                name,
+                format!(
+                    "sid={}&search={}&scid={}&returned={}&count={}",
+                    LIBRARY_SID,
+                    search,
+                    scid,
+                    found.len(),
+                    found.len()
+                ),
+                Some(Value::Array(found)),
+            )
+        }
+        _ => failure(name, 1, "Unrecognized Command"),
+    };
+    (response, events)
+}
+
+fn range(params: &BTreeMap<String, String>) -> (usize, usize) {
+    params
+        .get("range")
+        .and_then(|range| range.split_once(','))
+        .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+        .unwrap_or((0, 100))
+}
+
+fn group_json(state: &SimState, gid: GroupId, members: &[PlayerId]) -> Value {
+    let players: Vec<Value> = std::iter::once((gid.leader(), "leader"))
+        .chain(members.iter().map(|pid| (*pid, "member")))
+        .map(|(pid, role)| json!({"name": state.players[&pid].name, "pid": pid, "role": role}))
+        .collect();
+    let name = std::iter::once(gid.leader())
+        .chain(members.iter().copied())
+        .map(|pid| state.players[&pid].name.clone())
+        .collect::<Vec<_>>()
+        .join(" + ");
+    json!({"name": name, "gid": gid, "players": players})
+}
+
+fn song_json(track: &SimTrack) -> Value {
+    json!({
+        "container": "no",
+        "type": "song",
+        "playable": "yes",
+        "name": track.song,
+        "artist": track.artist,
+        "album": track.album,
+        "image_url": "",
+        "mid": track.mid,
+    })
+}
+
+// The library has the albums on top and their songs below.
+fn browse(state: &SimState, name: &str, params: &BTreeMap<String, String>) -> String {
+    let sid = params
+        .get("sid")
+        .and_then(|sid| sid.parse::<SourceId>().ok());
+    if sid != Some(LIBRARY_SID) {
+        return failure(name, 2, "Invalid ID");
+    }
+    let mut albums: Vec<&SimTrack> = vec![];
+    for track in &state.library {
+        if !albums.iter().any(|album| album.album == track.album) {
+            albums.push(track);
+        }
+    }
+    let items: Vec<Value> = match params.get("cid") {
+        None => albums
+            .iter()
+            .map(|track| {
+                json!({
+                    "container": "yes",
+                    "type": "album",
+                    "playable": "yes",
+                    "name": track.album,
+                    "artist": track.artist,
+                    "image_url": "",
+                    "cid": track.album_id(),
+                })
+            })
+            .collect(),
+        Some(cid) => state
+            .library
+            .iter()
+            .filter(|track| &track.album_id() == cid)
+            .map(song_json)
+            .collect(),
+    };
+    match params.get("cid") {
+        None => success(
+            name,
+            format!(
+                "sid={}&returned={}&count={}",
+                LIBRARY_SID,
+                items.len(),
+                items.len()
+            ),
+            Some(Value::Array(items)),
+        ),
+        Some(cid) => {
+            let (start, end) = range(params);
+            let count = items.len();
+            let page: Vec<Value> = items
+                .into_iter()
+                .skip(start)
+                .take(end.saturating_sub(start) + 1)
+                .collect();
+            success(
+                name,
+                format!(
+                    "sid={}&cid={}&range={},{}&returned={}&count={}",
+                    LIBRARY_SID,
+                    cid,
+                    start,
+                    end,
+                    page.len(),
+                    count
+                ),
+                Some(Value::Array(page)),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::types::event::HeosEvent;
+    use crate::types::{ContainerId, HeosErrorCode};
+    use crate::{HeosDriver, HeosError, RetryPolicy};
+
+    use super::*;
+
+    #[tokio::test]
+    pub async fn test_driver_against_simulated_device() {
+        let device = SimulatedDevice::start().await.unwrap();
+        let driver = HeosDriver::new(device.addr()).await.unwrap();
+        assert_eq!(driver.players().len(), 2);
+        assert_eq!(driver.music_sources().len(), 2);
+        let living_room = &driver.players()[0];
+        assert_eq!(living_room.now_playing.as_ref().unwrap().song, "Dreams");
+
+        let mut events = driver.subscribe();
+        driver.set_volume(PlayerId(2), 35).await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            event,
+            HeosEvent::PlayerVolumeChanged {
+                player_id: PlayerId(2),
+                level: 35,
+                ..
+            }
+        ));
+        assert_eq!(device.state().players[&PlayerId(2)].volume, 35);
+
+        driver
+            .create_group(PlayerId(1), vec![PlayerId(2)])
+            .await
+            .unwrap();
+        assert_eq!(driver.groups()[0].players.len(), 2);
+        assert!(device
+            .state()
+            .commands
+            .contains(&"group/set_group?pid=1,2".to_string()));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[tokio::test]
+    pub async fn test_blocking_connection() {
+        let device = SimulatedDevice::start().await.unwrap();
+        let addr = device.addr();
+        let response = tokio::task::spawn_blocking(move || {
+            let mut connection = crate::BlockingConnection::connect(addr)?;
+            connection.execute_command("player/get_players")
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(response.command_name, "player/get_players");
+        assert_eq!(response.payload.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn test_busy_device_is_retried() {
+        let device = SimulatedDevice::start().await.unwrap();
+        let driver = HeosDriver::new(device.addr()).await.unwrap();
+        driver.set_retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        });
+        device.update(|state| state.busy = 2);
+        driver.init().await.unwrap();
+        let get_players = device
+            .state()
+            .commands
+            .iter()
+            .filter(|command| *command == "player/get_players")
+            .count();
+        assert_eq!(get_players, 4);
+
+        device.update(|state| state.busy = 1);
+        let err = driver.play_next(PlayerId(1)).await.unwrap_err();
+        assert!(matches!(
+            err,
+            HeosError::InvalidCommand {
+                eid: HeosErrorCode::ProcessingPreviousCommand,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn test_browse_stream_fetches_pages_on_demand() {
+        use tokio_stream::StreamExt;
+
+        let mut state = SimState::example();
+        state.library = (0..120)
+            .map(|n| SimTrack::new("Bach", "Goldberg", &format!("Variation {}", n)))
+            .collect();
+        let device = SimulatedDevice::with_state(state).await.unwrap();
+        let driver = HeosDriver::new(device.addr()).await.unwrap();
+        let browse_commands = || {
+            device
+                .state()
+                .commands
+                .into_iter()
+                .filter(|command| command.contains("cid=album-goldberg"))
+                .collect::<Vec<_>>()
+        };
+
+        let first = driver
+            .browse_stream(LIBRARY_SID, ContainerId::from("album-goldberg"))
+            .take(10)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(first.len(), 10);
+        assert_eq!(browse_commands().len(), 1);
+
+        let all = driver
+            .browse_stream(LIBRARY_SID, ContainerId::from("album-goldberg"))
+            .collect::<HeosResult<Vec<_>>>()
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 120);
+        assert_eq!(all[119].name, "Variation 119");
+        assert_eq!(
+            browse_commands()[1..],
+            [
+                "browse/browse?sid=1024&cid=album-goldberg&range=0,49",
+                "browse/browse?sid=1024&cid=album-goldberg&range=50,99",
+                "browse/browse?sid=1024&cid=album-goldberg&range=100,149",
+            ]
+        );
+    }
+}
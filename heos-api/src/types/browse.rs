@@ -1,5 +1,5 @@
-use super::SourceId;
-use crate::types::{ContainerId, MediaId, Range, YesOrNo};
+use super::{SearchCriteriaId, SourceId};
+use crate::types::{ContainerId, MediaId, Page, Range, YesOrNo};
 use serde::Deserialize;
 
 #[derive(Serialize, Deserialize, Debug, Eq, Clone, PartialEq)]
@@ -45,22 +45,48 @@ pub enum BroseSourceItem {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "String", into = "String")]
 pub enum MediaType {
-    #[serde(rename = "artist")]
     Artist,
-    #[serde(rename = "album")]
     Album,
-    #[serde(rename = "song")]
     Song,
-    #[serde(rename = "genre")]
     Genre,
-    #[serde(rename = "container")]
     Container,
-    #[serde(rename = "station")]
     Station,
     // Not Documented in the HEOS Api ;)
-    #[serde(rename = "playlist")]
     Playlist,
+    /// see [`crate::types::player::MediaType::Other`]
+    Other(String),
+}
+
+impl From<String> for MediaType {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "artist" => MediaType::Artist,
+            "album" => MediaType::Album,
+            "song" => MediaType::Song,
+            "genre" => MediaType::Genre,
+            "container" => MediaType::Container,
+            "station" => MediaType::Station,
+            "playlist" => MediaType::Playlist,
+            _ => MediaType::Other(name),
+        }
+    }
+}
+
+impl From<MediaType> for String {
+    fn from(media_type: MediaType) -> Self {
+        match media_type {
+            MediaType::Artist => "artist".to_string(),
+            MediaType::Album => "album".to_string(),
+            MediaType::Song => "song".to_string(),
+            MediaType::Genre => "genre".to_string(),
+            MediaType::Container => "container".to_string(),
+            MediaType::Station => "station".to_string(),
+            MediaType::Playlist => "playlist".to_string(),
+            MediaType::Other(name) => name,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -68,7 +94,7 @@ pub struct BrowsableMedia {
     #[serde(rename = "type")]
     pub media_type: MediaType,
     #[serde(rename = "cid")]
-    pub container_id: Option<String>,
+    pub container_id: Option<ContainerId>,
     pub playable: YesOrNo,
     pub image_url: String,
     pub name: String,
@@ -86,3 +112,36 @@ pub struct BrowseMusicContainerResponse {
     pub returned: usize,
     pub items: Vec<BrowsableMedia>, //sid=10&cid=My Music-Tracks&range=0,100&returned=50&count=776
 }
+
+impl From<BrowseMusicContainerResponse> for Page<BrowsableMedia> {
+    fn from(response: BrowseMusicContainerResponse) -> Self {
+        Page {
+            items: response.items,
+            range: response.range,
+            total: response.count,
+        }
+    }
+}
+
+/// What a source can be searched by, e.g. Artist or Track.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchCriteria {
+    pub name: String,
+    pub scid: SearchCriteriaId,
+    pub wildcard: YesOrNo,
+    #[serde(default)]
+    pub playable: Option<YesOrNo>,
+    // the container to play all results
+    #[serde(default)]
+    pub cid: Option<ContainerId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchResponse {
+    pub sid: SourceId,
+    pub search: String,
+    pub scid: SearchCriteriaId,
+    pub count: usize,
+    pub returned: usize,
+    pub items: Vec<BroseSourceItem>, //sid=10&search=U2&scid=1&returned=20&count=56
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use super::player::*;
 use super::*;
 
@@ -81,4 +83,64 @@ pub enum HeosEvent {
         #[serde(rename = "un")]
         user_name: Option<String>,
     },
+
+    /// An event we don't know, e.g. from a newer firmware, or one we could
+    /// not make sense of.
+    Unknown {
+        /// e.g. `event/player_now_playing_changed`
+        name: String,
+        params: BTreeMap<String, String>,
+    },
+}
+
+impl HeosEvent {
+    /// The name HEOS uses, without the `event/` prefix, e.g.
+    /// `player_volume_changed`.
+    pub fn name(&self) -> &str {
+        match self {
+            HeosEvent::SourcesChanged => "sources_changed",
+            HeosEvent::PlayersChanged => "players_changed",
+            HeosEvent::GroupChanged => "groups_changed",
+            HeosEvent::PlayerStateChanged { .. } => "player_state_changed",
+            HeosEvent::PlayerNowPlayingChanged { .. } => "player_now_playing_changed",
+            HeosEvent::PlayerNowPlayingProgress { .. } => "player_now_playing_progress",
+            HeosEvent::PlayerPlaybackError { .. } => "player_playback_error",
+            HeosEvent::PlayerVolumeChanged { .. } => "player_volume_changed",
+            HeosEvent::PlayerQueueChanged { .. } => "player_queue_changed",
+            HeosEvent::PlayerRepeatModeChanged { .. } => "repeat_mode_changed",
+            HeosEvent::PlayerShuffleModeChanged { .. } => "shuffle_mode_changed",
+            HeosEvent::GroupVolumeChanged { .. } => "group_volume_changed",
+            HeosEvent::UserChanged { .. } => "user_changed",
+            HeosEvent::Unknown { name, .. } => name.trim_start_matches("event/"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_names_are_the_serde_names() {
+        let events = vec![
+            HeosEvent::GroupChanged,
+            HeosEvent::PlayerRepeatModeChanged {
+                player_id: PlayerId(1),
+                repeat: Repeat::Off,
+            },
+        ];
+        for event in events {
+            let name = match serde_json::to_value(&event).unwrap() {
+                serde_json::Value::String(name) => name,
+                serde_json::Value::Object(map) => map.keys().next().unwrap().clone(),
+                other => panic!("unexpected {:?}", other),
+            };
+            assert_eq!(format!("event/{}", event.name()), name);
+        }
+        let unknown = HeosEvent::Unknown {
+            name: "event/bass_changed".to_string(),
+            params: BTreeMap::new(),
+        };
+        assert_eq!(unknown.name(), "bass_changed");
+    }
 }
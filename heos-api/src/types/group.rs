@@ -71,7 +71,7 @@ pub struct CreateGroupResponse {
     pub pids: Vec<PlayerId>,
 }
 
-fn deserialize_silly_list<'de, D>(deserializer: D) -> Result<Vec<i64>, D::Error>
+fn deserialize_silly_list<'de, D>(deserializer: D) -> Result<Vec<PlayerId>, D::Error>
 where
     D: Deserializer<'de>,
 {
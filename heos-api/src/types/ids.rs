@@ -0,0 +1,161 @@
+//! The ids HEOS uses. To HEOS they are all numbers or strings, here each is a
+//! type of its own, so that e.g. a group id can't be sent as a `pid`.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+macro_rules! number_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub i64);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map($name)
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(id: i64) -> Self {
+                $name(id)
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+    };
+}
+
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok($name(s.to_string()))
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                $name(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                $name(id.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+number_id!(
+    /// A player, `pid` to HEOS.
+    PlayerId
+);
+number_id!(
+    /// A group, `gid` to HEOS. It is the pid of the leader.
+    GroupId
+);
+number_id!(
+    /// An entry of a queue, `qid` to HEOS.
+    QueueId
+);
+number_id!(
+    /// A music source, `sid` to HEOS.
+    SourceId
+);
+number_id!(
+    /// What a source is searched by, `scid` to HEOS.
+    SearchCriteriaId
+);
+string_id!(
+    /// A container of a source, e.g. an album, `cid` to HEOS.
+    ContainerId
+);
+string_id!(
+    /// Something playable, `mid` to HEOS.
+    MediaId
+);
+
+impl PlayerId {
+    /// The id of the group this player leads, or would lead.
+    pub fn group(self) -> GroupId {
+        GroupId(self.0)
+    }
+}
+
+impl GroupId {
+    pub fn leader(self) -> PlayerId {
+        PlayerId(self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_ids_are_what_heos_sends() {
+        let pid: PlayerId = serde_json::from_str("-1465850739").unwrap();
+        assert_eq!(pid, PlayerId(-1465850739));
+        assert_eq!(pid.to_string(), "-1465850739");
+        assert_eq!("-1465850739".parse(), Ok(pid));
+        assert_eq!(pid.group().leader(), pid);
+
+        let cid: ContainerId = serde_json::from_str("\"album-1\"").unwrap();
+        assert_eq!(cid, "album-1");
+        assert_eq!(serde_json::to_string(&cid).unwrap(), "\"album-1\"");
+    }
+}
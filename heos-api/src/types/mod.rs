@@ -4,51 +4,53 @@ use std::fmt::{Display, Formatter};
 pub mod browse;
 pub mod event;
 pub mod group;
+mod ids;
 pub mod player;
 pub mod system;
 
-pub type PlayerId = i64;
-pub type GroupId = i64;
-pub type QueueId = i64;
-pub type SourceId = i64;
+pub use ids::{ContainerId, GroupId, MediaId, PlayerId, QueueId, SearchCriteriaId, SourceId};
+
 pub type AlbumId = String;
-pub type MediaId = String;
-pub type ContainerId = String;
 pub type Level = u8;
 pub type Milliseconds = u64;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Items `start` to `end` of a list, both inclusive as HEOS counts them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Range {
     pub start: u16,
     pub end: u16,
 }
 
 impl Range {
+    /// `size` items from `start` on.
+    pub fn page(start: u16, size: u16) -> Self {
+        Range {
+            start,
+            end: start.saturating_add(size.max(1) - 1),
+        }
+    }
+
     pub fn length(&self) -> u16 {
-        self.end - self.start
+        self.end.saturating_sub(self.start) + 1
     }
 
+    /// The items right before this range, at most as many.
     pub fn previous(&self) -> Option<Self> {
         if self.start == 0 {
             None
-        } else if self.length() > self.start {
-            Some(Range {
-                start: self.start - self.length(),
-                end: self.end,
-            })
         } else {
             Some(Range {
-                start: 0,
-                end: self.end,
+                start: self.start.saturating_sub(self.length()),
+                end: self.start - 1,
             })
         }
     }
+
+    /// The items right after this range, as many.
     pub fn next(&self) -> Self {
-        Range {
-            start: self.end,
-            end: self.end + self.length(),
-        }
+        Range::page(self.end.saturating_add(1), self.length())
     }
+
     pub fn as_query_str(&self) -> String {
         format!("start={}&end={}", self.start, self.end)
     }
@@ -56,7 +58,43 @@ impl Range {
 
 impl Default for Range {
     fn default() -> Self {
-        Range { start: 0, end: 10 }
+        Range::page(0, 10)
+    }
+}
+
+/// A part of a list HEOS returns in pages, e.g. the items of a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// the requested range, there may be fewer items
+    pub range: Range,
+    /// the number of items in the whole list
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    pub fn has_next(&self) -> bool {
+        // an empty page ends the list, whatever HEOS counts
+        !self.items.is_empty() && (self.range.start as usize) + self.items.len() < self.total
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.range.start > 0
+    }
+
+    /// `None` on the last page, and past the ranges HEOS can be asked for.
+    pub fn next(&self) -> Option<Range> {
+        if !self.has_next() {
+            return None;
+        }
+        let start = u16::try_from(self.items.len())
+            .ok()
+            .and_then(|returned| self.range.start.checked_add(returned))?;
+        Some(Range::page(start, self.range.length()))
+    }
+
+    pub fn previous(&self) -> Option<Range> {
+        self.range.previous()
     }
 }
 
@@ -96,10 +134,11 @@ pub enum YesOrNo {
     No,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Deserialize, Serialize, Default)]
 pub enum OnOrOff {
     #[serde(rename = "on")]
     On,
+    #[default]
     #[serde(rename = "off")]
     Off,
 }
@@ -187,7 +226,7 @@ impl fmt::Display for Shuffle {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum HeosErrorCode {
     UnrecognizedCommand = 1,
     InvalidId = 2,
@@ -213,4 +252,78 @@ impl Display for HeosErrorCode {
     }
 }
 
+impl HeosErrorCode {
+    /// The closest http status, for the web apps to answer failed commands with.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            HeosErrorCode::UnrecognizedCommand => 502,
+            HeosErrorCode::InvalidId => 404,
+            HeosErrorCode::WrongNumberOfArguments => 400,
+            HeosErrorCode::RequestedDataNotAvailable => 404,
+            HeosErrorCode::ResourceCurrentlyNotAvailable => 503,
+            HeosErrorCode::InvalidCredentials => 403,
+            HeosErrorCode::CommandCouldNotBeExecuted => 422,
+            HeosErrorCode::UserNotLoggedIn => 401,
+            HeosErrorCode::ParameterOutOfRange => 400,
+            HeosErrorCode::UserNotFound => 403,
+            HeosErrorCode::InternalError => 502,
+            HeosErrorCode::SystemError => 502,
+            HeosErrorCode::ProcessingPreviousCommand => 503,
+            HeosErrorCode::MediaCantBePlayed => 422,
+            HeosErrorCode::OptionNotSupported => 501,
+            HeosErrorCode::Unknown => 502,
+        }
+    }
+}
+
 pub struct Success;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_ranges_are_inclusive() {
+        let range = Range::page(20, 10);
+        assert_eq!(range, Range { start: 20, end: 29 });
+        assert_eq!(range.length(), 10);
+        assert_eq!(range.next(), Range { start: 30, end: 39 });
+        assert_eq!(range.previous(), Some(Range { start: 10, end: 19 }));
+        assert_eq!(
+            Range::page(5, 10).previous(),
+            Some(Range { start: 0, end: 4 })
+        );
+        assert_eq!(Range::default().previous(), None);
+    }
+
+    #[test]
+    pub fn test_pages() {
+        let page = Page {
+            items: vec![1, 2, 3],
+            range: Range::page(0, 3),
+            total: 5,
+        };
+        assert!(page.has_next() && !page.has_previous());
+        let last = Page {
+            items: vec![4, 5],
+            range: page.next().unwrap(),
+            total: 5,
+        };
+        assert_eq!(last.range, Range { start: 3, end: 5 });
+        assert!(!last.has_next() && last.has_previous());
+        assert_eq!(last.previous(), Some(Range { start: 0, end: 2 }));
+        let end = Page {
+            items: vec![0; 10],
+            range: Range::page(u16::MAX - 5, 10),
+            total: 100_000,
+        };
+        assert_eq!(end.next(), None);
+    }
+
+    #[test]
+    pub fn test_heos_errors_map_to_status_codes() {
+        assert_eq!(HeosErrorCode::InvalidId.http_status(), 404);
+        assert_eq!(HeosErrorCode::ProcessingPreviousCommand.http_status(), 503);
+        assert_eq!(HeosErrorCode::ParameterOutOfRange.http_status(), 400);
+    }
+}
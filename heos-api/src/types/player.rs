@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
@@ -31,6 +32,19 @@ impl fmt::Display for PlayState {
     }
 }
 
+impl std::str::FromStr for PlayState {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<PlayState, String> {
+        match string {
+            "play" => Ok(PlayState::Play),
+            "pause" => Ok(PlayState::Pause),
+            "stop" => Ok(PlayState::Stop),
+            c => Err(format!("can't convert {} to PlayState", c)),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct PlayerPlayState {
     #[serde(alias = "pid")]
@@ -76,27 +90,69 @@ pub struct PlayerInfo {
     pub control: Option<String>,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
 pub enum MediaType {
-    #[serde(rename = "song")]
     Song,
-    #[serde(rename = "station")]
     Station,
+    /// a type unknown to us, as HEOS named it
+    Other(String),
+}
+
+impl From<String> for MediaType {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "song" => MediaType::Song,
+            "station" => MediaType::Station,
+            _ => MediaType::Other(name),
+        }
+    }
+}
+
+impl From<MediaType> for String {
+    fn from(media_type: MediaType) -> Self {
+        media_type.to_string()
+    }
 }
 
+impl Display for MediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaType::Song => write!(f, "song"),
+            MediaType::Station => write!(f, "station"),
+            MediaType::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// What a player plays.
+///
+/// Only the type is always there: AUX inputs and some stations come without
+/// ids or album, these are empty then, or 0. Fields we don't know end up in
+/// `extra`.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct NowPlayingMedia {
     #[serde(rename = "type")]
     pub media_type: MediaType,
+    #[serde(default)]
     pub song: String,
+    #[serde(default)]
     pub album: String,
+    #[serde(default)]
     pub artist: String,
+    #[serde(default)]
     pub image_url: String,
     pub station: Option<String>,
+    #[serde(default)]
     pub mid: MediaId,
+    #[serde(default)]
     pub qid: QueueId,
+    #[serde(default)]
     pub sid: SourceId,
+    #[serde(default)]
     pub album_id: AlbumId,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
 }
 
 // needed as guess what! The request responds with an empty object
@@ -108,7 +164,7 @@ pub struct NowPlayingMediaResponse {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct PlayerNowPlayingMedia {
     #[serde(rename = "pid")]
-    pub player_id: i64,
+    pub player_id: PlayerId,
     pub media: NowPlayingMedia,
 }
 
@@ -136,11 +192,15 @@ pub struct PlayerStepLevel {
 #[derive(Serialize, Deserialize, Debug, Eq, Clone, PartialEq)]
 pub struct QueueEntry {
     pub song: String,
+    #[serde(default)]
     pub album: String,
+    #[serde(default)]
     pub artist: String,
+    #[serde(default)]
     pub image_url: String,
-    pub qid: i64,
-    pub mid: String,
+    pub qid: QueueId,
+    pub mid: MediaId,
+    #[serde(default)]
     pub album_id: String,
 }
 
@@ -155,7 +215,7 @@ pub struct PlayerPlayMode {
 #[derive(Serialize, Deserialize, Debug, Eq, Clone, PartialEq)]
 pub struct PlayerMute {
     #[serde(rename = "pid")]
-    pub player_id: i64,
+    pub player_id: PlayerId,
     pub state: OnOrOff,
 }
 
@@ -193,9 +253,11 @@ pub struct HeosPlayer {
     pub player_id: PlayerId,
     pub name: String,
     pub volume: Level,
+    #[serde(default)]
+    pub mute: OnOrOff,
     pub now_playing: Option<NowPlayingMedia>,
     pub play_state: PlayState,
-    pub in_group: Option<PlayerId>,
+    pub in_group: Option<GroupId>,
     pub mode: Option<PlayMode>,
 }
 
@@ -204,11 +266,27 @@ impl HeosPlayer {
         self.in_group.is_none()
     }
     pub fn is_leader(&self) -> bool {
-        self.in_group.filter(|gid| *gid == self.player_id).is_some()
+        self.in_group == Some(self.player_id.group())
     }
     pub fn as_json(&self) -> Value {
         serde_json::to_value(self).unwrap()
     }
+
+    /// A stopped, ungrouped player at volume 10 playing nothing, for tests to
+    /// change what they need.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn test_player(pid: i64, name: &str) -> HeosPlayer {
+        HeosPlayer {
+            player_id: PlayerId(pid),
+            name: name.to_string(),
+            volume: 10,
+            mute: OnOrOff::Off,
+            now_playing: None,
+            play_state: PlayState::Stop,
+            in_group: None,
+            mode: None,
+        }
+    }
 }
 use serde_json::Value;
 
@@ -0,0 +1,172 @@
+//! Responses and events of different firmware versions, one file per version
+//! in `tests/corpus`. The files there are synthetic, written after what devices
+//! with that firmware answer, so their times are made up: a round start, e.g.
+//! 1700000000000, and 37 ms between lines. Real recordings of
+//! `heos-cli --record` can be added as they are.
+
+use std::path::Path;
+
+use heos_api::error::HeosError;
+use heos_api::record::{read_records, Direction};
+use heos_api::types::browse::{
+    BroseSourceItem, BrowseMusicContainerResponse, MusicSource, SearchResponse,
+};
+use heos_api::types::event::HeosEvent;
+use heos_api::types::group::GroupInfo;
+use heos_api::types::player::{
+    MediaType, NowPlayingMedia, PlayerInfo, PlayerMute, PlayerPlayMode, PlayerPlayState,
+    PlayerVolume, QueueEntry,
+};
+use heos_api::types::{PlayerId, QueueId};
+use heos_api::{CommandResponse, Frame};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+
+// what a line parsed to, as far as the tests care.
+#[derive(Debug)]
+enum Parsed {
+    NowPlaying(Option<NowPlayingMedia>),
+    Event(HeosEvent),
+    Other,
+}
+
+fn parse<T: TryFrom<CommandResponse, Error = HeosError>>(
+    response: CommandResponse,
+) -> Result<T, HeosError> {
+    response.try_into()
+}
+
+fn parse_line(line: &str) -> Result<Parsed, HeosError> {
+    let json = serde_json::from_str(line).map_err(anyhow::Error::from)?;
+    let response = match Frame::from_json(json)? {
+        Frame::Event(event) => return Ok(Parsed::Event(event.try_into()?)),
+        Frame::Response(response) => response,
+        frame => panic!("unexpected {:?}", frame),
+    };
+    let browse_container = response.message.contains("cid=");
+    match response.command_name.as_str() {
+        "player/get_now_playing_media" => return Ok(Parsed::NowPlaying(parse(response)?)),
+        "system/register_for_change_events" => {}
+        "player/get_players" => {
+            parse::<Vec<PlayerInfo>>(response)?;
+        }
+        "player/get_player_info" => {
+            parse::<PlayerInfo>(response)?;
+        }
+        "player/get_queue" => {
+            parse::<Vec<QueueEntry>>(response)?;
+        }
+        "player/get_play_state" => {
+            parse::<PlayerPlayState>(response)?;
+        }
+        "player/get_volume" => {
+            parse::<PlayerVolume>(response)?;
+        }
+        "player/get_mute" => {
+            parse::<PlayerMute>(response)?;
+        }
+        "player/get_play_mode" => {
+            parse::<PlayerPlayMode>(response)?;
+        }
+        "group/get_groups" => {
+            parse::<Vec<GroupInfo>>(response)?;
+        }
+        "browse/get_music_sources" => {
+            parse::<Vec<MusicSource>>(response)?;
+        }
+        "browse/browse" if browse_container => {
+            parse::<BrowseMusicContainerResponse>(response)?;
+        }
+        "browse/browse" => {
+            parse::<Vec<BroseSourceItem>>(response)?;
+        }
+        "browse/search" => {
+            parse::<SearchResponse>(response)?;
+        }
+        command => panic!("no parser for {}", command),
+    }
+    Ok(Parsed::Other)
+}
+
+fn corpus(version: &str) -> Vec<Parsed> {
+    let path = Path::new(CORPUS).join(format!("{}.jsonl", version));
+    read_records(&path)
+        .unwrap()
+        .into_iter()
+        .filter(|record| record.direction == Direction::Received)
+        .map(|record| {
+            parse_line(&record.line)
+                .unwrap_or_else(|err| panic!("{}: {:?}\n{}", version, err, record.line))
+        })
+        .collect()
+}
+
+fn now_playing(parsed: &[Parsed]) -> Vec<Option<NowPlayingMedia>> {
+    parsed
+        .iter()
+        .filter_map(|parsed| match parsed {
+            Parsed::NowPlaying(media) => Some(media.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn events(parsed: &[Parsed]) -> Vec<HeosEvent> {
+    parsed
+        .iter()
+        .filter_map(|parsed| match parsed {
+            Parsed::Event(event) => Some(event.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_every_sample_parses() {
+    for entry in std::fs::read_dir(CORPUS).unwrap() {
+        let path = entry.unwrap().path();
+        let version = path.file_stem().unwrap().to_string_lossy();
+        assert!(!corpus(&version).is_empty());
+    }
+}
+
+#[test]
+fn test_aux_inputs_come_without_ids() {
+    let media = now_playing(&corpus("1.583.147"));
+    let aux = media[1].as_ref().unwrap();
+    assert_eq!(aux.media_type, MediaType::Station);
+    assert_eq!(aux.mid, "inputs/aux_in_1");
+    assert_eq!((aux.qid, aux.album_id.as_str()), (QueueId(0), ""));
+    // nothing playing
+    assert_eq!(media[2], None);
+}
+
+#[test]
+fn test_unknown_types_and_fields_are_kept() {
+    let media = now_playing(&corpus("3.34.620"));
+    let tv = media[0].as_ref().unwrap();
+    assert_eq!(tv.extra["source_id"], "tv_audio");
+    let podcast = media[1].as_ref().unwrap();
+    assert_eq!(podcast.media_type, MediaType::Other("podcast".to_string()));
+    assert_eq!(
+        serde_json::to_value(podcast).unwrap()["type"],
+        serde_json::json!("podcast")
+    );
+}
+
+#[test]
+fn test_unknown_events_are_passed_on() {
+    let events = events(&corpus("3.34.620"));
+    let buffering = events
+        .iter()
+        .find_map(|event| match event {
+            HeosEvent::Unknown { name, params } => Some((name, params)),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(buffering.0, "event/player_state_changed");
+    assert_eq!(buffering.1["state"], "buffering");
+    assert!(events.contains(&HeosEvent::PlayerQueueChanged {
+        player_id: PlayerId(174128736)
+    }));
+}
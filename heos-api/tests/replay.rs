@@ -0,0 +1,47 @@
+use heos_api::error::HeosError;
+use heos_api::record::{read_records, Recorder, Replay};
+use heos_api::types::event::HeosEvent;
+use heos_api::types::HeosErrorCode;
+use heos_api::HeosApi;
+
+const VOLUME: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/recordings/volume.jsonl");
+
+#[tokio::test]
+async fn test_replay_volume() {
+    let replay = Replay::open(VOLUME).await.unwrap();
+    let path = std::env::temp_dir().join(format!("heos-replay-{}.jsonl", std::process::id()));
+    let api = HeosApi::record(replay.addr(), Recorder::create(&path).unwrap())
+        .await
+        .unwrap();
+    let mut events = api.events().await.unwrap();
+
+    let players = api.get_player_infos().await.unwrap();
+    assert_eq!(players[0].name, "Kitchen");
+    let volume = api.set_volume(players[0].pid, 20).await.unwrap();
+    assert_eq!(volume.level, 20);
+    match events.recv().await.unwrap() {
+        HeosEvent::PlayerVolumeChanged {
+            player_id, level, ..
+        } => assert_eq!((player_id, level), (players[0].pid, 20)),
+        event => panic!("unexpected {:?}", event),
+    }
+    assert!(matches!(
+        api.set_volume(players[0].pid, 120).await,
+        Err(HeosError::InvalidCommand {
+            eid: HeosErrorCode::ParameterOutOfRange,
+            ..
+        })
+    ));
+    assert!(replay.is_finished(), "{:?}", replay.errors());
+
+    // recording the replay gives the same traffic back
+    let strip = |records: Vec<heos_api::record::Record>| {
+        records
+            .into_iter()
+            .map(|record| (record.connection, record.direction, record.line))
+            .collect::<Vec<_>>()
+    };
+    let recorded = read_records(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(strip(recorded), strip(read_records(VOLUME).unwrap()));
+}
@@ -0,0 +1,11 @@
+use ructe::Ructe;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut ructe = Ructe::from_env()?;
+    let mut statics = ructe.statics()?;
+    statics.add_files("statics")?;
+    // both servers share the stylesheet of heos-actix.
+    statics.add_file_as("../heos-actix/src/routers/style/style.css", "style.css")?;
+    ructe.compile_templates("templates")?;
+    Ok(())
+}
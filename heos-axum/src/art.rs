@@ -0,0 +1,413 @@
+//! Album art served from our own origin, see [`art_url`] and [`ArtCache`].
+//!
+//! The image urls of HEOS point to third parties, are often plain http and
+//! always full size. Pages link to `/art/{hash}?size=..` instead, the cache
+//! fetches each image once, keeps it on disk and resizes it to thumbnails.
+//!
+//! Both heos-axum and heosd serve the same cache.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context};
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageOutputFormat};
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+/// The thumbnail sizes, a requested size is rounded up to the next one.
+pub const SIZES: [u32; 4] = [64, 128, 256, 512];
+
+/// `Cache-Control` of the art, an url is expected to always show the same image.
+pub const CACHE_CONTROL: &str = "public, max-age=2592000, immutable";
+
+// larger downloads are refused.
+const MAX_DOWNLOAD: usize = 10 * 1024 * 1024;
+
+// numbers the temporary files, requests for the same art may write at once.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+// the number of urls remembered for their hashes, a page shows a few dozen.
+const MAX_URLS: usize = 4096;
+
+lazy_static! {
+    static ref URLS: Mutex<Urls> = Mutex::new(Urls::new(MAX_URLS));
+}
+
+// the urls of the hashes that were handed out by `art_url`, the least
+// recently used are forgotten. Art already in the cache is served without.
+struct Urls {
+    urls: HashMap<String, (String, u64)>,
+    max: usize,
+    clock: u64,
+}
+
+impl Urls {
+    fn new(max: usize) -> Self {
+        Urls {
+            urls: HashMap::new(),
+            max,
+            clock: 0,
+        }
+    }
+
+    fn insert(&mut self, hash: String, url: &str) {
+        self.clock += 1;
+        let clock = self.clock;
+        self.urls
+            .entry(hash)
+            .or_insert_with(|| (url.to_string(), clock))
+            .1 = clock;
+        if self.urls.len() > self.max {
+            let oldest = self
+                .urls
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(hash, _)| hash.clone());
+            if let Some(oldest) = oldest {
+                self.urls.remove(&oldest);
+            }
+        }
+    }
+
+    fn get(&mut self, hash: &str) -> Option<String> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.urls.get_mut(hash).map(|(url, used)| {
+            *used = clock;
+            url.clone()
+        })
+    }
+}
+
+/// The hash identifying the art of an url.
+pub fn hash(image_url: &str) -> String {
+    Sha256::digest(image_url.as_bytes())[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The link to a thumbnail of at least `size` pixels, empty if there is no image.
+pub fn art_url(image_url: &str, size: u32) -> String {
+    if image_url.is_empty() {
+        return String::new();
+    }
+    let hash = hash(image_url);
+    URLS.lock().unwrap().insert(hash.clone(), image_url);
+    format!("/art/{}?size={}", hash, thumbnail_size(size))
+}
+
+fn registered_url(hash: &str) -> Option<String> {
+    URLS.lock().unwrap().get(hash)
+}
+
+fn thumbnail_size(size: u32) -> u32 {
+    SIZES
+        .iter()
+        .copied()
+        .find(|thumbnail| *thumbnail >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// An image ready to be served.
+#[derive(Debug, Clone)]
+pub struct Art {
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+    /// quoted, as the `ETag` header wants it
+    pub etag: String,
+}
+
+impl Art {
+    fn new(bytes: Vec<u8>, name: &str) -> Self {
+        let content_type = match image::guess_format(&bytes) {
+            Ok(ImageFormat::Png) => "image/png",
+            Ok(ImageFormat::Jpeg) => "image/jpeg",
+            Ok(ImageFormat::Gif) => "image/gif",
+            Ok(ImageFormat::WebP) => "image/webp",
+            _ => "application/octet-stream",
+        };
+        Art {
+            bytes,
+            content_type,
+            etag: etag(name),
+        }
+    }
+}
+
+/// The `ETag` of the art at `/art/{hash}?size={size}`, to answer `If-None-Match`
+/// without reading the cache.
+pub fn etag(name: &str) -> String {
+    format!("\"{}\"", name)
+}
+
+/// The name of the art in the cache and in its `ETag`.
+pub fn art_name(hash: &str, size: Option<u32>) -> String {
+    match size {
+        Some(size) => format!("{}-{}", hash, thumbnail_size(size)),
+        None => hash.to_string(),
+    }
+}
+
+struct Entry {
+    bytes: u64,
+    used: u64,
+}
+
+// what is in the cache directory, to evict the least recently used files.
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    clock: u64,
+    bytes: u64,
+}
+
+impl Index {
+    fn touch(&mut self, name: &str) -> bool {
+        self.clock += 1;
+        match self.entries.get_mut(name) {
+            Some(entry) => {
+                entry.used = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, name: String, bytes: u64) {
+        self.clock += 1;
+        let used = self.clock;
+        if let Some(old) = self.entries.insert(name, Entry { bytes, used }) {
+            self.bytes -= old.bytes;
+        }
+        self.bytes += bytes;
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some(entry) = self.entries.remove(name) {
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    fn least_recently_used(&self) -> Option<String> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.used)
+            .map(|(name, _)| name.clone())
+    }
+}
+
+/// Album art on disk, limited to a number of bytes.
+#[derive(Clone)]
+pub struct ArtCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    http: reqwest::Client,
+    index: Arc<Mutex<Index>>,
+}
+
+impl ArtCache {
+    /// Opens the cache in `dir`, creating it if needed. What is already there
+    /// counts as used in the order of its modification time.
+    pub fn open<P: AsRef<Path>>(dir: P, max_bytes: u64) -> anyhow::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create the art cache {}", dir.display()))?;
+        let mut files = vec![];
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if metadata.is_file() && !name.ends_with(".tmp") {
+                files.push((metadata.modified().ok(), name, metadata.len()));
+            }
+        }
+        files.sort();
+        let mut index = Index::default();
+        for (_, name, bytes) in files {
+            index.insert(name, bytes);
+        }
+        let cache = ArtCache {
+            dir,
+            max_bytes,
+            http: reqwest::Client::new(),
+            index: Arc::new(Mutex::new(index)),
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// The art with the `hash` of an url handed out by [`art_url`], resized to
+    /// fit `size` if given. None if the hash is unknown.
+    pub async fn get(&self, hash: &str, size: Option<u32>) -> anyhow::Result<Option<Art>> {
+        // it becomes a file name
+        if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let name = art_name(hash, size);
+        if let Some(bytes) = self.read(&name).await {
+            return Ok(Some(Art::new(bytes, &name)));
+        }
+        let original = match self.read(hash).await {
+            Some(original) => original,
+            None => match registered_url(hash) {
+                Some(url) => {
+                    let original = self.fetch(&url).await?;
+                    self.write(hash, &original).await?;
+                    original
+                }
+                None => return Ok(None),
+            },
+        };
+        let bytes = match size {
+            Some(size) => {
+                let size = thumbnail_size(size);
+                let resized = tokio::task::spawn_blocking(move || resize(&original, size))
+                    .await
+                    .context("Resizing panicked")??;
+                self.write(&name, &resized).await?;
+                resized
+            }
+            None => original,
+        };
+        Ok(Some(Art::new(bytes, &name)))
+    }
+
+    /// The bytes in the cache.
+    pub fn bytes(&self) -> u64 {
+        self.index.lock().unwrap().bytes
+    }
+
+    async fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        debug!("Fetching art {}", url);
+        let mut response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to fetch art {}", url))?;
+        let mut bytes = vec![];
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_DOWNLOAD {
+                bail!("Art {} is larger than {} bytes", url, MAX_DOWNLOAD);
+            }
+        }
+        Ok(bytes)
+    }
+
+    async fn read(&self, name: &str) -> Option<Vec<u8>> {
+        if !self.index.lock().unwrap().touch(name) {
+            return None;
+        }
+        match tokio::fs::read(self.dir.join(name)).await {
+            Ok(bytes) => Some(bytes),
+            Err(err) => {
+                warn!("Art {} vanished from the cache: {}", name, err);
+                self.index.lock().unwrap().remove(name);
+                None
+            }
+        }
+    }
+
+    async fn write(&self, name: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        // readers never see half a file
+        let tmp = self.dir.join(format!(
+            "{}.{}-{}.tmp",
+            name,
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp, bytes)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, self.dir.join(name)).await?;
+        self.index
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), bytes.len() as u64);
+        self.evict();
+        Ok(())
+    }
+
+    fn evict(&self) {
+        let mut index = self.index.lock().unwrap();
+        while index.bytes > self.max_bytes {
+            let name = match index.least_recently_used() {
+                Some(name) => name,
+                None => return,
+            };
+            debug!("Evicting art {}", name);
+            if let Err(err) = std::fs::remove_file(self.dir.join(&name)) {
+                warn!("Failed to evict art {}: {}", name, err);
+            }
+            index.remove(&name);
+        }
+    }
+}
+
+// jpegs stay jpegs, everything else becomes a png.
+fn resize(original: &[u8], size: u32) -> anyhow::Result<Vec<u8>> {
+    let format = image::guess_format(original).context("Unknown image format")?;
+    let image = image::load_from_memory_with_format(original, format)?;
+    if image.width() <= size && image.height() <= size {
+        return Ok(original.to_vec());
+    }
+    let resized = image.resize(size, size, FilterType::Triangle);
+    let output = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(85),
+        _ => ImageOutputFormat::Png,
+    };
+    let mut bytes = Cursor::new(vec![]);
+    resized.write_to(&mut bytes, output)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_art_urls() {
+        assert_eq!(art_url("", 64), "");
+        let url = art_url("http://192.168.1.2/cover.jpg", 100);
+        let hash = hash("http://192.168.1.2/cover.jpg");
+        assert_eq!(hash.len(), 32);
+        assert_eq!(url, format!("/art/{}?size=128", hash));
+        assert_eq!(
+            registered_url(&hash).as_deref(),
+            Some("http://192.168.1.2/cover.jpg")
+        );
+        assert_eq!(thumbnail_size(1), 64);
+        assert_eq!(thumbnail_size(4000), 512);
+    }
+
+    #[test]
+    pub fn test_urls_are_bounded() {
+        let mut urls = Urls::new(2);
+        urls.insert("a".to_string(), "http://a");
+        urls.insert("b".to_string(), "http://b");
+        assert_eq!(urls.get("a").as_deref(), Some("http://a"));
+        urls.insert("c".to_string(), "http://c");
+        assert_eq!(urls.get("b"), None);
+        assert_eq!(urls.get("a").as_deref(), Some("http://a"));
+        assert_eq!(urls.get("c").as_deref(), Some("http://c"));
+    }
+
+    #[test]
+    pub fn test_lru() {
+        let mut index = Index::default();
+        index.insert("a".to_string(), 10);
+        index.insert("b".to_string(), 10);
+        index.touch("a");
+        assert_eq!(index.least_recently_used().as_deref(), Some("b"));
+        index.remove("b");
+        assert_eq!(index.bytes, 10);
+    }
+}
@@ -1,6 +1,12 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use anyhow::Context;
 use clap::Parser;
-use heos_api::HeosResult;
+use heos_api::rules::{read_rules, RuleEngine};
+use heos_api::{RetryPolicy, VolumePolicy};
+
+use crate::art::ArtCache;
+use crate::history::{History, HistoryStore, Submitter};
 
 #[derive(Parser, Debug)]
 pub struct Config{
@@ -17,7 +23,43 @@ pub struct Config{
     pub heos_device_addr: Option<IpAddr>,
 
     #[clap(long, env)]
-    pub base_url: String
+    pub base_url: String,
+
+    /// yaml file with the volume limits of the players
+    #[clap(long, env)]
+    pub volume_policies: Option<PathBuf>,
+
+    /// attempts of a query HEOS is too busy to answer, 1 to never retry
+    #[clap(long, env, default_value_t = 4)]
+    pub heos_command_attempts: u32,
+
+    /// yaml file with automation rules, see heos_api::rules
+    #[clap(long, env)]
+    pub rules: Option<PathBuf>,
+
+    /// log the rules that fire without running their actions
+    #[clap(long, env)]
+    pub rules_dry_run: bool,
+
+    /// sqlite database of the listening history
+    #[clap(long, env, default_value = "heos-history.sqlite")]
+    pub history_db: PathBuf,
+
+    /// ListenBrainz, or another server with its api, to submit listens to
+    #[clap(long, env, default_value = "https://api.listenbrainz.org")]
+    pub listenbrainz_url: String,
+
+    /// completed listens are only submitted with a user token
+    #[clap(long, env)]
+    pub listenbrainz_token: Option<String>,
+
+    /// directory of the cached album art
+    #[clap(long, env, default_value = "art-cache")]
+    pub art_cache_dir: PathBuf,
+
+    /// the album art cache evicts the least recently used images beyond this size
+    #[clap(long, env, default_value_t = 100)]
+    pub art_cache_megabytes: u64,
 }
 
 impl Config {
@@ -25,4 +67,48 @@ impl Config {
         let host = self.host.unwrap_or(Ipv4Addr::new(127, 0, 0, 1).into());
         SocketAddr::new(host,self.port)
     }
+
+    pub fn load_rules(&self) -> anyhow::Result<RuleEngine> {
+        let rules = match &self.rules {
+            Some(path) => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("Failed to open {:?}", path))?;
+                read_rules(file).with_context(|| format!("Failed to read rules from {:?}", path))?
+            }
+            None => vec![],
+        };
+        Ok(RuleEngine::new(rules, self.rules_dry_run))
+    }
+
+    pub fn open_art_cache(&self) -> anyhow::Result<ArtCache> {
+        ArtCache::open(&self.art_cache_dir, self.art_cache_megabytes * 1024 * 1024)
+    }
+
+    pub fn open_history(&self) -> anyhow::Result<History> {
+        let store = HistoryStore::open(&self.history_db)?;
+        let submitter = self
+            .listenbrainz_token
+            .as_ref()
+            .map(|token| Submitter::new(self.listenbrainz_url.clone(), token.clone()));
+        Ok(History { store, submitter })
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.heos_command_attempts.max(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn load_volume_policies(&self) -> anyhow::Result<Vec<VolumePolicy>> {
+        match &self.volume_policies {
+            Some(path) => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("Failed to open {:?}", path))?;
+                serde_yaml::from_reader(file)
+                    .with_context(|| format!("Failed to read volume policies from {:?}", path))
+            }
+            None => Ok(vec![]),
+        }
+    }
 }
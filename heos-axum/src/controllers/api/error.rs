@@ -0,0 +1,87 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tracing::error;
+
+use heos_api::error::HeosError;
+
+use crate::models::api::ApiError;
+
+pub type ApiResult<T> = Result<T, ApiErrorResponse>;
+
+pub enum ApiErrorResponse {
+    Heos(HeosError),
+    NotFound(String),
+    /// a feature that is not configured
+    Unavailable(String),
+}
+
+impl From<HeosError> for ApiErrorResponse {
+    fn from(err: HeosError) -> Self {
+        ApiErrorResponse::Heos(err)
+    }
+}
+
+impl From<anyhow::Error> for ApiErrorResponse {
+    fn from(err: anyhow::Error) -> Self {
+        ApiErrorResponse::Heos(HeosError::InternalError(err))
+    }
+}
+
+impl ApiErrorResponse {
+    /// The http status and the json body, shared by http and websocket replies.
+    pub fn into_parts(self) -> (StatusCode, ApiError) {
+        match self {
+            ApiErrorResponse::NotFound(what) => (
+                StatusCode::NOT_FOUND,
+                ApiError {
+                    error: format!("{} not found", what),
+                    eid: None,
+                    text: None,
+                },
+            ),
+            ApiErrorResponse::Unavailable(what) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ApiError {
+                    error: format!("{} is not configured", what),
+                    eid: None,
+                    text: None,
+                },
+            ),
+            ApiErrorResponse::Heos(HeosError::InvalidCommand { command, eid, text }) => (
+                StatusCode::from_u16(eid.http_status()).unwrap_or(StatusCode::BAD_GATEWAY),
+                ApiError {
+                    error: format!("HEOS failed to execute {}: {}", command, eid),
+                    eid: Some(eid as u8),
+                    text: Some(text),
+                },
+            ),
+            ApiErrorResponse::Heos(HeosError::NoDeviceFound) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ApiError {
+                    error: "No HEOS devices found".to_string(),
+                    eid: None,
+                    text: None,
+                },
+            ),
+            ApiErrorResponse::Heos(HeosError::InternalError(err)) => {
+                error!("Well this sucks! {:#?}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiError {
+                        error: "Something went wrong".to_string(),
+                        eid: None,
+                        text: None,
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiErrorResponse {
+    fn into_response(self) -> Response {
+        let (status, body) = self.into_parts();
+        (status, Json(body)).into_response()
+    }
+}
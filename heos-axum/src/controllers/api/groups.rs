@@ -0,0 +1,135 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+
+use heos_api::types::{GroupId, OnOrOff};
+use heos_api::HeosDriver;
+
+use crate::controllers::api::error::{ApiErrorResponse, ApiResult};
+use crate::models::api::{ApiGroup, SetGroup, SetMute, SetVolume};
+
+pub(super) fn find_group(driver: &HeosDriver, gid: GroupId) -> ApiResult<ApiGroup> {
+    driver
+        .groups()
+        .into_iter()
+        .find(|group| group.gid == gid)
+        .map(|group| group.into())
+        .ok_or_else(|| ApiErrorResponse::NotFound(format!("group {}", gid)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups",
+    tag = "groups",
+    responses((status = 200, description = "All groups", body = [ApiGroup]))
+)]
+pub async fn list_groups(Extension(driver): Extension<HeosDriver>) -> Json<Vec<ApiGroup>> {
+    Json(driver.groups().into_iter().map(|g| g.into()).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{gid}",
+    tag = "groups",
+    params(("gid" = i64, Path, description = "group id, the pid of the leader")),
+    responses(
+        (status = 200, description = "The group", body = ApiGroup),
+        (status = 404, description = "Unknown group", body = ApiError)
+    )
+)]
+pub async fn group_details(
+    Path(gid): Path<GroupId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<Json<ApiGroup>> {
+    find_group(&driver, gid).map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups",
+    tag = "groups",
+    request_body = SetGroup,
+    responses(
+        (status = 204, description = "Group changed"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn set_group(
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetGroup>,
+) -> ApiResult<StatusCode> {
+    let players = driver.players();
+    for pid in std::iter::once(&body.leader).chain(body.members.iter()) {
+        if !players.iter().any(|p| p.player_id == *pid) {
+            return Err(ApiErrorResponse::NotFound(format!("player {}", pid)));
+        }
+    }
+    if body.members.is_empty() {
+        driver.delete_group(body.leader).await?;
+    } else {
+        driver.create_group(body.leader, body.members).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{gid}",
+    tag = "groups",
+    params(("gid" = i64, Path, description = "group id, the pid of the leader")),
+    responses(
+        (status = 204, description = "Group dissolved"),
+        (status = 404, description = "Unknown group", body = ApiError)
+    )
+)]
+pub async fn delete_group(
+    Path(gid): Path<GroupId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<StatusCode> {
+    find_group(&driver, gid)?;
+    driver.delete_group(gid.leader()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/groups/{gid}/volume",
+    tag = "groups",
+    params(("gid" = i64, Path, description = "group id, the pid of the leader")),
+    request_body = SetVolume,
+    responses(
+        (status = 204, description = "Volume changed"),
+        (status = 404, description = "Unknown group", body = ApiError)
+    )
+)]
+pub async fn set_group_volume(
+    Path(gid): Path<GroupId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetVolume>,
+) -> ApiResult<StatusCode> {
+    find_group(&driver, gid)?;
+    driver.set_group_volume(gid, body.level).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/groups/{gid}/mute",
+    tag = "groups",
+    params(("gid" = i64, Path, description = "group id, the pid of the leader")),
+    request_body = SetMute,
+    responses(
+        (status = 204, description = "Mute changed"),
+        (status = 404, description = "Unknown group", body = ApiError)
+    )
+)]
+pub async fn set_group_mute(
+    Path(gid): Path<GroupId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetMute>,
+) -> ApiResult<StatusCode> {
+    find_group(&driver, gid)?;
+    let state = if body.mute { OnOrOff::On } else { OnOrOff::Off };
+    driver.set_group_mute(gid, state).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
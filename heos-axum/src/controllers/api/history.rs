@@ -0,0 +1,91 @@
+use axum::extract::Query;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::controllers::api::error::{ApiErrorResponse, ApiResult};
+use crate::history::{export, History};
+use crate::models::api::ApiListen;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryParams {
+    /// at most this many listens, 50 by default
+    #[serde(default)]
+    limit: Option<u32>,
+    /// only listens older than the listen with this id
+    #[serde(default)]
+    before: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/history",
+    tag = "history",
+    params(HistoryParams),
+    responses((status = 200, description = "The latest listens first", body = [ApiListen]))
+)]
+pub async fn list_listens(
+    Query(params): Query<HistoryParams>,
+    Extension(history): Extension<History>,
+) -> ApiResult<Json<Vec<ApiListen>>> {
+    let listens = history
+        .store
+        .recent(params.limit.unwrap_or(50), params.before)?;
+    Ok(Json(listens.into_iter().map(|l| l.into()).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/listenbrainz.json",
+    tag = "history",
+    responses((status = 200, description = "The completed listens as a ListenBrainz import"))
+)]
+pub async fn listenbrainz(
+    Extension(history): Extension<History>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let listens = history.store.completed()?;
+    Ok(Json(export::listenbrainz(&listens, "import")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/scrobbler.log",
+    tag = "history",
+    responses((status = 200, description = "All listens as an Audioscrobbler log for Last.fm"))
+)]
+pub async fn scrobbler_log(Extension(history): Extension<History>) -> ApiResult<impl IntoResponse> {
+    let listens = history.store.recent(u32::MAX, None)?;
+    // the log is oldest first
+    let listens: Vec<_> = listens.into_iter().rev().collect();
+    Ok((
+        [(CONTENT_TYPE, "text/plain; charset=utf-8")],
+        export::scrobbler_log(&listens),
+    ))
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct Submitted {
+    /// the number of listens submitted
+    pub submitted: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/history/submit",
+    tag = "history",
+    responses(
+        (status = 200, description = "Submitted the pending listens", body = Submitted),
+        (status = 503, description = "No ListenBrainz token configured", body = ApiError)
+    )
+)]
+pub async fn submit(Extension(history): Extension<History>) -> ApiResult<Json<Submitted>> {
+    let submitter = history
+        .submitter
+        .as_ref()
+        .ok_or_else(|| ApiErrorResponse::Unavailable("ListenBrainz".to_string()))?;
+    let submitted = submitter.submit_pending(&history.store).await?;
+    Ok(Json(Submitted { submitted }))
+}
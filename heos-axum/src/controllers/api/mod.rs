@@ -0,0 +1,143 @@
+use axum::routing::{get, post, put};
+use axum::{Extension, Json, Router};
+use utoipa::OpenApi;
+
+use heos_api::types::Range;
+use heos_api::HeosDriver;
+
+use crate::history::History;
+
+use crate::models::api::*;
+
+mod error;
+mod groups;
+mod history;
+mod players;
+mod sources;
+mod ws;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        players::list_players,
+        players::player_details,
+        players::set_play_state,
+        players::play_next,
+        players::play_previous,
+        players::set_volume,
+        players::set_mute,
+        players::set_play_mode,
+        players::queue,
+        groups::list_groups,
+        groups::group_details,
+        groups::set_group,
+        groups::delete_group,
+        groups::set_group_volume,
+        groups::set_group_mute,
+        sources::list_sources,
+        sources::browse_source,
+        sources::browse_container,
+        history::list_listens,
+        history::listenbrainz,
+        history::scrobbler_log,
+        history::submit,
+    ),
+    components(schemas(
+        ApiPlayState,
+        ApiRepeat,
+        ApiNowPlaying,
+        ApiPlayer,
+        ApiGroupMember,
+        ApiGroup,
+        ApiMusicSource,
+        ApiBrowseItem,
+        ApiBrowsePage,
+        ApiQueueEntry,
+        SetPlayState,
+        SetVolume,
+        SetMute,
+        SetPlayMode,
+        SetGroup,
+        ApiListen,
+        history::Submitted,
+        ApiError,
+    )),
+    tags(
+        (name = "players", description = "Single players"),
+        (name = "groups", description = "Groups of players, a.k.a. zones"),
+        (name = "sources", description = "Music sources"),
+        (name = "browse", description = "Browsing music sources"),
+        (name = "history", description = "The listening history"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The items from `start` to `end`, both inclusive, a page of the default size
+/// without an `end`.
+fn requested_range(start: Option<u16>, end: Option<u16>) -> Range {
+    let start = start.unwrap_or(0);
+    match end {
+        Some(end) if end >= start => Range { start, end },
+        _ => Range::page(start, Range::default().length()),
+    }
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// The json api. Everything is below `/api/v1`, including the websocket at `/api/v1/ws`.
+pub fn router(driver: HeosDriver, history: History) -> Router {
+    let v1 = Router::new()
+        .route("/openapi.json", get(openapi))
+        .route("/ws", get(ws::connect))
+        .route("/players", get(players::list_players))
+        .route("/players/:pid", get(players::player_details))
+        .route("/players/:pid/play_state", put(players::set_play_state))
+        .route("/players/:pid/next", post(players::play_next))
+        .route("/players/:pid/previous", post(players::play_previous))
+        .route("/players/:pid/volume", put(players::set_volume))
+        .route("/players/:pid/mute", put(players::set_mute))
+        .route("/players/:pid/play_mode", put(players::set_play_mode))
+        .route("/players/:pid/queue", get(players::queue))
+        .route(
+            "/groups",
+            get(groups::list_groups).post(groups::set_group),
+        )
+        .route(
+            "/groups/:gid",
+            get(groups::group_details).delete(groups::delete_group),
+        )
+        .route("/groups/:gid/volume", put(groups::set_group_volume))
+        .route("/groups/:gid/mute", put(groups::set_group_mute))
+        .route("/sources", get(sources::list_sources))
+        .route("/sources/:sid/browse", get(sources::browse_source))
+        .route(
+            "/sources/:sid/containers/:cid",
+            get(sources::browse_container),
+        )
+        .route("/history", get(history::list_listens))
+        .route("/history/listenbrainz.json", get(history::listenbrainz))
+        .route("/history/scrobbler.log", get(history::scrobbler_log))
+        .route("/history/submit", post(history::submit))
+        .layer(Extension(driver))
+        .layer(Extension(history));
+    Router::new().nest("/api/v1", v1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_openapi_document_contains_all_paths() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        assert_eq!(doc["openapi"], "3.0.3");
+        let paths = doc["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/api/v1/players/{pid}/volume"));
+        assert!(paths.contains_key("/api/v1/groups/{gid}"));
+        assert!(paths.contains_key("/api/v1/sources/{sid}/containers/{cid}"));
+        assert!(paths.contains_key("/api/v1/history/submit"));
+        assert!(doc["components"]["schemas"]["ApiPlayer"].is_object());
+    }
+}
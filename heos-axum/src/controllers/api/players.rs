@@ -0,0 +1,204 @@
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use heos_api::types::{OnOrOff, PlayerId};
+use heos_api::HeosDriver;
+
+use crate::controllers::api::error::{ApiErrorResponse, ApiResult};
+use crate::controllers::api::requested_range;
+use crate::models::api::{ApiPlayer, ApiQueueEntry, SetMute, SetPlayMode, SetPlayState, SetVolume};
+
+pub(super) fn find_player(driver: &HeosDriver, pid: PlayerId) -> ApiResult<ApiPlayer> {
+    driver
+        .players()
+        .into_iter()
+        .find(|player| player.player_id == pid)
+        .map(|player| player.into())
+        .ok_or_else(|| ApiErrorResponse::NotFound(format!("player {}", pid)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/players",
+    tag = "players",
+    responses((status = 200, description = "All players", body = [ApiPlayer]))
+)]
+pub async fn list_players(Extension(driver): Extension<HeosDriver>) -> Json<Vec<ApiPlayer>> {
+    Json(driver.players().into_iter().map(|p| p.into()).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/players/{pid}",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    responses(
+        (status = 200, description = "The player", body = ApiPlayer),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn player_details(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<Json<ApiPlayer>> {
+    find_player(&driver, pid).map(Json)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/players/{pid}/play_state",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    request_body = SetPlayState,
+    responses(
+        (status = 204, description = "Play state changed"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn set_play_state(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetPlayState>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    driver.set_play_state(pid, body.state.into()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/players/{pid}/next",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    responses(
+        (status = 204, description = "Playing the next track"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn play_next(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    driver.play_next(pid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/players/{pid}/previous",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    responses(
+        (status = 204, description = "Playing the previous track"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn play_previous(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    driver.play_previous(pid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/players/{pid}/volume",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    request_body = SetVolume,
+    responses(
+        (status = 204, description = "Volume changed"),
+        (status = 400, description = "Level out of range", body = ApiError),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn set_volume(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetVolume>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    driver.set_volume(pid, body.level).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/players/{pid}/mute",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    request_body = SetMute,
+    responses(
+        (status = 204, description = "Mute changed"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn set_mute(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetMute>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    let state = if body.mute { OnOrOff::On } else { OnOrOff::Off };
+    driver.set_mute(pid, state).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/players/{pid}/play_mode",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id")),
+    request_body = SetPlayMode,
+    responses(
+        (status = 204, description = "Play mode changed"),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn set_play_mode(
+    Path(pid): Path<PlayerId>,
+    Extension(driver): Extension<HeosDriver>,
+    Json(body): Json<SetPlayMode>,
+) -> ApiResult<StatusCode> {
+    find_player(&driver, pid)?;
+    driver.set_play_mode(pid, body.into()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QueueParams {
+    /// first entry, starting with 0
+    #[serde(default)]
+    start: Option<u16>,
+    /// last entry
+    #[serde(default)]
+    end: Option<u16>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/players/{pid}/queue",
+    tag = "players",
+    params(("pid" = i64, Path, description = "player id"), QueueParams),
+    responses(
+        (status = 200, description = "The queue of the player", body = [ApiQueueEntry]),
+        (status = 404, description = "Unknown player", body = ApiError)
+    )
+)]
+pub async fn queue(
+    Path(pid): Path<PlayerId>,
+    Query(params): Query<QueueParams>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<Json<Vec<ApiQueueEntry>>> {
+    find_player(&driver, pid)?;
+    let range = requested_range(params.start, params.end);
+    let queue = driver.get_player_queue(pid, range).await?;
+    Ok(Json(queue.into_iter().map(|e| e.into()).collect()))
+}
@@ -0,0 +1,74 @@
+use axum::extract::{Path, Query};
+use axum::{Extension, Json};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use heos_api::types::{ContainerId, SourceId};
+use heos_api::HeosDriver;
+
+use crate::controllers::api::error::ApiResult;
+use crate::controllers::api::requested_range;
+use crate::models::api::{ApiBrowseItem, ApiBrowsePage, ApiMusicSource};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sources",
+    tag = "sources",
+    responses((status = 200, description = "All music sources", body = [ApiMusicSource]))
+)]
+pub async fn list_sources(Extension(driver): Extension<HeosDriver>) -> Json<Vec<ApiMusicSource>> {
+    Json(driver.music_sources().into_iter().map(|s| s.into()).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sources/{sid}/browse",
+    tag = "browse",
+    params(("sid" = i64, Path, description = "source id")),
+    responses(
+        (status = 200, description = "The top level of the source", body = [ApiBrowseItem]),
+        (status = 404, description = "Unknown source", body = ApiError)
+    )
+)]
+pub async fn browse_source(
+    Path(sid): Path<SourceId>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<Json<Vec<ApiBrowseItem>>> {
+    let items = driver.browse(sid).await?;
+    Ok(Json(items.into_iter().map(|item| item.into()).collect()))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BrowseParams {
+    /// first item, starting with 0
+    #[serde(default)]
+    start: Option<u16>,
+    /// last item
+    #[serde(default)]
+    end: Option<u16>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sources/{sid}/containers/{cid}",
+    tag = "browse",
+    params(
+        ("sid" = i64, Path, description = "source id"),
+        ("cid" = String, Path, description = "container id"),
+        BrowseParams
+    ),
+    responses(
+        (status = 200, description = "A page of the container", body = ApiBrowsePage),
+        (status = 404, description = "Unknown source or container", body = ApiError)
+    )
+)]
+pub async fn browse_container(
+    Path((sid, cid)): Path<(SourceId, ContainerId)>,
+    Query(params): Query<BrowseParams>,
+    Extension(driver): Extension<HeosDriver>,
+) -> ApiResult<Json<ApiBrowsePage>> {
+    let range = requested_range(params.start, params.end);
+    let response = driver.browse_music_containers(&sid, &cid, &range).await?;
+    Ok(Json(ApiBrowsePage::new(sid, response)))
+}
@@ -0,0 +1,419 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use axum::Extension;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use heos_api::error::HeosError;
+use heos_api::types::event::HeosEvent;
+use heos_api::types::{ContainerId, GroupId, OnOrOff, PlayerId, Range, SourceId};
+use heos_api::HeosDriver;
+
+use crate::controllers::api::error::ApiErrorResponse;
+use crate::controllers::api::groups::find_group;
+use crate::controllers::api::players::find_player;
+use crate::controllers::api::requested_range;
+use crate::models::api::*;
+use crate::models::rpc::*;
+
+/// `GET /api/v1/ws`: control players and follow their events over a websocket.
+///
+/// Requests look like `{"id": 1, "method": "players.set_volume", "params": {"pid": 1, "level": 20}}`
+/// and are answered with `{"jsonrpc": "2.0", "id": 1, "result": {"level": 20}}` or an `error`.
+/// Replies may arrive out of order, the `id` correlates them.
+///
+/// After `{"method": "subscribe", "params": {"players": [1], "groups": [2]}}` the server pushes
+/// `event` notifications with the HEOS events and `player`, `players` and `groups`
+/// notifications with the new state. `unsubscribe` stops them.
+pub async fn connect(ws: WebSocketUpgrade, Extension(driver): Extension<HeosDriver>) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, driver))
+}
+
+async fn handle_socket(mut socket: WebSocket, driver: HeosDriver) {
+    let mut events = driver.subscribe();
+    // requests run concurrently, their replies come back through here.
+    let (replies, mut outgoing) = mpsc::unbounded_channel::<String>();
+    // nothing is pushed before the client subscribes.
+    let mut subscription: Option<Subscription> = None;
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // pings are answered by axum
+                    Some(Ok(_)) => continue,
+                };
+                let request = match RpcRequest::parse(&text) {
+                    Ok(request) => request,
+                    Err(response) => {
+                        if !send(&mut socket, &response).await {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                match request.method.as_str() {
+                    "subscribe" => {
+                        let result = params::<Option<Subscription>>(request.params)
+                            .map(|filter| {
+                                debug!("Subscribed to {:?}", &filter);
+                                subscription = Some(filter.unwrap_or_default());
+                                Value::Null
+                            });
+                        if !reply(&mut socket, request.id, result).await {
+                            break;
+                        }
+                    }
+                    "unsubscribe" => {
+                        subscription = None;
+                        if !reply(&mut socket, request.id, Ok(Value::Null)).await {
+                            break;
+                        }
+                    }
+                    _ => {
+                        let driver = driver.clone();
+                        let replies = replies.clone();
+                        tokio::spawn(async move {
+                            let result = call(&driver, &request.method, request.params).await;
+                            if let Some(response) = response(request.id, result) {
+                                let _ = replies.send(encode(&response));
+                            }
+                        });
+                    }
+                }
+            }
+            Some(text) = outgoing.recv() => {
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let subscription = match &subscription {
+                    Some(subscription) => subscription,
+                    None => continue,
+                };
+                let notifications = match event {
+                    Ok(event) => notifications(&driver, subscription, &event),
+                    // we don't know what we missed, so send everything.
+                    Err(RecvError::Lagged(_)) => state(&driver, subscription),
+                    Err(RecvError::Closed) => break,
+                };
+                for notification in notifications {
+                    if !send(&mut socket, &notification).await {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn encode<T: Serialize>(message: &T) -> String {
+    serde_json::to_string(message).expect("rpc messages are always valid json")
+}
+
+async fn send<T: Serialize>(socket: &mut WebSocket, message: &T) -> bool {
+    socket.send(Message::Text(encode(message))).await.is_ok()
+}
+
+async fn reply(socket: &mut WebSocket, id: Option<Value>, result: Result<Value, RpcError>) -> bool {
+    match response(id, result) {
+        Some(response) => send(socket, &response).await,
+        None => true,
+    }
+}
+
+fn response(id: Option<Value>, result: Result<Value, RpcError>) -> Option<RpcResponse> {
+    let id = id?;
+    Some(match result {
+        Ok(result) => RpcResponse::ok(id, result),
+        Err(error) => RpcResponse::error(id, error),
+    })
+}
+
+impl From<ApiErrorResponse> for RpcError {
+    fn from(err: ApiErrorResponse) -> Self {
+        let code = match &err {
+            ApiErrorResponse::NotFound(_) => NOT_FOUND,
+            ApiErrorResponse::Unavailable(_) => UNAVAILABLE,
+            ApiErrorResponse::Heos(_) => SERVER_ERROR,
+        };
+        let (_, data) = err.into_parts();
+        RpcError {
+            code,
+            message: data.error.clone(),
+            data: Some(data),
+        }
+    }
+}
+
+impl From<HeosError> for RpcError {
+    fn from(err: HeosError) -> Self {
+        ApiErrorResponse::from(err).into()
+    }
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    Ok(serde_json::to_value(value).unwrap_or(Value::Null))
+}
+
+#[derive(Deserialize)]
+struct Pid {
+    pid: PlayerId,
+}
+
+#[derive(Deserialize)]
+struct Gid {
+    gid: GroupId,
+}
+
+/// The params of a command for a player: `{"pid": 1, ...}`.
+#[derive(Deserialize)]
+struct ForPlayer<T> {
+    pid: PlayerId,
+    #[serde(flatten)]
+    body: T,
+}
+
+#[derive(Deserialize)]
+struct ForGroup<T> {
+    gid: GroupId,
+    #[serde(flatten)]
+    body: T,
+}
+
+#[derive(Deserialize)]
+struct Paged<T> {
+    #[serde(flatten)]
+    body: T,
+    #[serde(default)]
+    start: Option<u16>,
+    #[serde(default)]
+    end: Option<u16>,
+}
+
+impl<T> Paged<T> {
+    fn range(&self) -> Range {
+        requested_range(self.start, self.end)
+    }
+}
+
+#[derive(Deserialize)]
+struct Sid {
+    sid: SourceId,
+}
+
+#[derive(Deserialize)]
+struct Container {
+    sid: SourceId,
+    cid: ContainerId,
+}
+
+fn on_or_off(mute: bool) -> OnOrOff {
+    if mute {
+        OnOrOff::On
+    } else {
+        OnOrOff::Off
+    }
+}
+
+/// Executes a request, the same operations as the http api.
+async fn call(driver: &HeosDriver, method: &str, raw: Value) -> Result<Value, RpcError> {
+    match method {
+        "players.list" => to_value(
+            driver
+                .players()
+                .into_iter()
+                .map(ApiPlayer::from)
+                .collect::<Vec<_>>(),
+        ),
+        "players.get" => {
+            let Pid { pid } = params(raw)?;
+            to_value(find_player(driver, pid)?)
+        }
+        "players.set_play_state" => {
+            let ForPlayer { pid, body } = params::<ForPlayer<SetPlayState>>(raw)?;
+            find_player(driver, pid)?;
+            driver.set_play_state(pid, body.state.into()).await?;
+            Ok(Value::Null)
+        }
+        "players.next" => {
+            let Pid { pid } = params(raw)?;
+            find_player(driver, pid)?;
+            driver.play_next(pid).await?;
+            Ok(Value::Null)
+        }
+        "players.previous" => {
+            let Pid { pid } = params(raw)?;
+            find_player(driver, pid)?;
+            driver.play_previous(pid).await?;
+            Ok(Value::Null)
+        }
+        "players.set_volume" => {
+            let ForPlayer { pid, body } = params::<ForPlayer<SetVolume>>(raw)?;
+            find_player(driver, pid)?;
+            // the volume policies might have lowered it.
+            let level = driver.set_volume(pid, body.level).await?;
+            Ok(json!({ "level": level }))
+        }
+        "players.set_mute" => {
+            let ForPlayer { pid, body } = params::<ForPlayer<SetMute>>(raw)?;
+            find_player(driver, pid)?;
+            driver.set_mute(pid, on_or_off(body.mute)).await?;
+            Ok(Value::Null)
+        }
+        "players.set_play_mode" => {
+            let ForPlayer { pid, body } = params::<ForPlayer<SetPlayMode>>(raw)?;
+            find_player(driver, pid)?;
+            driver.set_play_mode(pid, body.into()).await?;
+            Ok(Value::Null)
+        }
+        "players.queue" => {
+            let paged = params::<Paged<Pid>>(raw)?;
+            let pid = paged.body.pid;
+            find_player(driver, pid)?;
+            let queue = driver.get_player_queue(pid, paged.range()).await?;
+            to_value(
+                queue
+                    .into_iter()
+                    .map(ApiQueueEntry::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "groups.list" => to_value(
+            driver
+                .groups()
+                .into_iter()
+                .map(ApiGroup::from)
+                .collect::<Vec<_>>(),
+        ),
+        "groups.get" => {
+            let Gid { gid } = params(raw)?;
+            to_value(find_group(driver, gid)?)
+        }
+        "groups.set" => {
+            let body: SetGroup = params(raw)?;
+            for pid in std::iter::once(&body.leader).chain(body.members.iter()) {
+                find_player(driver, *pid)?;
+            }
+            if body.members.is_empty() {
+                driver.delete_group(body.leader).await?;
+            } else {
+                driver.create_group(body.leader, body.members).await?;
+            }
+            Ok(Value::Null)
+        }
+        "groups.delete" => {
+            let Gid { gid } = params(raw)?;
+            find_group(driver, gid)?;
+            driver.delete_group(gid.leader()).await?;
+            Ok(Value::Null)
+        }
+        "groups.set_volume" => {
+            let ForGroup { gid, body } = params::<ForGroup<SetVolume>>(raw)?;
+            find_group(driver, gid)?;
+            let level = driver.set_group_volume(gid, body.level).await?;
+            Ok(json!({ "level": level }))
+        }
+        "groups.set_mute" => {
+            let ForGroup { gid, body } = params::<ForGroup<SetMute>>(raw)?;
+            find_group(driver, gid)?;
+            driver.set_group_mute(gid, on_or_off(body.mute)).await?;
+            Ok(Value::Null)
+        }
+        "sources.list" => to_value(
+            driver
+                .music_sources()
+                .into_iter()
+                .map(ApiMusicSource::from)
+                .collect::<Vec<_>>(),
+        ),
+        "sources.browse" => {
+            let Sid { sid } = params(raw)?;
+            let items = driver.browse(sid).await?;
+            to_value(
+                items
+                    .into_iter()
+                    .map(ApiBrowseItem::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "sources.browse_container" => {
+            let paged = params::<Paged<Container>>(raw)?;
+            let range = paged.range();
+            let Container { sid, cid } = paged.body;
+            let response = driver.browse_music_containers(&sid, &cid, &range).await?;
+            to_value(ApiBrowsePage::new(sid, response))
+        }
+        method => Err(RpcError::method_not_found(method)),
+    }
+}
+
+/// The event and the changed state, as far as the subscription wants them.
+fn notifications(
+    driver: &HeosDriver,
+    subscription: &Subscription,
+    event: &HeosEvent,
+) -> Vec<Notification> {
+    let groups = driver.groups();
+    if !subscription.wants(event, &groups) {
+        return vec![];
+    }
+    let mut notifications = vec![Notification::event(event)];
+    match event {
+        // no state to send, and way too often.
+        HeosEvent::PlayerNowPlayingProgress { .. } | HeosEvent::PlayerQueueChanged { .. } => {}
+        HeosEvent::PlayersChanged | HeosEvent::GroupChanged => {
+            notifications.extend(state(driver, subscription))
+        }
+        HeosEvent::GroupVolumeChanged { .. } => {
+            notifications.push(groups_notification(driver, subscription))
+        }
+        event => {
+            let player = event_player(event).and_then(|pid| {
+                driver
+                    .players()
+                    .into_iter()
+                    .find(|player| player.player_id == pid)
+            });
+            if let Some(player) = player {
+                notifications.push(Notification::new("player", ApiPlayer::from(player)));
+            }
+        }
+    }
+    notifications
+}
+
+/// All players and groups the subscription wants.
+fn state(driver: &HeosDriver, subscription: &Subscription) -> Vec<Notification> {
+    let groups = driver.groups();
+    let players: Vec<ApiPlayer> = driver
+        .players()
+        .into_iter()
+        .filter(|player| subscription.wants_player(player.player_id, &groups))
+        .map(ApiPlayer::from)
+        .collect();
+    vec![
+        Notification::new("players", players),
+        groups_notification(driver, subscription),
+    ]
+}
+
+fn groups_notification(driver: &HeosDriver, subscription: &Subscription) -> Notification {
+    let groups: Vec<ApiGroup> = driver
+        .groups()
+        .into_iter()
+        .filter(|group| subscription.wants_group(group.gid))
+        .map(ApiGroup::from)
+        .collect();
+    Notification::new("groups", groups)
+}
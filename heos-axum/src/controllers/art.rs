@@ -0,0 +1,60 @@
+use axum::extract::{Path, Query};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Router};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::art::{self, ArtCache};
+
+#[derive(Debug, Deserialize)]
+pub struct ArtParams {
+    size: Option<u32>,
+}
+
+/// The album art behind `/art/{hash}`, see [`art::art_url`].
+pub async fn show_art(
+    Path(hash): Path<String>,
+    Query(params): Query<ArtParams>,
+    headers: HeaderMap,
+    Extension(cache): Extension<ArtCache>,
+) -> Response {
+    let etag = art::etag(&art::art_name(&hash, params.size));
+    if headers
+        .get(IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes())
+    {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [
+                (ETAG, etag),
+                (CACHE_CONTROL, art::CACHE_CONTROL.to_string()),
+            ],
+        )
+            .into_response();
+    }
+    match cache.get(&hash, params.size).await {
+        Ok(Some(art)) => (
+            [
+                (CONTENT_TYPE, art.content_type.to_string()),
+                (ETAG, art.etag),
+                (CACHE_CONTROL, art::CACHE_CONTROL.to_string()),
+            ],
+            art.bytes,
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!("Failed to get art {}: {:#}", hash, err);
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+    }
+}
+
+pub fn router(cache: ArtCache) -> Router {
+    Router::new()
+        .route("/art/:hash", get(show_art))
+        .layer(Extension(cache))
+}
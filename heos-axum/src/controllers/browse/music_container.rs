@@ -1,49 +1,47 @@
 use axum::extract::{Path, Query};
+use axum::response::Response;
 use axum::Extension;
 
 use serde::Deserialize;
 use tracing::info;
 
-use heos_api::types::Range;
+use heos_api::types::{ContainerId, Range, SourceId};
 use heos_api::HeosDriver;
 
 use crate::error::AppError;
-
+use crate::negotiate::Negotiated;
 use crate::views::pages::music_containers::BrowseMusicContainerPage;
 
+const PAGE_SIZE: u16 = 50;
+
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct Params {
+    /// first item, starting with 0
     #[serde(default)]
     start: Option<u16>,
+    /// last item
     #[serde(default)]
     end: Option<u16>,
 }
 
 pub async fn browse_music_container(
+    negotiated: Negotiated,
     Query(params): Query<Params>,
-    Path((source_id, container_id)): Path<(i64, String)>,
+    Path((source_id, container_id)): Path<(SourceId, ContainerId)>,
     Extension(driver): Extension<HeosDriver>,
-) -> Result<BrowseMusicContainerPage, AppError> {
+) -> Result<Response, AppError> {
     info!("Enter browse_container");
-    let range = match (params.start, params.end) {
-        (Some(start), Some(end)) => Range { start, end },
-        (Some(start), None) => Range {
-            start,
-            end: start + 10,
-        },
-        (None, Some(end)) => Range { start: 0, end },
-        _ => Range::default(),
+    let start = params.start.unwrap_or(0);
+    let range = match params.end {
+        Some(end) if end >= start => Range { start, end },
+        _ => Range::page(start, PAGE_SIZE),
     };
-    let items = driver
-        .browse_music_containers(&source_id, &container_id, &range.clone())
+    let page = driver
+        .browse_page(&source_id, &container_id, &range)
         .await?;
-    Ok(BrowseMusicContainerPage {
-        items: items.items,
+    Ok(negotiated.respond(BrowseMusicContainerPage {
         source_id,
-        count: items.count,
-        returned: items.returned,
         container_id,
-        range,
-    })
+        page,
+    }))
 }
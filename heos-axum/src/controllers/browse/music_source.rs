@@ -1,53 +1,59 @@
 use std::sync::Arc;
 use axum::extract::Path;
+use axum::response::Response;
 use axum::Extension;
 
 use heos_api::types::browse::BroseSourceItem;
+use heos_api::types::SourceId;
 use heos_api::HeosDriver;
 use crate::controllers::BaseUrl;
 
 use crate::error::AppError;
+use crate::negotiate::Negotiated;
 use crate::views::pages::music_sources::{
     BrowseMusicSourcePage, MusicSourcesPages, SourceDetailsPage,
 };
 
 pub async fn source_details(
-    Path(source_id): Path<i64>,
+    negotiated: Negotiated,
+    Path(source_id): Path<SourceId>,
     Extension(driver): Extension<HeosDriver>,
-) -> Result<SourceDetailsPage, AppError> {
+) -> Result<Response, AppError> {
     let source = driver
         .music_sources()
         .into_iter()
         .find(|s| s.sid == source_id)
         .ok_or(AppError::NotFound)?;
-    Ok(SourceDetailsPage { source })
+    Ok(negotiated.respond(SourceDetailsPage { source }))
 }
 
 pub async fn list_music_sources(
+    negotiated: Negotiated,
     Extension(driver): Extension<HeosDriver>,
     Extension(baseUrl): Extension<Arc<BaseUrl>>,
-) -> Result<MusicSourcesPages, AppError> {
+) -> Response {
     let music_sources = driver.music_sources();
-    Ok(MusicSourcesPages {
+    negotiated.respond(MusicSourcesPages {
         base_uri: baseUrl.as_str().to_string(),
         music_sources,
     })
 }
 
 pub async fn browse_music_source(
-    Path(source_id): Path<i64>,
+    negotiated: Negotiated,
+    Path(source_id): Path<SourceId>,
     Extension(driver): Extension<HeosDriver>,
-) -> Result<BrowseMusicSourcePage, AppError> {
+) -> Result<Response, AppError> {
     let contents = driver.browse(source_id).await?;
     use itertools::{Either, Itertools};
     let (services, media_items) = contents.into_iter().partition_map(|item| match item {
         BroseSourceItem::HeosService(service) => Either::Left(service),
         BroseSourceItem::BrowsableMedia(media) => Either::Right(media),
     });
-    Ok(BrowseMusicSourcePage {
+    Ok(negotiated.respond(BrowseMusicSourcePage {
         base_uri: "".to_string(),
         source_id,
         services,
         media_items,
-    })
+    }))
 }
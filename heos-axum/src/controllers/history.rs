@@ -0,0 +1,24 @@
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+
+use crate::error::AppError;
+use crate::history::History;
+use crate::negotiate::Negotiated;
+use crate::views::pages::history::HistoryPage;
+
+pub async fn show_history(
+    negotiated: Negotiated,
+    Extension(history): Extension<History>,
+) -> Result<Response, AppError> {
+    Ok(negotiated.respond(HistoryPage {
+        listens: history.store.recent(100, None)?,
+        submitting: history.submitter.is_some(),
+    }))
+}
+
+pub fn router(history: History) -> Router {
+    Router::new()
+        .route("/history", get(show_history))
+        .layer(Extension(history))
+}
@@ -0,0 +1,16 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+
+/// The metrics of heos-api for Prometheus.
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, heos_api::metrics::CONTENT_TYPE)],
+        heos_api::metrics::gather(),
+    )
+}
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(metrics))
+}
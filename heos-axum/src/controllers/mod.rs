@@ -12,17 +12,26 @@ use tokio::signal;
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
+use heos_api::rules::RuleEngine;
 use heos_api::HeosDriver;
 
+use crate::art::ArtCache;
 use crate::config::Config;
+use crate::history::{tracker, History};
 
 // this is generated before build
 use crate::templates::statics::StaticFile;
 
+mod api;
+mod art;
 mod browse;
 mod error;
+mod history;
 mod login;
+mod metrics;
 mod players;
+mod policies;
+mod rules;
 mod zones;
 
 #[derive(Clone)]
@@ -38,7 +47,12 @@ impl BaseUrl {
 }
 
 pub async fn serve(config: Config, driver: HeosDriver) -> anyhow::Result<()> {
-    let app = router(&config, driver)
+    let history = config.open_history()?;
+    tokio::spawn(tracker::run(driver.clone(), history.clone()));
+    let art = config.open_art_cache()?;
+    let rules = config.load_rules()?;
+    tokio::spawn(rules.clone().run(driver.clone()));
+    let app = router(&config, driver, history, art, rules)
         .fallback(error::code_404.into_service())
         // See https://docs.rs/tower-http/0.1.1/tower_http/trace/index.html for more details.
         .layer(TraceLayer::new_for_http());
@@ -52,12 +66,24 @@ pub async fn serve(config: Config, driver: HeosDriver) -> anyhow::Result<()> {
         .context("error running HTTP server")
 }
 
-fn router(config: &Config, driver: HeosDriver) -> Router {
+fn router(
+    config: &Config,
+    driver: HeosDriver,
+    history: History,
+    art: ArtCache,
+    rules: RuleEngine,
+) -> Router {
     // This is the order that the modules were authored in.
     browse::router(driver.clone(), &config)
         .route("/assets/:filename", get(static_files))
         .merge(login::router(driver.clone()))
         .merge(players::router(driver.clone()))
+        .merge(policies::router(driver.clone()))
+        .merge(history::router(history.clone()))
+        .merge(api::router(driver.clone(), history))
+        .merge(metrics::router())
+        .merge(art::router(art))
+        .merge(rules::router(rules))
         .merge(zones::router(driver))
 }
 
@@ -1,24 +1,18 @@
-use axum::response::IntoResponse;
+use axum::response::Response;
 use axum::routing::get;
 use axum::{Extension, Router};
 use heos_api::HeosDriver;
-use maud::html;
 
-pub async fn show_players(Extension(driver): Extension<HeosDriver>) -> impl IntoResponse {
-    let players = driver.players();
-    html!({
-        div {
-            ol {
-                @for player in players {
-                    li id=(player.player_id) {
-                        p { (player.name) }
-                        p { (player.volume)}
-                    }
-                }
-            }
-        }
+use crate::negotiate::Negotiated;
+use crate::views::pages::players::PlayersPage;
+
+pub async fn show_players(
+    negotiated: Negotiated,
+    Extension(driver): Extension<HeosDriver>,
+) -> Response {
+    negotiated.respond(PlayersPage {
+        players: driver.players(),
     })
-    .into_response()
 }
 
 pub fn router(driver: HeosDriver) -> Router {
@@ -0,0 +1,24 @@
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+
+use heos_api::HeosDriver;
+
+use crate::negotiate::Negotiated;
+use crate::views::pages::policies::VolumePoliciesPage;
+
+pub async fn show_policies(
+    negotiated: Negotiated,
+    Extension(driver): Extension<HeosDriver>,
+) -> Response {
+    negotiated.respond(VolumePoliciesPage {
+        policies: driver.volume_policies().iter().cloned().collect(),
+        now: chrono::Local::now().time(),
+    })
+}
+
+pub fn router(driver: HeosDriver) -> Router {
+    Router::new()
+        .route("/policies", get(show_policies))
+        .layer(Extension(driver))
+}
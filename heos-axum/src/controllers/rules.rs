@@ -0,0 +1,25 @@
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+
+use heos_api::rules::RuleEngine;
+
+use crate::negotiate::Negotiated;
+use crate::views::pages::rules::RulesPage;
+
+pub async fn show_rules(
+    negotiated: Negotiated,
+    Extension(rules): Extension<RuleEngine>,
+) -> Response {
+    negotiated.respond(RulesPage {
+        rules: rules.rules().to_vec(),
+        log: rules.log(),
+        dry_run: rules.dry_run(),
+    })
+}
+
+pub fn router(rules: RuleEngine) -> Router {
+    Router::new()
+        .route("/rules", get(show_rules))
+        .layer(Extension(rules))
+}
@@ -1,12 +1,12 @@
 use crate::error::AppError;
 
-use crate::views::pages::page;
+use crate::negotiate::Negotiated;
 use crate::views::zones::edit::EditZoneMembers;
 use crate::views::zones::listing::ZonesPage;
 use anyhow::anyhow;
 use anyhow::Context;
 use axum::extract::Path;
-use axum::response::{IntoResponse, Redirect};
+use axum::response::{Redirect, Response};
 use axum::routing::{get, post};
 use axum::{Extension, Form, Router};
 
@@ -18,19 +18,22 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use tracing::info;
 
-pub async fn show_zones(Extension(driver): Extension<HeosDriver>) -> impl IntoResponse {
-    let _groups = driver.groups();
-    let pages = ZonesPage::new(driver.players(), driver.groups());
-    page(pages.render_html())
+pub async fn show_zones(
+    negotiated: Negotiated,
+    Extension(driver): Extension<HeosDriver>,
+) -> Response {
+    let zones = ZonesPage::new(driver.players(), driver.groups());
+    negotiated.respond(zones)
 }
 
 pub async fn show_edit_zone_members(
-    Path(zone_id): Path<i64>,
+    negotiated: Negotiated,
+    Path(zone_id): Path<PlayerId>,
     Extension(driver): Extension<HeosDriver>,
-) -> Result<EditZoneMembers, AppError> {
+) -> Result<Response, AppError> {
     info!("Start show_edit_zone_members");
     info!("Found group to edit");
-    let mut players: BTreeMap<i64, HeosPlayer> = driver
+    let mut players: BTreeMap<PlayerId, HeosPlayer> = driver
         .players()
         .into_iter()
         .map(|p| (p.player_id, p))
@@ -38,7 +41,7 @@ pub async fn show_edit_zone_members(
     let player_to_edit = players.remove(&zone_id).ok_or(AppError::NotFound)?;
     let page = EditZoneMembers::new(player_to_edit, players.into_values());
     info!("Page: {:?}", &page.members);
-    Ok(page)
+    Ok(negotiated.respond(page))
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -60,7 +63,7 @@ impl ChangeZoneMemberForm {
     }
 }
 pub async fn change_zone_members(
-    Path(zone_id): Path<i64>,
+    Path(zone_id): Path<PlayerId>,
     Form(form): Form<ChangeZoneMemberForm>,
     Extension(driver): Extension<HeosDriver>,
 ) -> Result<Redirect, AppError> {
@@ -0,0 +1,199 @@
+//! The listens in the formats of ListenBrainz and Last.fm.
+//!
+//! ListenBrainz takes JSON, see
+//! <https://listenbrainz.readthedocs.io/en/latest/users/json.html>. Last.fm importers
+//! read the `.scrobbler.log` of the Audioscrobbler portable player format.
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+
+use crate::history::store::HistoryStore;
+use crate::history::Listen;
+
+const CLIENT: &str = "heos-axum";
+
+// ListenBrainz takes at most 1000 listens per request, smaller is friendlier.
+const CHUNK: usize = 100;
+
+/// The listens as the payload of a ListenBrainz submission, `listen_type` is
+/// `import`, `single` or `playing_now`.
+pub fn listenbrainz(listens: &[Listen], listen_type: &str) -> Value {
+    let payload: Vec<Value> = listens
+        .iter()
+        .map(|listen| {
+            let mut additional_info = json!({
+                "media_player": "HEOS",
+                "submission_client": CLIENT,
+                "submission_client_version": env!("CARGO_PKG_VERSION"),
+            });
+            if let Some(source) = &listen.source {
+                additional_info["music_service_name"] = json!(source);
+            }
+            if let Some(duration) = listen.duration {
+                additional_info["duration_ms"] = json!(duration);
+            }
+            let mut track_metadata = json!({
+                "artist_name": listen.artist,
+                "track_name": listen.song,
+                "additional_info": additional_info,
+            });
+            if !listen.album.is_empty() {
+                track_metadata["release_name"] = json!(listen.album);
+            }
+            json!({
+                "listened_at": listen.started_at,
+                "track_metadata": track_metadata,
+            })
+        })
+        .collect();
+    json!({
+        "listen_type": listen_type,
+        "payload": payload,
+    })
+}
+
+/// The listens as an Audioscrobbler `.scrobbler.log`, the format Last.fm
+/// importers understand. Completed listens are marked `L`, skipped ones `S`.
+pub fn scrobbler_log(listens: &[Listen]) -> String {
+    let mut log = format!(
+        "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/{} {}\n",
+        CLIENT,
+        env!("CARGO_PKG_VERSION")
+    );
+    for listen in listens {
+        let duration = listen
+            .duration
+            .map(|duration| (duration / 1000).to_string())
+            .unwrap_or_default();
+        let rating = if listen.completed { "L" } else { "S" };
+        let fields = [
+            field(&listen.artist),
+            field(&listen.album),
+            field(&listen.song),
+            String::new(),
+            duration,
+            rating.to_string(),
+            listen.started_at.to_string(),
+            String::new(),
+        ];
+        log.push_str(&fields.join("\t"));
+        log.push('\n');
+    }
+    log
+}
+
+// tabs and newlines separate the fields and rows.
+fn field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Submits listens to ListenBrainz, or anything speaking its API.
+#[derive(Clone)]
+pub struct Submitter {
+    url: String,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl Submitter {
+    pub fn new(url: impl Into<String>, token: impl Into<String>) -> Self {
+        Submitter {
+            url: url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn submit(&self, listens: &[Listen]) -> anyhow::Result<()> {
+        for chunk in listens.chunks(CHUNK) {
+            self.post(chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Submits the completed listens not submitted yet and marks them, returns how
+    /// many were submitted. Each chunk is marked as soon as it is accepted, a
+    /// failing one leaves only itself and the ones after it pending.
+    pub async fn submit_pending(&self, store: &HistoryStore) -> anyhow::Result<usize> {
+        let pending = store.pending()?;
+        let mut submitted = 0;
+        for chunk in pending.chunks(CHUNK) {
+            self.post(chunk).await?;
+            let ids: Vec<i64> = chunk.iter().map(|listen| listen.id).collect();
+            store.mark_submitted(&ids, chrono::Utc::now().timestamp())?;
+            submitted += ids.len();
+        }
+        Ok(submitted)
+    }
+
+    // one listen is a `single` one, as when it was just played.
+    async fn post(&self, listens: &[Listen]) -> anyhow::Result<()> {
+        let listen_type = if listens.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        let response = self
+            .http
+            .post(format!("{}/1/submit-listens", self.url))
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&listenbrainz(listens, listen_type))
+            .send()
+            .await
+            .context("Failed to reach ListenBrainz")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("ListenBrainz answered {}: {}", status, body);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::{PlayerId, SourceId};
+
+    use super::*;
+
+    fn listen(completed: bool) -> Listen {
+        Listen {
+            id: 1,
+            pid: PlayerId(1),
+            player: "Kitchen".to_string(),
+            song: "Dreams".to_string(),
+            artist: "Fleetwood Mac".to_string(),
+            album: "Rumours\tDeluxe".to_string(),
+            sid: SourceId(1024),
+            source: Some("Local Music".to_string()),
+            started_at: 1000,
+            ended_at: Some(1257),
+            duration: Some(257_000),
+            played: 257_000,
+            completed,
+            submitted_at: None,
+        }
+    }
+
+    #[test]
+    pub fn test_exports() {
+        let json = listenbrainz(&[listen(true)], "import");
+        assert_eq!(json["listen_type"], "import");
+        let listen_json = &json["payload"][0];
+        assert_eq!(listen_json["listened_at"], 1000);
+        assert_eq!(listen_json["track_metadata"]["track_name"], "Dreams");
+        assert_eq!(
+            listen_json["track_metadata"]["additional_info"]["duration_ms"],
+            257_000
+        );
+
+        let log = scrobbler_log(&[listen(true), listen(false)]);
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines[0], "#AUDIOSCROBBLER/1.1");
+        assert_eq!(
+            lines[3],
+            "Fleetwood Mac\tRumours Deluxe\tDreams\t\t257\tL\t1000\t"
+        );
+        assert!(lines[4].contains("\tS\t"));
+    }
+}
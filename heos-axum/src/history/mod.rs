@@ -0,0 +1,44 @@
+//! The listening history: which track played on which player and for how long.
+//!
+//! The [`tracker`] follows the events of the driver and writes the listens to a
+//! SQLite [`store`], [`export`] turns them into ListenBrainz or Last.fm formats.
+
+use heos_api::types::{Milliseconds, PlayerId, SourceId};
+
+pub mod export;
+pub mod store;
+pub mod tracker;
+
+pub use export::Submitter;
+pub use store::HistoryStore;
+
+/// A track played on a player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listen {
+    pub id: i64,
+    pub pid: PlayerId,
+    pub player: String,
+    pub song: String,
+    pub artist: String,
+    pub album: String,
+    pub sid: SourceId,
+    pub source: Option<String>,
+    /// unix time in seconds
+    pub started_at: i64,
+    /// unix time in seconds, missing while the track still plays
+    pub ended_at: Option<i64>,
+    pub duration: Option<Milliseconds>,
+    /// the furthest position reached
+    pub played: Milliseconds,
+    /// played past half of its duration
+    pub completed: bool,
+    /// unix time in seconds of the submission to ListenBrainz
+    pub submitted_at: Option<i64>,
+}
+
+/// The history as the app uses it, the store and where to submit listens to.
+#[derive(Clone)]
+pub struct History {
+    pub store: HistoryStore,
+    pub submitter: Option<Submitter>,
+}
@@ -0,0 +1,258 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use heos_api::types::{Milliseconds, PlayerId, SourceId};
+
+use crate::history::Listen;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS listens (
+    id INTEGER PRIMARY KEY,
+    pid INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    song TEXT NOT NULL,
+    artist TEXT NOT NULL,
+    album TEXT NOT NULL,
+    sid INTEGER NOT NULL,
+    source TEXT,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    duration INTEGER,
+    played INTEGER NOT NULL DEFAULT 0,
+    completed INTEGER NOT NULL DEFAULT 0,
+    submitted_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS listens_started_at ON listens (started_at);
+";
+
+const COLUMNS: &str = "id, pid, player, song, artist, album, sid, source, started_at, ended_at, \
+     duration, played, completed, submitted_at";
+
+/// The start of a listen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewListen {
+    pub pid: PlayerId,
+    pub player: String,
+    pub song: String,
+    pub artist: String,
+    pub album: String,
+    pub sid: SourceId,
+    pub source: Option<String>,
+    pub started_at: i64,
+}
+
+/// The listens in a SQLite database.
+///
+/// The queries are small, so they run right on the calling task.
+#[derive(Clone)]
+pub struct HistoryStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl HistoryStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open the history {:?}", path))?;
+        HistoryStore::init(connection)
+    }
+
+    pub fn in_memory() -> anyhow::Result<Self> {
+        HistoryStore::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> anyhow::Result<Self> {
+        connection
+            .execute_batch(SCHEMA)
+            .context("Failed to create the history tables")?;
+        Ok(HistoryStore {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    pub fn start(&self, listen: &NewListen) -> anyhow::Result<i64> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO listens (pid, player, song, artist, album, sid, source, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                listen.pid.0,
+                listen.player,
+                listen.song,
+                listen.artist,
+                listen.album,
+                listen.sid.0,
+                listen.source,
+                listen.started_at
+            ],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    pub fn finish(
+        &self,
+        id: i64,
+        ended_at: i64,
+        played: Milliseconds,
+        duration: Option<Milliseconds>,
+    ) -> anyhow::Result<()> {
+        let completed = duration.is_some_and(|duration| duration > 0 && played * 2 >= duration);
+        self.connection.lock().unwrap().execute(
+            "UPDATE listens SET ended_at = ?2, played = ?3, duration = ?4, completed = ?5
+             WHERE id = ?1",
+            params![id, ended_at, played, duration, completed],
+        )?;
+        Ok(())
+    }
+
+    /// Ends the listens still open, left by a process that stopped while they
+    /// played. They end at the last position recorded and are not completed.
+    /// Returns how many there were.
+    pub fn close_open(&self) -> anyhow::Result<usize> {
+        let closed = self.connection.lock().unwrap().execute(
+            "UPDATE listens SET ended_at = started_at + played / 1000 WHERE ended_at IS NULL",
+            [],
+        )?;
+        Ok(closed)
+    }
+
+    pub fn get(&self, id: i64) -> anyhow::Result<Option<Listen>> {
+        let connection = self.connection.lock().unwrap();
+        let listen = connection
+            .query_row(
+                &format!("SELECT {} FROM listens WHERE id = ?1", COLUMNS),
+                [id],
+                listen,
+            )
+            .optional()?;
+        Ok(listen)
+    }
+
+    /// The latest listens first, older than the listen `before` if given.
+    pub fn recent(&self, limit: u32, before: Option<i64>) -> anyhow::Result<Vec<Listen>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM listens WHERE id < ?1 ORDER BY id DESC LIMIT ?2",
+                COLUMNS
+            ),
+            params![before.unwrap_or(i64::MAX), limit],
+        )
+    }
+
+    /// All completed listens, the oldest first.
+    pub fn completed(&self) -> anyhow::Result<Vec<Listen>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM listens WHERE completed ORDER BY id",
+                COLUMNS
+            ),
+            [],
+        )
+    }
+
+    /// The completed listens not submitted yet, the oldest first.
+    pub fn pending(&self) -> anyhow::Result<Vec<Listen>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM listens WHERE completed AND submitted_at IS NULL ORDER BY id",
+                COLUMNS
+            ),
+            [],
+        )
+    }
+
+    pub fn mark_submitted(&self, ids: &[i64], at: i64) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for id in ids {
+            transaction.execute(
+                "UPDATE listens SET submitted_at = ?2 WHERE id = ?1",
+                params![id, at],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn query<P: rusqlite::Params>(&self, sql: &str, params: P) -> anyhow::Result<Vec<Listen>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(sql)?;
+        let listens = statement
+            .query_map(params, listen)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(listens)
+    }
+}
+
+fn listen(row: &Row) -> rusqlite::Result<Listen> {
+    Ok(Listen {
+        id: row.get(0)?,
+        pid: PlayerId(row.get(1)?),
+        player: row.get(2)?,
+        song: row.get(3)?,
+        artist: row.get(4)?,
+        album: row.get(5)?,
+        sid: SourceId(row.get(6)?),
+        source: row.get(7)?,
+        started_at: row.get(8)?,
+        ended_at: row.get(9)?,
+        duration: row.get(10)?,
+        played: row.get(11)?,
+        completed: row.get(12)?,
+        submitted_at: row.get(13)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_listen(song: &str, started_at: i64) -> NewListen {
+        NewListen {
+            pid: PlayerId(1),
+            player: "Kitchen".to_string(),
+            song: song.to_string(),
+            artist: "Fleetwood Mac".to_string(),
+            album: "Rumours".to_string(),
+            sid: SourceId(1024),
+            source: Some("Local Music".to_string()),
+            started_at,
+        }
+    }
+
+    #[test]
+    pub fn test_store() {
+        let store = HistoryStore::in_memory().unwrap();
+        let dreams = store.start(&new_listen("Dreams", 1000)).unwrap();
+        store.finish(dreams, 1250, 200_000, Some(257_000)).unwrap();
+        let chain = store.start(&new_listen("The Chain", 1250)).unwrap();
+        store.finish(chain, 1300, 50_000, Some(270_000)).unwrap();
+        let playing = store.start(&new_listen("Go Your Own Way", 1300)).unwrap();
+
+        let recent = store.recent(10, None).unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].id, playing);
+        assert_eq!(recent[0].ended_at, None);
+        assert_eq!(store.recent(10, Some(playing)).unwrap().len(), 2);
+
+        // only the one played past the half counts
+        let completed = store.completed().unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].song, "Dreams");
+        assert!(!store.get(chain).unwrap().unwrap().completed);
+
+        store.mark_submitted(&[dreams], 2000).unwrap();
+        assert!(store.pending().unwrap().is_empty());
+        assert_eq!(store.get(dreams).unwrap().unwrap().submitted_at, Some(2000));
+
+        // the process stopped while it played
+        assert_eq!(store.close_open().unwrap(), 1);
+        let closed = store.get(playing).unwrap().unwrap();
+        assert_eq!(closed.ended_at, Some(1300));
+        assert!(!closed.completed);
+        assert_eq!(store.close_open().unwrap(), 0);
+    }
+}
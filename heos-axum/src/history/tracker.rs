@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use heos_api::types::browse::MusicSource;
+use heos_api::types::event::HeosEvent;
+use heos_api::types::player::{HeosPlayer, PlayState};
+use heos_api::types::{Milliseconds, PlayerId};
+use heos_api::HeosDriver;
+
+use crate::history::store::{HistoryStore, NewListen};
+use crate::history::History;
+
+// what is playing right now on a player.
+#[derive(Debug, Clone)]
+struct Playing {
+    id: i64,
+    track: (String, String, String),
+    played: Milliseconds,
+    duration: Option<Milliseconds>,
+}
+
+/// Turns the events of the driver into listens.
+pub struct Tracker {
+    store: HistoryStore,
+    playing: HashMap<PlayerId, Playing>,
+}
+
+impl Tracker {
+    pub fn new(store: HistoryStore) -> Self {
+        Tracker {
+            store,
+            playing: HashMap::new(),
+        }
+    }
+
+    /// Handles an event after the driver updated its state, returns the ids of the
+    /// finished listens. `now` is the unix time in seconds.
+    pub fn on_event(
+        &mut self,
+        event: &HeosEvent,
+        players: &[HeosPlayer],
+        sources: &[MusicSource],
+        now: i64,
+    ) -> anyhow::Result<Vec<i64>> {
+        let mut finished = vec![];
+        match event {
+            HeosEvent::PlayerNowPlayingChanged { player_id } => {
+                let player = players.iter().find(|p| p.player_id == *player_id);
+                let track = player.and_then(track);
+                let same = match (self.playing.get(player_id), &track) {
+                    (Some(playing), Some(track)) => &playing.track == track,
+                    _ => false,
+                };
+                // HEOS likes to tell twice.
+                if !same {
+                    finished.extend(self.finish(*player_id, now)?);
+                    if let Some(player) = player {
+                        self.start(player, sources, now, 0)?;
+                    }
+                }
+            }
+            HeosEvent::PlayerStateChanged {
+                player_id,
+                state: PlayState::Stop,
+            } => finished.extend(self.finish(*player_id, now)?),
+            HeosEvent::PlayerNowPlayingProgress {
+                player_id,
+                cur_pos,
+                duration,
+            } => {
+                if !self.playing.contains_key(player_id) {
+                    // started before we were listening
+                    if let Some(player) = players.iter().find(|p| p.player_id == *player_id) {
+                        if player.play_state == PlayState::Play {
+                            self.start(player, sources, now, *cur_pos)?;
+                        }
+                    }
+                }
+                if let Some(playing) = self.playing.get_mut(player_id) {
+                    playing.played = playing.played.max(*cur_pos);
+                    if duration.is_some() {
+                        playing.duration = *duration;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(finished)
+    }
+
+    fn start(
+        &mut self,
+        player: &HeosPlayer,
+        sources: &[MusicSource],
+        now: i64,
+        position: Milliseconds,
+    ) -> anyhow::Result<()> {
+        let (media, track) = match (&player.now_playing, track(player)) {
+            (Some(media), Some(track)) => (media, track),
+            _ => return Ok(()),
+        };
+        let id = self.store.start(&NewListen {
+            pid: player.player_id,
+            player: player.name.clone(),
+            song: media.song.clone(),
+            artist: media.artist.clone(),
+            album: media.album.clone(),
+            sid: media.sid,
+            source: sources
+                .iter()
+                .find(|source| source.sid == media.sid)
+                .map(|source| source.name.clone()),
+            started_at: now - (position / 1000) as i64,
+        })?;
+        self.playing.insert(
+            player.player_id,
+            Playing {
+                id,
+                track,
+                played: position,
+                duration: None,
+            },
+        );
+        Ok(())
+    }
+
+    fn finish(&mut self, player_id: PlayerId, now: i64) -> anyhow::Result<Option<i64>> {
+        match self.playing.remove(&player_id) {
+            Some(playing) => {
+                self.store
+                    .finish(playing.id, now, playing.played, playing.duration)?;
+                Ok(Some(playing.id))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn track(player: &HeosPlayer) -> Option<(String, String, String)> {
+    let media = player.now_playing.as_ref()?;
+    if media.song.is_empty() {
+        return None;
+    }
+    Some((
+        media.song.clone(),
+        media.artist.clone(),
+        media.album.clone(),
+    ))
+}
+
+/// Records the listens until the driver goes away, submitting completed ones if a
+/// submitter is configured.
+pub async fn run(driver: HeosDriver, history: History) {
+    match history.store.close_open() {
+        Ok(0) => {}
+        Ok(closed) => info!("Closed {} listens left open by the last run", closed),
+        Err(err) => warn!("Failed to close the open listens: {:#}", err),
+    }
+    let mut events = driver.subscribe();
+    let mut tracker = Tracker::new(history.store.clone());
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("History missed {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let now = chrono::Utc::now().timestamp();
+        let finished = tracker.on_event(&event, &driver.players(), &driver.music_sources(), now);
+        match (finished, &history.submitter) {
+            (Ok(finished), Some(submitter)) if !finished.is_empty() => {
+                match submitter.submit_pending(&history.store).await {
+                    Ok(0) => {}
+                    Ok(submitted) => info!("Submitted {} listens", submitted),
+                    Err(err) => warn!("Failed to submit listens: {:#}", err),
+                }
+            }
+            (Ok(_), _) => {}
+            (Err(err), _) => warn!("Failed to record the history: {:#}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::player::{MediaType, NowPlayingMedia};
+    use heos_api::types::{QueueId, SourceId};
+
+    use super::*;
+
+    fn player(song: &str, state: PlayState) -> HeosPlayer {
+        HeosPlayer {
+            volume: 20,
+            now_playing: Some(NowPlayingMedia {
+                media_type: MediaType::Song,
+                song: song.to_string(),
+                album: "Rumours".to_string(),
+                artist: "Fleetwood Mac".to_string(),
+                image_url: String::new(),
+                station: None,
+                mid: Default::default(),
+                qid: QueueId(1),
+                sid: SourceId(1024),
+                album_id: String::new(),
+                extra: Default::default(),
+            }),
+            play_state: state,
+            ..HeosPlayer::test_player(1, "Kitchen")
+        }
+    }
+
+    fn progress(cur_pos: Milliseconds) -> HeosEvent {
+        HeosEvent::PlayerNowPlayingProgress {
+            player_id: PlayerId(1),
+            cur_pos,
+            duration: Some(200_000),
+        }
+    }
+
+    #[test]
+    pub fn test_tracker() {
+        let store = HistoryStore::in_memory().unwrap();
+        let mut tracker = Tracker::new(store.clone());
+        let changed = HeosEvent::PlayerNowPlayingChanged {
+            player_id: PlayerId(1),
+        };
+
+        let dreams = [player("Dreams", PlayState::Play)];
+        tracker.on_event(&changed, &dreams, &[], 1000).unwrap();
+        // told twice, still one listen
+        tracker.on_event(&changed, &dreams, &[], 1001).unwrap();
+        tracker
+            .on_event(&progress(120_000), &dreams, &[], 1120)
+            .unwrap();
+
+        let chain = [player("The Chain", PlayState::Play)];
+        let finished = tracker.on_event(&changed, &chain, &[], 1130).unwrap();
+        assert_eq!(finished.len(), 1);
+        let listen = store.get(finished[0]).unwrap().unwrap();
+        assert_eq!(listen.song, "Dreams");
+        assert_eq!((listen.started_at, listen.ended_at), (1000, Some(1130)));
+        assert!(listen.completed);
+
+        tracker
+            .on_event(&progress(30_000), &chain, &[], 1160)
+            .unwrap();
+        let stopped = HeosEvent::PlayerStateChanged {
+            player_id: PlayerId(1),
+            state: PlayState::Stop,
+        };
+        let finished = tracker.on_event(&stopped, &chain, &[], 1170).unwrap();
+        assert!(!store.get(finished[0]).unwrap().unwrap().completed);
+        assert_eq!(store.recent(10, None).unwrap().len(), 2);
+    }
+}
@@ -1,3 +1,6 @@
+// the templates ructe generates start with `#[allow(renamed_and_removed_lints)]`.
+#![allow(clippy::useless_attribute)]
+
 use heos_api::HeosDriver;
 
 // macros need to go to the top!
@@ -13,10 +16,13 @@ pub mod axum_ructe;
 /// Defines the arguments required to start the server application using [`clap`].
 ///
 /// [`clap`]: https://github.com/clap-rs/clap/
+pub mod art;
 pub mod config;
 pub mod controllers;
 pub mod error;
+pub mod history;
 pub mod models;
+pub mod negotiate;
 pub mod views;
 #[derive(Clone)]
 pub struct ApiContext {
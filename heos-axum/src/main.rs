@@ -22,6 +22,8 @@ async fn main() -> anyhow::Result<()> {
         Some(addr) => HeosDriver::new((addr, 1255)).await?,
         None => heos_api::find_driver().await?
     };
+    diver.set_volume_policies(config.load_volume_policies()?);
+    diver.set_retry_policy(config.retry_policy());
     println!("Found driver, now starting http server");
     controllers::serve(config, diver).await?;
     Ok(())
@@ -0,0 +1,381 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use heos_api::types::browse::{
+    BroseSourceItem, BrowsableMedia, BrowseMusicContainerResponse, MusicSource,
+};
+use heos_api::types::group::{Group, GroupRole};
+use heos_api::types::player::{HeosPlayer, NowPlayingMedia, PlayState, QueueEntry};
+use heos_api::types::{
+    ContainerId, GroupId, Level, MediaId, Page, PlayMode, PlayerId, QueueId, SourceId, YesOrNo,
+};
+
+use crate::history::Listen;
+
+// These are the json representations of the /api/v1 endpoints.
+// They are separate from the heos_api types so the api stays stable
+// when the HEOS protocol types change.
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiPlayState {
+    Play,
+    Pause,
+    Stop,
+}
+
+impl From<PlayState> for ApiPlayState {
+    fn from(state: PlayState) -> Self {
+        match state {
+            PlayState::Play => ApiPlayState::Play,
+            PlayState::Pause => ApiPlayState::Pause,
+            PlayState::Stop => ApiPlayState::Stop,
+        }
+    }
+}
+
+impl From<ApiPlayState> for PlayState {
+    fn from(state: ApiPlayState) -> Self {
+        match state {
+            ApiPlayState::Play => PlayState::Play,
+            ApiPlayState::Pause => PlayState::Pause,
+            ApiPlayState::Stop => PlayState::Stop,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiRepeat {
+    Off,
+    OnOne,
+    OnAll,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiNowPlaying {
+    pub media_type: String,
+    pub song: String,
+    pub album: String,
+    pub artist: String,
+    pub station: Option<String>,
+    pub image_url: String,
+}
+
+impl From<NowPlayingMedia> for ApiNowPlaying {
+    fn from(media: NowPlayingMedia) -> Self {
+        ApiNowPlaying {
+            media_type: media.media_type.to_string(),
+            song: media.song,
+            album: media.album,
+            artist: media.artist,
+            station: media.station,
+            image_url: media.image_url,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiPlayer {
+    pub pid: PlayerId,
+    pub name: String,
+    pub volume: Level,
+    pub play_state: ApiPlayState,
+    /// the group the player belongs to
+    pub gid: Option<GroupId>,
+    pub repeat: Option<ApiRepeat>,
+    pub shuffle: Option<bool>,
+    pub now_playing: Option<ApiNowPlaying>,
+}
+
+impl From<HeosPlayer> for ApiPlayer {
+    fn from(player: HeosPlayer) -> Self {
+        let (repeat, shuffle) = match player.mode {
+            Some(mode) => (Some(repeat_to_api(&mode)), Some(shuffle_to_api(&mode))),
+            None => (None, None),
+        };
+        ApiPlayer {
+            pid: player.player_id,
+            name: player.name,
+            volume: player.volume,
+            play_state: player.play_state.into(),
+            gid: player.in_group,
+            repeat,
+            shuffle,
+            now_playing: player.now_playing.map(|media| media.into()),
+        }
+    }
+}
+
+fn repeat_to_api(mode: &PlayMode) -> ApiRepeat {
+    match mode.repeat {
+        heos_api::types::Repeat::Off => ApiRepeat::Off,
+        heos_api::types::Repeat::OnOne => ApiRepeat::OnOne,
+        heos_api::types::Repeat::OnAll => ApiRepeat::OnAll,
+    }
+}
+
+fn shuffle_to_api(mode: &PlayMode) -> bool {
+    mode.shuffle == heos_api::types::Shuffle::On
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiGroupMember {
+    pub pid: PlayerId,
+    pub name: String,
+    pub leader: bool,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiGroup {
+    pub gid: GroupId,
+    pub name: String,
+    pub volume: Level,
+    pub members: Vec<ApiGroupMember>,
+}
+
+impl From<Group> for ApiGroup {
+    fn from(group: Group) -> Self {
+        ApiGroup {
+            gid: group.gid,
+            name: group.name,
+            volume: group.volume,
+            members: group
+                .players
+                .into_iter()
+                .map(|member| ApiGroupMember {
+                    pid: member.pid,
+                    name: member.name,
+                    leader: member.role == GroupRole::Leader,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiMusicSource {
+    pub sid: SourceId,
+    pub name: String,
+    pub source_type: String,
+    pub image_url: String,
+    pub available: bool,
+}
+
+impl From<MusicSource> for ApiMusicSource {
+    fn from(source: MusicSource) -> Self {
+        ApiMusicSource {
+            sid: source.sid,
+            name: source.name,
+            source_type: source.source_type,
+            image_url: source.image_url,
+            available: source.available,
+        }
+    }
+}
+
+/// An entry of a music source or container. Either a service (with `sid`),
+/// a container (with `cid`) or a playable media item (with `mid`).
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiBrowseItem {
+    pub name: String,
+    pub item_type: String,
+    pub image_url: String,
+    pub sid: Option<SourceId>,
+    pub cid: Option<ContainerId>,
+    pub mid: Option<MediaId>,
+    pub playable: bool,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+impl From<BroseSourceItem> for ApiBrowseItem {
+    fn from(item: BroseSourceItem) -> Self {
+        match item {
+            BroseSourceItem::HeosService(service) => ApiBrowseItem {
+                name: service.name,
+                item_type: service.server_type,
+                image_url: service.image_url,
+                sid: Some(service.sid),
+                cid: None,
+                mid: None,
+                playable: false,
+                artist: None,
+                album: None,
+            },
+            BroseSourceItem::BrowsableMedia(media) => ApiBrowseItem {
+                name: media.name,
+                // the serde name is the one HEOS uses.
+                item_type: serde_json::to_value(&media.media_type)
+                    .ok()
+                    .and_then(|v| v.as_str().map(|s| s.to_string()))
+                    .unwrap_or_default(),
+                image_url: media.image_url,
+                sid: None,
+                cid: media.container_id,
+                mid: media.mid,
+                playable: media.playable == YesOrNo::Yes,
+                artist: media.artist,
+                album: media.album,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiBrowsePage {
+    pub sid: SourceId,
+    pub cid: ContainerId,
+    /// first item, starting with 0
+    pub start: u16,
+    /// last item requested, there may be fewer
+    pub end: u16,
+    /// number of items in the container
+    pub count: usize,
+    pub returned: usize,
+    pub has_next: bool,
+    pub has_previous: bool,
+    pub items: Vec<ApiBrowseItem>,
+}
+
+impl ApiBrowsePage {
+    pub fn new(sid: SourceId, response: BrowseMusicContainerResponse) -> Self {
+        let cid = response.cid.clone();
+        let returned = response.returned;
+        let page: Page<BrowsableMedia> = response.into();
+        ApiBrowsePage {
+            sid,
+            cid,
+            start: page.range.start,
+            end: page.range.end,
+            count: page.total,
+            returned,
+            has_next: page.has_next(),
+            has_previous: page.has_previous(),
+            items: page
+                .items
+                .into_iter()
+                .map(|media| BroseSourceItem::BrowsableMedia(media).into())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct ApiQueueEntry {
+    pub qid: QueueId,
+    pub song: String,
+    pub album: String,
+    pub artist: String,
+    pub image_url: String,
+}
+
+impl From<QueueEntry> for ApiQueueEntry {
+    fn from(entry: QueueEntry) -> Self {
+        ApiQueueEntry {
+            qid: entry.qid,
+            song: entry.song,
+            album: entry.album,
+            artist: entry.artist,
+            image_url: entry.image_url,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+pub struct SetPlayState {
+    pub state: ApiPlayState,
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+pub struct SetVolume {
+    pub level: Level,
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+pub struct SetMute {
+    pub mute: bool,
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+pub struct SetPlayMode {
+    pub repeat: ApiRepeat,
+    pub shuffle: bool,
+}
+
+impl From<SetPlayMode> for PlayMode {
+    fn from(mode: SetPlayMode) -> Self {
+        PlayMode {
+            repeat: match mode.repeat {
+                ApiRepeat::Off => heos_api::types::Repeat::Off,
+                ApiRepeat::OnOne => heos_api::types::Repeat::OnOne,
+                ApiRepeat::OnAll => heos_api::types::Repeat::OnAll,
+            },
+            shuffle: if mode.shuffle {
+                heos_api::types::Shuffle::On
+            } else {
+                heos_api::types::Shuffle::Off
+            },
+        }
+    }
+}
+
+/// Groups `members` with `leader`. An empty list of members removes the group.
+#[derive(Deserialize, ToSchema, Debug)]
+pub struct SetGroup {
+    pub leader: PlayerId,
+    #[serde(default)]
+    pub members: Vec<PlayerId>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ApiError {
+    pub error: String,
+    /// the HEOS error id, if HEOS refused the command
+    pub eid: Option<u8>,
+    pub text: Option<String>,
+}
+
+/// A track played on a player, times are unix seconds.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ApiListen {
+    pub id: i64,
+    pub pid: PlayerId,
+    pub player: String,
+    pub song: String,
+    pub artist: String,
+    pub album: String,
+    pub sid: SourceId,
+    pub source: Option<String>,
+    pub started_at: i64,
+    /// missing while the track still plays
+    pub ended_at: Option<i64>,
+    /// milliseconds
+    pub duration: Option<u64>,
+    /// milliseconds, the furthest position reached
+    pub played: u64,
+    /// played past half of its duration
+    pub completed: bool,
+    pub submitted_at: Option<i64>,
+}
+
+impl From<Listen> for ApiListen {
+    fn from(listen: Listen) -> Self {
+        ApiListen {
+            id: listen.id,
+            pid: listen.pid,
+            player: listen.player,
+            song: listen.song,
+            artist: listen.artist,
+            album: listen.album,
+            sid: listen.sid,
+            source: listen.source,
+            started_at: listen.started_at,
+            ended_at: listen.ended_at,
+            duration: listen.duration,
+            played: listen.played,
+            completed: listen.completed,
+            submitted_at: listen.submitted_at,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use heos_api::types::player::{MediaType, NowPlayingMedia};
 
+pub mod api;
+pub mod rpc;
 pub mod zones;
 #[derive(Debug)]
 pub enum TrackName {
@@ -19,7 +21,7 @@ pub enum TrackName {
 impl TrackName {
     pub fn from_now_playing(now_playing: &NowPlayingMedia) -> Self {
         match now_playing.media_type {
-            MediaType::Song => TrackName::Song {
+            MediaType::Song | MediaType::Other(_) => TrackName::Song {
                 album: now_playing.album.clone(),
                 artist: now_playing.artist.clone(),
                 song: now_playing.song.clone(),
@@ -0,0 +1,301 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use heos_api::types::event::HeosEvent;
+use heos_api::types::group::Group;
+use heos_api::types::{GroupId, PlayerId};
+
+use crate::models::api::ApiError;
+
+// The messages of the websocket at /api/v1/ws, modelled after JSON-RPC 2.0.
+// Clients send requests with an `id`, the server answers each with a response
+// carrying the same `id` and pushes notifications (messages without `id`)
+// for the events the client subscribed to.
+
+pub const JSONRPC: &str = "2.0";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// an unknown player, group or source
+pub const NOT_FOUND: i64 = -32001;
+/// a feature the server was started without, e.g. the history
+pub const UNAVAILABLE: i64 = -32002;
+/// HEOS refused or failed, `data` has the details
+pub const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize, Debug)]
+pub struct RpcRequest {
+    /// requests without id are executed, but not answered.
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    pub fn parse(text: &str) -> Result<RpcRequest, Box<RpcResponse>> {
+        let value: Value = serde_json::from_str(text).map_err(|err| {
+            RpcResponse::error(Value::Null, RpcError::new(PARSE_ERROR, err.to_string()))
+        })?;
+        let id = value.get("id").cloned().unwrap_or(Value::Null);
+        serde_json::from_value(value).map_err(|err| {
+            RpcResponse::error(id, RpcError::new(INVALID_REQUEST, err.to_string())).into()
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn ok(id: Value, result: Value) -> Self {
+        RpcResponse {
+            jsonrpc: JSONRPC,
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error(id: Value, error: RpcError) -> Self {
+        RpcResponse {
+            jsonrpc: JSONRPC,
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<ApiError>,
+}
+
+impl RpcError {
+    pub fn new<S: Into<String>>(code: i64, message: S) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        RpcError::new(METHOD_NOT_FOUND, format!("unknown method {}", method))
+    }
+
+    pub fn invalid_params(err: serde_json::Error) -> Self {
+        RpcError::new(INVALID_PARAMS, err.to_string())
+    }
+}
+
+/// A message pushed by the server.
+#[derive(Serialize, Debug)]
+pub struct Notification {
+    pub jsonrpc: &'static str,
+    pub method: &'static str,
+    pub params: Value,
+}
+
+impl Notification {
+    pub fn new<T: Serialize>(method: &'static str, params: T) -> Self {
+        Notification {
+            jsonrpc: JSONRPC,
+            method,
+            params: serde_json::to_value(params).unwrap_or(Value::Null),
+        }
+    }
+
+    /// The raw HEOS event, as `{"event": "player_volume_changed", "pid": 1, ...}`.
+    pub fn event(event: &HeosEvent) -> Self {
+        let mut params = match event {
+            HeosEvent::Unknown { params, .. } => json!(params),
+            event => match serde_json::to_value(event).unwrap_or(Value::Null) {
+                Value::Object(map) => match map.into_iter().next() {
+                    Some((_, Value::Object(fields))) => Value::Object(fields),
+                    _ => json!({}),
+                },
+                // unit variants are just the name.
+                _ => json!({}),
+            },
+        };
+        params["event"] = json!(event.name());
+        Notification {
+            jsonrpc: JSONRPC,
+            method: "event",
+            params,
+        }
+    }
+}
+
+/// The player an event is about, if any.
+pub fn event_player(event: &HeosEvent) -> Option<PlayerId> {
+    match event {
+        HeosEvent::PlayerStateChanged { player_id, .. }
+        | HeosEvent::PlayerNowPlayingChanged { player_id }
+        | HeosEvent::PlayerNowPlayingProgress { player_id, .. }
+        | HeosEvent::PlayerPlaybackError { player_id, .. }
+        | HeosEvent::PlayerVolumeChanged { player_id, .. }
+        | HeosEvent::PlayerQueueChanged { player_id }
+        | HeosEvent::PlayerRepeatModeChanged { player_id, .. }
+        | HeosEvent::PlayerShuffleModeChanged { player_id, .. } => Some(*player_id),
+        _ => None,
+    }
+}
+
+/// Params of `subscribe`: which events a connection gets.
+///
+/// Without players and groups it gets everything. Otherwise the events of the
+/// listed players and of the members of the listed groups. Events about all
+/// players, like `players_changed`, are always sent.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    #[serde(default)]
+    pub players: Option<BTreeSet<PlayerId>>,
+    #[serde(default)]
+    pub groups: Option<BTreeSet<GroupId>>,
+}
+
+impl Subscription {
+    pub fn everything(&self) -> bool {
+        self.players.is_none() && self.groups.is_none()
+    }
+
+    pub fn wants_player(&self, pid: PlayerId, groups: &[Group]) -> bool {
+        if self.everything() {
+            return true;
+        }
+        let player = self
+            .players
+            .as_ref()
+            .is_some_and(|players| players.contains(&pid));
+        let member = self.groups.as_ref().is_some_and(|gids| {
+            groups.iter().any(|group| {
+                gids.contains(&group.gid) && group.players.iter().any(|member| member.pid == pid)
+            })
+        });
+        player || member
+    }
+
+    pub fn wants_group(&self, gid: GroupId) -> bool {
+        self.everything()
+            || self
+                .groups
+                .as_ref()
+                .is_some_and(|groups| groups.contains(&gid))
+    }
+
+    pub fn wants(&self, event: &HeosEvent, groups: &[Group]) -> bool {
+        match event {
+            HeosEvent::GroupVolumeChanged { group_id, .. } => self.wants_group(*group_id),
+            event => match event_player(event) {
+                Some(pid) => self.wants_player(pid, groups),
+                None => true,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::group::{GroupMember, GroupRole};
+    use heos_api::types::OnOrOff;
+
+    use super::*;
+
+    fn volume_changed(player_id: PlayerId) -> HeosEvent {
+        HeosEvent::PlayerVolumeChanged {
+            player_id,
+            level: 10,
+            mute: OnOrOff::Off,
+        }
+    }
+
+    #[test]
+    pub fn test_subscription_filters() {
+        let groups = vec![Group {
+            name: "Downstairs".to_string(),
+            gid: GroupId(1),
+            volume: 20,
+            players: vec![
+                GroupMember {
+                    name: "Kitchen".to_string(),
+                    pid: PlayerId(1),
+                    role: GroupRole::Leader,
+                },
+                GroupMember {
+                    name: "Dining".to_string(),
+                    pid: PlayerId(2),
+                    role: GroupRole::Member,
+                },
+            ],
+        }];
+        let all = Subscription::default();
+        assert!(all.wants(&volume_changed(PlayerId(3)), &groups));
+
+        let bedroom = Subscription {
+            players: Some(BTreeSet::from([PlayerId(3)])),
+            groups: None,
+        };
+        assert!(bedroom.wants(&volume_changed(PlayerId(3)), &groups));
+        assert!(!bedroom.wants(&volume_changed(PlayerId(2)), &groups));
+        assert!(bedroom.wants(&HeosEvent::PlayersChanged, &groups));
+
+        let downstairs = Subscription {
+            players: None,
+            groups: Some(BTreeSet::from([GroupId(1)])),
+        };
+        assert!(downstairs.wants(&volume_changed(PlayerId(2)), &groups));
+        assert!(!downstairs.wants(&volume_changed(PlayerId(3)), &groups));
+        let group_volume = HeosEvent::GroupVolumeChanged {
+            group_id: GroupId(1),
+            level: 5,
+            mute: OnOrOff::On,
+        };
+        assert!(downstairs.wants(&group_volume, &groups));
+        assert!(!bedroom.wants(&group_volume, &groups));
+    }
+
+    #[test]
+    pub fn test_messages() {
+        let notification =
+            serde_json::to_value(Notification::event(&volume_changed(PlayerId(3)))).unwrap();
+        assert_eq!(
+            notification,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "event",
+                "params": {"event": "player_volume_changed", "pid": 3, "level": 10, "mute": "off"}
+            })
+        );
+        let notification =
+            serde_json::to_value(Notification::event(&HeosEvent::GroupChanged)).unwrap();
+        assert_eq!(notification["params"], json!({"event": "groups_changed"}));
+
+        let request =
+            RpcRequest::parse(r#"{"jsonrpc": "2.0", "id": 7, "method": "players.list"}"#).unwrap();
+        assert_eq!(request.id, Some(json!(7)));
+        assert_eq!(request.params, Value::Null);
+        let response = RpcRequest::parse(r#"{"id": "a", "params": {}}"#).unwrap_err();
+        assert_eq!(response.id, json!("a"));
+        assert_eq!(response.error.unwrap().code, INVALID_REQUEST);
+        let response = RpcRequest::parse("{").unwrap_err();
+        assert_eq!(response.error.unwrap().code, PARSE_ERROR);
+    }
+}
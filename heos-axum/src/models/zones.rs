@@ -4,6 +4,8 @@ use heos_api::types::group::Group;
 use heos_api::types::player::{HeosPlayer, NowPlayingMedia, PlayState};
 use heos_api::types::{AlbumId, Level, MediaId, PlayerId, QueueId, SourceId};
 
+use crate::art::art_url;
+
 pub struct Zone {
     pub name: String,
     pub id: PlayerId,
@@ -21,11 +23,12 @@ impl Zone {
             PlayState::Stop => "fa-solid fa-pause",
         }
     }
-    pub fn now_playing_image(&self) -> &str {
+    /// The art of what is playing, see [`art_url`].
+    pub fn now_playing_image(&self, size: u32) -> String {
         match &self.now_playing {
-            NowPlaying::Noting => "/assets/playing_nothing.png",
-            NowPlaying::Station { image_url, .. } => &image_url,
-            NowPlaying::Song { image_url, .. } => &image_url,
+            NowPlaying::Noting => "/assets/playing_nothing.png".to_string(),
+            NowPlaying::Station { image_url, .. } => art_url(image_url, size),
+            NowPlaying::Song { image_url, .. } => art_url(image_url, size),
         }
     }
 }
@@ -123,7 +126,7 @@ impl From<(Vec<HeosPlayer>, Vec<Group>)> for Zones {
             .map(|player| (player.player_id, player))
             .collect();
         for group in input.1 {
-            if let Some(leader) = players.remove(&group.gid) {
+            if let Some(leader) = players.remove(&group.gid.leader()) {
                 let mut members: BTreeMap<PlayerId, (String, Level)> = group
                     .players
                     .iter()
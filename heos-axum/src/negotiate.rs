@@ -0,0 +1,72 @@
+use axum::async_trait;
+use axum::extract::{FromRequest, RequestParts};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use maud::Markup;
+use rust_hall::negotiate::{negotiate, Representation, HAL_JSON};
+use rust_hall::HalResource;
+use serde_json::Value;
+
+/// A resource with an HTML page, an htmx fragment and a JSON representation.
+pub trait Representable {
+    fn to_json(&self) -> Value;
+    fn render_page(&self) -> Markup;
+    /// The part of the page htmx swaps in, the whole page by default.
+    fn render_fragment(&self) -> Markup {
+        self.render_page()
+    }
+    fn to_hal(&self, self_link: &str) -> HalResource {
+        match self.to_json() {
+            value @ Value::Object(_) => HalResource::with_self(self_link).add_object(value),
+            value => HalResource::with_self(self_link).add_state("items", value),
+        }
+    }
+}
+
+/// The representation the client asked for with `Accept` and `HX-Request`.
+///
+/// The request is rejected with 406 if the client accepts none of them.
+pub struct Negotiated {
+    pub representation: Representation,
+    path: String,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Negotiated {
+    type Rejection = StatusCode;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let accept = req
+            .headers()
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok());
+        let htmx = req.headers().contains_key("hx-request");
+        let representation =
+            negotiate(accept, htmx, &Representation::ALL).ok_or(StatusCode::NOT_ACCEPTABLE)?;
+        Ok(Negotiated {
+            representation,
+            path: req.uri().path().to_string(),
+        })
+    }
+}
+
+impl Negotiated {
+    pub fn respond<R: Representable>(&self, resource: R) -> Response {
+        let mut response = match self.representation {
+            Representation::HalJson => (
+                [(CONTENT_TYPE, HeaderValue::from_static(HAL_JSON))],
+                Json(resource.to_hal(&self.path)),
+            )
+                .into_response(),
+            Representation::Json => Json(resource.to_json()).into_response(),
+            Representation::Html => resource.render_page().into_response(),
+            Representation::HtmxFragment => resource.render_fragment().into_response(),
+        };
+        response
+            .headers_mut()
+            .insert(VARY, HeaderValue::from_static("Accept, HX-Request"));
+        response
+    }
+}
@@ -1,5 +1,6 @@
 use maud::{html, Markup};
 
+use crate::art::art_url;
 use crate::templates::statics::*;
 use heos_api::types::browse::BrowsableMedia;
 use heos_api::types::SourceId;
@@ -22,7 +23,7 @@ pub fn render_media_list_item(item: &BrowsableMedia, source_id: &SourceId) -> Ma
     let image_url = if item.image_url.is_empty() {
         format!("/assets/{}", &folder_svg.name)
     } else {
-        item.image_url.clone()
+        art_url(&item.image_url, 64)
     };
     html!({
         li {
@@ -0,0 +1,99 @@
+use chrono::{Local, TimeZone};
+use maud::{html, Markup};
+use serde_json::{json, Value};
+
+use heos_api::types::Milliseconds;
+
+use crate::history::Listen;
+use crate::models::api::ApiListen;
+use crate::negotiate::Representable;
+use crate::views::pages::page;
+
+pub struct HistoryPage {
+    pub listens: Vec<Listen>,
+    /// completed listens are submitted to ListenBrainz
+    pub submitting: bool,
+}
+
+fn render_time(time: i64) -> Markup {
+    match Local.timestamp_opt(time, 0).single() {
+        Some(time) => html!({ (time.format("%Y-%m-%d %H:%M").to_string()) }),
+        None => html!({ ("-") }),
+    }
+}
+
+fn render_duration(duration: Option<Milliseconds>) -> Markup {
+    match duration {
+        Some(duration) => {
+            let seconds = duration / 1000;
+            html!({ (format!("{}:{:02}", seconds / 60, seconds % 60)) })
+        }
+        None => html!({ ("-") }),
+    }
+}
+
+impl HistoryPage {
+    pub fn render_html(&self) -> Markup {
+        html!({
+            h3 { ("Listening history") }
+            p {
+                a href="/api/v1/history/listenbrainz.json" download { ("ListenBrainz export") }
+                (" | ")
+                a href="/api/v1/history/scrobbler.log" download=".scrobbler.log" { ("Last.fm scrobbler log") }
+                @if self.submitting {
+                    (" | ")
+                    button hx-post="/api/v1/history/submit" hx-swap="none" { ("Submit to ListenBrainz") }
+                }
+            }
+            @if self.listens.is_empty() {
+                p { ("Nothing played yet.") }
+            } @else {
+                table .history {
+                    thead {
+                        tr {
+                            th { ("Started") }
+                            th { ("Player") }
+                            th { ("Track") }
+                            th { ("Artist") }
+                            th { ("Album") }
+                            th { ("Source") }
+                            th { ("Length") }
+                            th { ("Played") }
+                        }
+                    }
+                    tbody {
+                        @for listen in &self.listens {
+                            tr .history__listen
+                               .completed[listen.completed]
+                               .playing[listen.ended_at.is_none()] {
+                                td { (render_time(listen.started_at)) }
+                                td { (listen.player) }
+                                td { (listen.song) }
+                                td { (listen.artist) }
+                                td { (listen.album) }
+                                td { (listen.source.as_deref().unwrap_or("-")) }
+                                td { (render_duration(listen.duration)) }
+                                td { (render_duration(Some(listen.played))) }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Representable for HistoryPage {
+    fn to_json(&self) -> Value {
+        let listens: Vec<ApiListen> = self.listens.iter().cloned().map(ApiListen::from).collect();
+        json!({ "listens": listens, "submitting": self.submitting })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
+    }
+}
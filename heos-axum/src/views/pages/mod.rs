@@ -1,7 +1,11 @@
 use crate::templates::statics::*;
 use maud::{html, Markup, DOCTYPE};
+pub mod history;
 pub mod music_containers;
 pub mod music_sources;
+pub mod players;
+pub mod policies;
+pub mod rules;
 
 pub fn page(contents: Markup) -> Markup {
     html!( {
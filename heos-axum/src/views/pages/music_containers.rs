@@ -1,50 +1,41 @@
-use axum::response::{IntoResponse, Response};
 use maud::{html, Markup};
+use serde_json::{json, Value};
 
-use heos_api::types::browse::BrowsableMedia;
-use heos_api::types::{ContainerId, Range, SourceId};
+use heos_api::types::browse::{BroseSourceItem, BrowsableMedia};
+use heos_api::types::{ContainerId, Page, Range, SourceId};
 
+use crate::models::api::ApiBrowseItem;
+use crate::negotiate::Representable;
 use crate::views::browse::render_media_list_item;
 use crate::views::pages::page;
 
 #[derive(Debug)]
 pub struct BrowseMusicContainerPage {
     pub source_id: SourceId,
-    pub count: usize,
-    pub returned: usize,
     pub container_id: ContainerId,
-    pub items: Vec<BrowsableMedia>,
-    pub range: Range,
+    pub page: Page<BrowsableMedia>,
 }
 
 impl BrowseMusicContainerPage {
+    fn link(&self, range: Range) -> String {
+        format!(
+            "/sources/{}/containers/{}?{}",
+            &self.source_id,
+            &self.container_id,
+            range.as_query_str()
+        )
+    }
+
     pub fn next_link(&self) -> Option<String> {
-        if (self.count as u16) > self.range.start {
-            let next = self.range.next();
-            Some(format!(
-                "/sources/{}/containers/{}?{}",
-                &self.source_id,
-                &self.container_id,
-                next.as_query_str()
-            ))
-        } else {
-            None
-        }
+        self.page.next().map(|next| self.link(next))
     }
 
     pub fn prev_link(&self) -> Option<String> {
-        self.range.previous().map(|next| {
-            format!(
-                "/sources/{}/containers/{}?{}",
-                &self.source_id,
-                &self.container_id,
-                next.as_query_str()
-            )
-        })
+        self.page.previous().map(|previous| self.link(previous))
     }
 
     pub fn render_html(&self) -> Markup {
-        page(html!({
+        html!({
             nav {
                 ol {
                     li { a href="/sources/" { ( "Back to sources")} }
@@ -54,26 +45,58 @@ impl BrowseMusicContainerPage {
 
             }
             ul .media-list {
-                @for item in &self.items {
+                @for item in &self.page.items {
                     ( render_media_list_item(item, &self.source_id) )
                 }
             }
-            nav {
+            nav .pagination {
                 ol {
                     @if let Some(link) = self.prev_link() {
                          li { { a href=(link) { ( "prev" ) } } }
                     }
+                    @if !self.page.items.is_empty() {
+                        li .pagination__position {
+                            ( format!("{}-{} of {}",
+                                self.page.range.start as usize + 1,
+                                self.page.range.start as usize + self.page.items.len(),
+                                self.page.total) )
+                        }
+                    }
                     @if let Some(next) = self.next_link() {
                         li { a href=(next) { ( "next" ) } }
                     }
                 }
             }
-        }))
+        })
     }
 }
 
-impl IntoResponse for BrowseMusicContainerPage {
-    fn into_response(self) -> Response {
-        self.render_html().into_response()
+impl Representable for BrowseMusicContainerPage {
+    fn to_json(&self) -> Value {
+        let items: Vec<ApiBrowseItem> = self
+            .page
+            .items
+            .iter()
+            .cloned()
+            .map(|media| BroseSourceItem::BrowsableMedia(media).into())
+            .collect();
+        json!({
+            "sid": self.source_id,
+            "cid": self.container_id,
+            "start": self.page.range.start,
+            "end": self.page.range.end,
+            "count": self.page.total,
+            "has_next": self.page.has_next(),
+            "has_previous": self.page.has_previous(),
+            "items": items,
+        })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
     }
 }
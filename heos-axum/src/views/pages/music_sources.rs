@@ -1,9 +1,12 @@
-use axum::response::{IntoResponse, Response};
 use maud::{html, Markup};
+use serde_json::{json, Value};
 
-use heos_api::types::browse::{BrowsableMedia, HeosService, MusicSource};
+use heos_api::types::browse::{BroseSourceItem, BrowsableMedia, HeosService, MusicSource};
 use heos_api::types::SourceId;
 
+use crate::art::art_url;
+use crate::models::api::{ApiBrowseItem, ApiMusicSource};
+use crate::negotiate::Representable;
 use crate::views::browse::render_media_list_item;
 use crate::views::pages::page;
 
@@ -14,15 +17,9 @@ pub struct BrowseMusicSourcePage {
     pub base_uri: String,
 }
 
-impl IntoResponse for BrowseMusicSourcePage {
-    fn into_response(self) -> Response {
-        self.render_html().into_response()
-    }
-}
-
 impl BrowseMusicSourcePage {
     pub fn render_html(&self) -> Markup {
-        let html = html!({
+        html!({
             div {
                 ol .media-list {
                     @for item in &self.media_items {
@@ -35,7 +32,7 @@ impl BrowseMusicSourcePage {
                     @for service in &self.services {
                          li {
                             div .media-list__heos-service {
-                                img src=(service.image_url) height="32px" {}
+                                img src=(art_url(&service.image_url, 64)) height="32px" {}
                                 a href=(format!("/sources/{}/browse", service.sid)) {
                                     ( service.name )
                                 }
@@ -44,20 +41,47 @@ impl BrowseMusicSourcePage {
                     }
                 }
             }
-        });
-        page(html)
+        })
+    }
+}
+
+impl Representable for BrowseMusicSourcePage {
+    fn to_json(&self) -> Value {
+        let items: Vec<ApiBrowseItem> = self
+            .media_items
+            .iter()
+            .cloned()
+            .map(BroseSourceItem::BrowsableMedia)
+            .chain(
+                self.services
+                    .iter()
+                    .cloned()
+                    .map(BroseSourceItem::HeosService),
+            )
+            .map(ApiBrowseItem::from)
+            .collect();
+        json!({ "sid": self.source_id, "items": items })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
     }
 }
 
 pub struct SourceDetailsPage {
     pub source: MusicSource,
 }
-impl IntoResponse for SourceDetailsPage {
-    fn into_response(self) -> Response {
+
+impl SourceDetailsPage {
+    pub fn render_html(&self) -> Markup {
         html!({
             div {
                 h3 { (self.source.name) }
-                img src=(self.source.image_url) {}
+                img src=(art_url(&self.source.image_url, 256)) {}
                 div {
                     p {
                         a href="/sources" {
@@ -71,7 +95,20 @@ impl IntoResponse for SourceDetailsPage {
                 }
             }
         })
-        .into_response()
+    }
+}
+
+impl Representable for SourceDetailsPage {
+    fn to_json(&self) -> Value {
+        json!(ApiMusicSource::from(self.source.clone()))
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
     }
 }
 
@@ -82,22 +119,36 @@ pub struct MusicSourcesPages {
 
 impl MusicSourcesPages {
     pub fn render_html(&self) -> Markup {
-        page(html!({
+        html!({
             div .music-sources {
                 @for source in &self.music_sources {
                      div {
                         a href=(format!("{}/sources/{}/browse", self.base_uri, source.sid)) alt=( source.name ) {
-                            img src=(source.image_url) {}
+                            img src=(art_url(&source.image_url, 256)) {}
                         }
                     }
                 }
             }
-        }))
+        })
     }
 }
 
-impl IntoResponse for MusicSourcesPages {
-    fn into_response(self) -> Response {
-        self.render_html().into_response()
+impl Representable for MusicSourcesPages {
+    fn to_json(&self) -> Value {
+        let sources: Vec<ApiMusicSource> = self
+            .music_sources
+            .iter()
+            .cloned()
+            .map(ApiMusicSource::from)
+            .collect();
+        json!(sources)
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
     }
 }
@@ -0,0 +1,44 @@
+use maud::{html, Markup};
+use serde_json::{json, Value};
+
+use heos_api::types::player::HeosPlayer;
+
+use crate::models::api::ApiPlayer;
+use crate::negotiate::Representable;
+use crate::views::pages::page;
+
+pub struct PlayersPage {
+    pub players: Vec<HeosPlayer>,
+}
+
+impl PlayersPage {
+    pub fn render_html(&self) -> Markup {
+        html!({
+            div {
+                ol {
+                    @for player in &self.players {
+                        li id=(player.player_id.to_string()) {
+                            p { (player.name) }
+                            p { (player.volume)}
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Representable for PlayersPage {
+    fn to_json(&self) -> Value {
+        let players: Vec<ApiPlayer> = self.players.iter().cloned().map(ApiPlayer::from).collect();
+        json!(players)
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::NaiveTime;
+use maud::{html, Markup};
+use serde_json::{json, Value};
+
+use heos_api::VolumePolicy;
+
+use crate::negotiate::Representable;
+use crate::views::pages::page;
+
+pub struct VolumePoliciesPage {
+    pub policies: Vec<VolumePolicy>,
+    pub now: NaiveTime,
+}
+
+fn render_level(level: Option<u8>) -> Markup {
+    match level {
+        Some(level) => html!({ (level) }),
+        None => html!({ ("-") }),
+    }
+}
+
+impl VolumePoliciesPage {
+    pub fn render_html(&self) -> Markup {
+        html!({
+            h3 { ("Volume limits") }
+            @if self.policies.is_empty() {
+                p { ("No volume limits configured.") }
+            } @else {
+                table .volume-policies {
+                    thead {
+                        tr {
+                            th { ("Player") }
+                            th { ("Max") }
+                            th { ("Min") }
+                            th { ("Quiet hours") }
+                            th { ("Max right now") }
+                        }
+                    }
+                    tbody {
+                        @for policy in &self.policies {
+                            tr {
+                                td { (policy.player) }
+                                td { (render_level(policy.max_volume)) }
+                                td { (render_level(policy.min_volume)) }
+                                td {
+                                    @for quiet_hours in &policy.quiet_hours {
+                                        p .volume-policies__quiet-hours
+                                          .active[quiet_hours.contains(self.now)] {
+                                            (format!("{} - {}: {}",
+                                                quiet_hours.from.format("%H:%M"),
+                                                quiet_hours.until.format("%H:%M"),
+                                                quiet_hours.max_volume))
+                                        }
+                                    }
+                                }
+                                td { (render_level(policy.max_volume_at(self.now))) }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Representable for VolumePoliciesPage {
+    fn to_json(&self) -> Value {
+        let policies: Vec<Value> = self
+            .policies
+            .iter()
+            .map(|policy| {
+                let mut value = json!(policy);
+                value["max_volume_now"] = json!(policy.max_volume_at(self.now));
+                value
+            })
+            .collect();
+        json!(policies)
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
+    }
+}
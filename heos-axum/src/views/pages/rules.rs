@@ -0,0 +1,123 @@
+use maud::{html, Markup};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use heos_api::rules::{Firing, Rule};
+
+use crate::negotiate::Representable;
+use crate::views::pages::page;
+
+pub struct RulesPage {
+    pub rules: Vec<Rule>,
+    /// the latest first
+    pub log: Vec<Firing>,
+    pub dry_run: bool,
+}
+
+// the rules as they are written in the file.
+fn render_yaml<T: Serialize>(value: &T) -> Markup {
+    let mut bytes = vec![];
+    let mut serializer = serde_yaml::Serializer::new(&mut bytes);
+    let _ = serde_yaml::with::singleton_map_recursive::serialize(value, &mut serializer);
+    let yaml = String::from_utf8_lossy(&bytes);
+    html!({ pre .rules__yaml { (yaml.trim_end()) } })
+}
+
+impl RulesPage {
+    pub fn render_html(&self) -> Markup {
+        html!({
+            h3 { ("Rules") }
+            @if self.dry_run {
+                p .rules__dry-run { ("Dry run: rules are logged, but do nothing.") }
+            }
+            @if self.rules.is_empty() {
+                p { ("No rules configured.") }
+            } @else {
+                table .rules {
+                    thead {
+                        tr {
+                            th { ("Rule") }
+                            th { ("When") }
+                            th { ("If") }
+                            th { ("Then") }
+                        }
+                    }
+                    tbody {
+                        @for rule in &self.rules {
+                            tr {
+                                td { (rule.name) }
+                                td { (render_yaml(&rule.when)) }
+                                td {
+                                    @if !rule.conditions.is_empty() {
+                                        (render_yaml(&rule.conditions))
+                                    }
+                                }
+                                td { (render_yaml(&rule.actions)) }
+                            }
+                        }
+                    }
+                }
+            }
+            h3 { ("Fired") }
+            @if self.log.is_empty() {
+                p { ("Nothing fired yet.") }
+            } @else {
+                table .rules-log {
+                    thead {
+                        tr {
+                            th { ("Time") }
+                            th { ("Rule") }
+                            th { ("Commands") }
+                            th { ("Errors") }
+                        }
+                    }
+                    tbody {
+                        @for firing in &self.log {
+                            tr .dry-run[firing.dry_run] .failed[!firing.errors.is_empty()] {
+                                td { (firing.time.format("%Y-%m-%d %H:%M:%S").to_string()) }
+                                td { (firing.rule) }
+                                td {
+                                    @for command in &firing.commands {
+                                        p { (command) }
+                                    }
+                                }
+                                td {
+                                    @for error in &firing.errors {
+                                        p { (error) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Representable for RulesPage {
+    fn to_json(&self) -> Value {
+        let log: Vec<Value> = self
+            .log
+            .iter()
+            .map(|firing| {
+                json!({
+                    "time": firing.time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                    "rule": firing.rule,
+                    "commands": firing.commands,
+                    "dry_run": firing.dry_run,
+                    "errors": firing.errors,
+                })
+            })
+            .collect();
+        json!({ "rules": self.rules, "log": log, "dry_run": self.dry_run })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
+    }
+}
@@ -1,12 +1,13 @@
-use axum::response::{IntoResponse, Response};
-
+use crate::negotiate::Representable;
 use crate::views::pages::page;
 use heos_api::types::player::HeosPlayer;
 use heos_api::types::PlayerId;
 use maud::{html, Markup};
+use serde::Serialize;
+use serde_json::{json, Value};
 use tracing::log::info;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Member {
     pub id: PlayerId,
     pub name: String,
@@ -44,13 +45,13 @@ impl EditZoneMembers {
         self.members.push(Member {
             id: member.player_id,
             name: member.name,
-            checked: member.in_group.filter(|p| *p == self.zone_id).is_some(),
+            checked: member.in_group == Some(self.zone_id.group()),
         });
     }
 
     pub fn render_html(&self) -> Markup {
         let action = format!("/zones/{}", self.zone_id);
-        page(html!({
+        html!({
             .zone-edit-members {
                 form method="post" action=(action)
                     hx-post=(action) hx-target="#zones"
@@ -79,12 +80,20 @@ impl EditZoneMembers {
 
                 }
             }
-        }))
+        })
     }
 }
 
-impl IntoResponse for EditZoneMembers {
-    fn into_response(self) -> Response {
-        self.render_html().into_response()
+impl Representable for EditZoneMembers {
+    fn to_json(&self) -> Value {
+        json!({ "zone_id": self.zone_id, "zone_name": self.zone_name, "members": self.members })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
     }
 }
@@ -1,16 +1,29 @@
+use crate::art::art_url;
+use crate::models::api::{ApiGroup, ApiPlayer};
 use crate::models::zones::{NowPlaying, Zone, Zones};
+use crate::negotiate::Representable;
+use crate::views::pages::page;
 use heos_api::types::group::Group;
 use heos_api::types::player::HeosPlayer;
 use maud::{html, Markup};
+use serde_json::{json, Value};
 
 pub struct ZonesPage {
     pub zones: Zones,
+    players: Vec<ApiPlayer>,
+    groups: Vec<ApiGroup>,
 }
 
 impl ZonesPage {
     pub fn new(players: Vec<HeosPlayer>, groups: Vec<Group>) -> Self {
+        let api_players = players.iter().cloned().map(ApiPlayer::from).collect();
+        let api_groups = groups.iter().cloned().map(ApiGroup::from).collect();
         let zones: Zones = (players, groups).into();
-        Self { zones }
+        Self {
+            zones,
+            players: api_players,
+            groups: api_groups,
+        }
     }
 
     pub fn render_html(&self) -> Markup {
@@ -24,13 +37,27 @@ impl ZonesPage {
     }
 }
 
+impl Representable for ZonesPage {
+    fn to_json(&self) -> Value {
+        json!({ "players": self.players, "groups": self.groups })
+    }
+
+    fn render_page(&self) -> Markup {
+        page(self.render_html())
+    }
+
+    fn render_fragment(&self) -> Markup {
+        self.render_html()
+    }
+}
+
 pub fn render_zone(zone: &Zone) -> Markup {
     html!({
         .zones__zone id=(format!("zone{}", zone.id)) hx-target="this" hx-swap="outerHTML"
         {
          .zones__zone__header {
             .zones__zone__header__image {
-                img src=(zone.now_playing_image()) {}
+                img src=(zone.now_playing_image(128)) {}
             }
             .zones__zone__header__name  { (zone.name) }
             .zones__zone__header__song  { (zone.now_playing.song()) }
@@ -84,7 +111,7 @@ fn render_now_playing(now_playing: &NowPlaying) -> Markup {
             artist,
             ..
         } => html!({
-            img src=(image_url) {}
+            img src=(art_url(image_url, 128)) {}
             p .zones__zone__now-playing__station {
                 ( station)
             }
@@ -103,7 +130,7 @@ fn render_now_playing(now_playing: &NowPlaying) -> Markup {
             ..
         } => {
             html!({
-                img src=(image_url) {}
+                img src=(art_url(image_url, 128)) {}
                 p .zones__zone__now-playing__song {
                     ( song )
                 }
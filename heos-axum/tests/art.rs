@@ -0,0 +1,73 @@
+//! Fetches album art from a local stand-in, caches and resizes it.
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Router};
+use image::{ImageOutputFormat, RgbImage};
+
+use heos_axum::art::{art_url, hash, ArtCache};
+
+fn cover() -> Vec<u8> {
+    let mut bytes = Cursor::new(vec![]);
+    image::DynamicImage::ImageRgb8(RgbImage::new(300, 200))
+        .write_to(&mut bytes, ImageOutputFormat::Png)
+        .unwrap();
+    bytes.into_inner()
+}
+
+async fn serve_cover(Extension(requests): Extension<Arc<AtomicUsize>>) -> impl IntoResponse {
+    requests.fetch_add(1, Ordering::SeqCst);
+    ([(CONTENT_TYPE, "image/png")], cover())
+}
+
+async fn stand_in() -> (SocketAddr, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/covers/:name", get(serve_cover))
+        .layer(Extension(requests.clone()));
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    (addr, requests)
+}
+
+#[tokio::test]
+async fn test_art_is_cached_and_resized() {
+    let (addr, requests) = stand_in().await;
+    let dir = std::env::temp_dir().join(format!("heos-art-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = ArtCache::open(&dir, 1024 * 1024).unwrap();
+
+    let url = format!("http://{}/covers/rumours.png", addr);
+    assert!(art_url(&url, 100).ends_with("?size=128"));
+    let hash = hash(&url);
+
+    let art = cache.get(&hash, Some(100)).await.unwrap().unwrap();
+    assert_eq!(art.content_type, "image/png");
+    let thumbnail = image::load_from_memory(&art.bytes).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (128, 85));
+
+    let original = cache.get(&hash, None).await.unwrap().unwrap();
+    assert_eq!(original.bytes, cover());
+    cache.get(&hash, Some(128)).await.unwrap().unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // what is on disk survives a restart
+    let cache = ArtCache::open(&dir, 1024 * 1024).unwrap();
+    assert!(cache.get(&hash, Some(512)).await.unwrap().is_some());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // too small for anything
+    let cache = ArtCache::open(&dir, 10).unwrap();
+    assert_eq!(cache.bytes(), 0);
+    assert!(cache.get(&"0".repeat(32), None).await.unwrap().is_none());
+    assert!(cache.get("../secrets", None).await.unwrap().is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,119 @@
+//! Submits listens to a local stand-in for ListenBrainz.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use serde_json::Value;
+
+use heos_api::types::{PlayerId, SourceId};
+use heos_axum::history::store::NewListen;
+use heos_axum::history::{HistoryStore, Submitter};
+
+// the authorization header and the body of each submission.
+type Submission = (Option<String>, Value);
+
+// the submissions accepted, the ones after the first `accept` are refused.
+#[derive(Clone)]
+struct Received {
+    submissions: Arc<Mutex<Vec<Submission>>>,
+    accept: usize,
+}
+
+async fn submit_listens(
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+    Extension(received): Extension<Received>,
+) -> (StatusCode, Json<Value>) {
+    let token = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let mut submissions = received.submissions.lock().unwrap();
+    if submissions.len() == received.accept {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({"error": "try again later"})),
+        );
+    }
+    submissions.push((token, body));
+    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+}
+
+async fn stand_in(accept: usize) -> (SocketAddr, Received) {
+    let received = Received {
+        submissions: Arc::default(),
+        accept,
+    };
+    let app = Router::new()
+        .route("/1/submit-listens", post(submit_listens))
+        .layer(Extension(received.clone()));
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    (addr, received)
+}
+
+fn play(store: &HistoryStore, song: &str, started_at: i64, played: u64) -> i64 {
+    let id = store
+        .start(&NewListen {
+            pid: PlayerId(1),
+            player: "Kitchen".to_string(),
+            song: song.to_string(),
+            artist: "Fleetwood Mac".to_string(),
+            album: "Rumours".to_string(),
+            sid: SourceId(1024),
+            source: Some("Local Music".to_string()),
+            started_at,
+        })
+        .unwrap();
+    store
+        .finish(id, started_at + 200, played, Some(200_000))
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn test_submit_pending_listens() {
+    let (addr, received) = stand_in(usize::MAX).await;
+    let store = HistoryStore::in_memory().unwrap();
+    let dreams = play(&store, "Dreams", 1000, 150_000);
+    // skipped, never submitted
+    play(&store, "The Chain", 1200, 20_000);
+
+    let submitter = Submitter::new(format!("http://{}/", addr), "secret");
+    assert_eq!(submitter.submit_pending(&store).await.unwrap(), 1);
+    // nothing left to submit
+    assert_eq!(submitter.submit_pending(&store).await.unwrap(), 0);
+
+    let received = received.submissions.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let (token, body) = &received[0];
+    assert_eq!(token.as_deref(), Some("Token secret"));
+    assert_eq!(body["listen_type"], "single");
+    let payload = body["payload"].as_array().unwrap();
+    assert_eq!(payload.len(), 1);
+    assert_eq!(payload[0]["listened_at"], 1000);
+    assert_eq!(payload[0]["track_metadata"]["track_name"], "Dreams");
+    assert_eq!(payload[0]["track_metadata"]["release_name"], "Rumours");
+
+    assert!(store.get(dreams).unwrap().unwrap().submitted_at.is_some());
+}
+
+#[tokio::test]
+async fn test_accepted_chunks_stay_submitted() {
+    let (addr, received) = stand_in(1).await;
+    let store = HistoryStore::in_memory().unwrap();
+    for i in 0..150 {
+        play(&store, "Dreams", 1000 + i * 300, 150_000);
+    }
+
+    let submitter = Submitter::new(format!("http://{}/", addr), "secret");
+    assert!(submitter.submit_pending(&store).await.is_err());
+    let received = received.submissions.lock().unwrap();
+    assert_eq!(received[0].1["listen_type"], "import");
+    assert_eq!(received[0].1["payload"].as_array().unwrap().len(), 100);
+    assert_eq!(store.pending().unwrap().len(), 50);
+}
@@ -0,0 +1,191 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use heos_api::types::SearchCriteriaId;
+use heos_api::types::{ContainerId, SourceId};
+
+const AFTER_HELP: &str = "\
+Players are selected by pid or by name, a unique prefix of the name is enough.
+
+Exit status:
+  0        success
+  1        unexpected failure
+  2        invalid arguments
+  3        no HEOS device found
+  4        unknown or ambiguous player
+  10 + eid HEOS refused the command with error id `eid`, e.g. 12 for an invalid id";
+
+/// Control HEOS speakers from the command line.
+#[derive(Parser, Debug)]
+#[command(name = "heos", version, about, after_help = AFTER_HELP)]
+pub struct Cli {
+    /// The HEOS device to talk to, discovered if missing.
+    #[arg(long, env = "HEOS_DEVICE_ADDR", global = true)]
+    pub host: Option<IpAddr>,
+
+    /// Print json for scripting instead of text.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Write the traffic with the device to a JSONL file, e.g. for a bug report.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List the HEOS devices in the local network.
+    Discover {
+        /// How long to wait for answers, in seconds.
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
+    /// List all players.
+    Players,
+    /// List all groups.
+    Groups,
+    Play {
+        player: String,
+    },
+    Pause {
+        player: String,
+    },
+    Stop {
+        player: String,
+    },
+    Next {
+        player: String,
+    },
+    Prev {
+        player: String,
+    },
+    /// Show or set the volume, absolute (`30`) or relative (`+5`, `-5`).
+    Volume {
+        player: String,
+        #[arg(allow_hyphen_values = true)]
+        level: Option<String>,
+    },
+    /// Show or set the mute state.
+    Mute {
+        player: String,
+        #[arg(value_parser = ["on", "off", "toggle"])]
+        state: Option<String>,
+    },
+    #[command(subcommand)]
+    Group(GroupCommand),
+    /// List the music sources or browse one of them.
+    Browse {
+        sid: Option<SourceId>,
+        cid: Option<ContainerId>,
+        #[arg(long, default_value_t = 0)]
+        start: u16,
+        #[arg(long, default_value_t = 49)]
+        end: u16,
+    },
+    /// Search a music source.
+    Search {
+        sid: SourceId,
+        #[arg(required_unless_present = "list_criteria")]
+        text: Option<String>,
+        /// The search criteria id, see `--list-criteria`. The first one by default.
+        #[arg(long)]
+        criteria: Option<SearchCriteriaId>,
+        /// List the search criteria of the source instead of searching.
+        #[arg(long)]
+        list_criteria: bool,
+        #[arg(long, default_value_t = 0)]
+        start: u16,
+        #[arg(long, default_value_t = 49)]
+        end: u16,
+    },
+    /// Show the queue of a player.
+    Queue {
+        player: String,
+        #[arg(long, default_value_t = 0)]
+        start: u16,
+        #[arg(long, default_value_t = 49)]
+        end: u16,
+    },
+    /// Print the next change event, or all of them with `--follow`.
+    Events {
+        #[arg(long, short)]
+        follow: bool,
+    },
+    /// Navigate the HAL api of heosd, e.g. `heos api http://localhost:8000/api/zones`.
+    ///
+    /// Follows the rels one after another and prints the resource it ends at.
+    Api {
+        url: String,
+        rels: Vec<String>,
+        /// Send the HAL-FORMS template of this name of the last resource.
+        #[arg(long, value_name = "TEMPLATE")]
+        submit: Option<String>,
+        /// The json body for `--submit`.
+        #[arg(long, requires = "submit", default_value = "{}")]
+        body: String,
+    },
+    /// Send a raw protocol command like `player/get_players`, or start a prompt without one.
+    Raw {
+        command: Option<String>,
+        /// Let the device indent its responses.
+        #[arg(long)]
+        prettify: bool,
+        /// Register for change events and print them too.
+        #[arg(long)]
+        events: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GroupCommand {
+    /// Groups the members with the leader, replacing its current group.
+    Set {
+        leader: String,
+        #[arg(required = true)]
+        members: Vec<String>,
+    },
+    /// Removes all members from the group of the leader.
+    Dissolve { leader: String },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    pub fn test_cli() {
+        Cli::command().debug_assert();
+        let cli = Cli::parse_from(["heos", "--json", "volume", "Kitchen", "-5"]);
+        assert!(cli.json);
+        match cli.command {
+            Command::Volume { player, level } => {
+                assert_eq!(player, "Kitchen");
+                assert_eq!(level.as_deref(), Some("-5"));
+            }
+            command => panic!("unexpected {:?}", command),
+        }
+        let cli = Cli::parse_from([
+            "heos",
+            "api",
+            "http://localhost:8000/api/zones",
+            "self",
+            "--submit",
+            "volume",
+            "--body",
+            r#"{"level": 20}"#,
+        ]);
+        match cli.command {
+            Command::Api { rels, submit, .. } => {
+                assert_eq!(rels, vec!["self"]);
+                assert_eq!(submit.as_deref(), Some("volume"));
+            }
+            command => panic!("unexpected {:?}", command),
+        }
+    }
+}
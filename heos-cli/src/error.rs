@@ -0,0 +1,53 @@
+use heos_api::error::HeosError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum CliError {
+    #[error(transparent)]
+    Heos(#[from] HeosError),
+
+    #[error(transparent)]
+    Hal(#[from] rust_hall::ClientError),
+
+    #[error("no player named '{0}'")]
+    UnknownPlayer(String),
+
+    #[error("'{0}' matches several players: {}", .1.join(", "))]
+    AmbiguousPlayer(String, Vec<String>),
+
+    #[error("{0}")]
+    InvalidArgument(String),
+}
+
+pub type CliResult<T> = Result<T, CliError>;
+
+impl CliError {
+    /// The exit status of the process, see the help of `heos`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Heos(HeosError::InvalidCommand { eid, .. }) => 10 + *eid as i32,
+            CliError::Heos(HeosError::NoDeviceFound) => 3,
+            CliError::Heos(HeosError::InternalError(_)) | CliError::Hal(_) => 1,
+            CliError::UnknownPlayer(_) | CliError::AmbiguousPlayer(_, _) => 4,
+            CliError::InvalidArgument(_) => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::HeosErrorCode;
+
+    use super::*;
+
+    #[test]
+    pub fn test_exit_codes() {
+        let refused = CliError::Heos(HeosError::InvalidCommand {
+            command: "player/set_volume".to_string(),
+            eid: HeosErrorCode::InvalidId,
+            text: "Invalid ID".to_string(),
+        });
+        assert_eq!(refused.exit_code(), 12);
+        assert_eq!(CliError::Heos(HeosError::NoDeviceFound).exit_code(), 3);
+        assert_eq!(CliError::UnknownPlayer("x".to_string()).exit_code(), 4);
+    }
+}
@@ -0,0 +1,27 @@
+use std::process::exit;
+
+use clap::Parser;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::cli::Cli;
+
+mod cli;
+mod error;
+mod repl;
+mod run;
+mod select;
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    // quiet by default, the output is for humans and scripts.
+    let rust_log = std::env::var("RUST_LOG").unwrap_or_else(|_| "warn".into());
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(rust_log))
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+    if let Err(err) = run::run(cli).await {
+        eprintln!("heos: {}", err);
+        exit(err.exit_code());
+    }
+}
@@ -0,0 +1,271 @@
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, ExternalPrinter, Helper, Highlighter, Hinter, Validator};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use heos_api::error::HeosError;
+use heos_api::{CommandSpec, Connection, Frame, COMMANDS};
+
+use crate::error::CliResult;
+
+const HELP: &str = "\
+Type a command like `player/get_volume?pid=1`, `heos://` may be left out.
+Tab completes command and parameter names. Events are shown as they arrive.
+
+  :prettify on|off   let the device indent its responses
+  :events on|off     register for change events
+  :help              show this
+  :quit              leave, as does ctrl-d";
+
+/// Sends one command and prints its response, returning an error if the device refused it.
+pub async fn passthrough(
+    connection: &mut Connection,
+    command: &str,
+    events: bool,
+    json: bool,
+) -> CliResult<()> {
+    let command = strip_scheme(command);
+    let name = command.split('?').next().unwrap_or_default().to_string();
+    connection.send_command(command).await?;
+    while let Some(value) = connection.read_json().await? {
+        match Frame::from_json(value.clone()) {
+            Ok(Frame::Event(_)) => {
+                if events {
+                    println!("{}", format_json(&value, json));
+                }
+            }
+            Ok(Frame::UnderProcess(_)) => {}
+            Ok(Frame::Response(response)) if response.command_name == name => {
+                println!("{}", format_json(&value, json));
+                return Ok(());
+            }
+            Ok(Frame::Error(err)) => {
+                println!("{}", format_json(&value, json));
+                return Err(err.into());
+            }
+            // a late response to something else
+            Ok(Frame::Response(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(HeosError::InternalError(anyhow::anyhow!("the device closed the connection")).into())
+}
+
+enum Input {
+    Line(Option<String>),
+    Json(Option<Value>),
+}
+
+/// Reads commands with line editing and prints everything the device sends.
+///
+/// rustyline blocks, so it runs on its own thread and hands the lines over,
+/// while responses and events are printed above the prompt.
+pub async fn repl(mut connection: Connection, json: bool) -> CliResult<()> {
+    let mut editor = Editor::<ReplHelper, DefaultHistory>::new()
+        .map_err(|err| HeosError::InternalError(err.into()))?;
+    editor.set_helper(Some(ReplHelper));
+    let history = history_path();
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+    let mut printer = editor.create_external_printer().ok();
+
+    let (lines, mut input) = mpsc::channel::<String>(16);
+    // not joined, it may wait for a line after the device is gone.
+    std::thread::spawn(move || loop {
+        match editor.readline("heos> ") {
+            Ok(line) => {
+                let line = line.trim().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(line.as_str());
+                if let Some(history) = &history {
+                    let _ = editor.save_history(history);
+                }
+                if line == ":quit" || lines.blocking_send(line).is_err() {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(_) => break,
+        }
+    });
+
+    println!("Connected to {}, :help for help.", connection.ip_addr());
+    loop {
+        let next = tokio::select! {
+            line = input.recv() => Input::Line(line),
+            value = connection.read_json() => Input::Json(value?),
+        };
+        match next {
+            Input::Line(None) => break,
+            Input::Line(Some(line)) => {
+                let command = match meta_command(&line) {
+                    Ok(command) => command,
+                    Err(text) => {
+                        print(&mut printer, text);
+                        continue;
+                    }
+                };
+                connection.send_command(command).await?;
+            }
+            Input::Json(Some(value)) => print(&mut printer, format_json(&value, json)),
+            Input::Json(None) => {
+                print(&mut printer, "the device closed the connection".to_string());
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The command to send for a line, or the text to show instead.
+fn meta_command(line: &str) -> Result<String, String> {
+    let meta = match line.strip_prefix(':') {
+        Some(meta) => meta,
+        None => return Ok(strip_scheme(line).to_string()),
+    };
+    let (meta, argument) = meta.split_once(' ').unwrap_or((meta, ""));
+    let command = match meta {
+        "help" => return Err(HELP.to_string()),
+        "prettify" => "system/prettify_json_response",
+        "events" => "system/register_for_change_events",
+        _ => return Err(format!("unknown ':{}', try :help", meta)),
+    };
+    match argument.trim() {
+        enable @ ("on" | "off") => Ok(format!("{}?enable={}", command, enable)),
+        _ => Err(format!("usage: :{} on|off", meta)),
+    }
+}
+
+fn strip_scheme(command: &str) -> &str {
+    command.trim().trim_start_matches("heos://")
+}
+
+// events on one line, responses indented unless json lines are wanted.
+fn format_json(value: &Value, json: bool) -> String {
+    let event = value["heos"]["command"]
+        .as_str()
+        .is_some_and(|command| command.starts_with("event/"));
+    if json || event {
+        value.to_string()
+    } else {
+        serde_json::to_string_pretty(value).expect("values are always valid json")
+    }
+}
+
+fn print<P: ExternalPrinter>(printer: &mut Option<P>, text: String) {
+    if let Some(printer) = printer {
+        if printer.print(text.clone()).is_ok() {
+            return;
+        }
+    }
+    println!("{}", text);
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".heos_history"))
+}
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ReplHelper;
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&line[..pos]))
+    }
+}
+
+/// Completes the command name, or the parameter name after `?` and `&`.
+/// Returns where the completed word starts and the candidates.
+pub fn complete(line: &str) -> (usize, Vec<String>) {
+    let offset = line.len() - line.trim_start().len();
+    let offset = offset + line[offset..].strip_prefix("heos://").map_or(0, |_| 7);
+    let command = &line[offset..];
+    match command.split_once('?') {
+        None => {
+            let names = COMMANDS
+                .iter()
+                .filter(|spec| spec.name.starts_with(command))
+                .map(|spec| {
+                    if spec.params.is_empty() {
+                        spec.name.to_string()
+                    } else {
+                        format!("{}?", spec.name)
+                    }
+                })
+                .collect();
+            (offset, names)
+        }
+        Some((name, query)) => {
+            let spec = match CommandSpec::find(name) {
+                Some(spec) => spec,
+                None => return (line.len(), vec![]),
+            };
+            let word = query.rsplit('&').next().unwrap_or_default();
+            if word.contains('=') {
+                return (line.len(), vec![]);
+            }
+            let given: Vec<&str> = query
+                .split('&')
+                .filter_map(|param| param.split_once('=').map(|(key, _)| key))
+                .collect();
+            let params = spec
+                .params
+                .iter()
+                .filter(|param| param.starts_with(word) && !given.contains(param))
+                .map(|param| format!("{}=", param))
+                .collect();
+            (line.len() - word.len(), params)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_complete() {
+        let (start, names) = complete("player/set_v");
+        assert_eq!(start, 0);
+        assert_eq!(names, vec!["player/set_volume?"]);
+
+        let (start, names) = complete("heos://group/get_g");
+        assert_eq!(start, 7);
+        assert_eq!(names, vec!["group/get_groups"]);
+
+        let (start, params) = complete("player/set_volume?pid=1&");
+        assert_eq!(start, 24);
+        assert_eq!(params, vec!["level="]);
+
+        assert!(complete("player/set_volume?pid=").1.is_empty());
+        assert!(complete("nonsense/command?").1.is_empty());
+    }
+
+    #[test]
+    pub fn test_meta_command() {
+        assert_eq!(
+            meta_command("heos://player/get_players"),
+            Ok("player/get_players".to_string())
+        );
+        assert_eq!(
+            meta_command(":prettify on"),
+            Ok("system/prettify_json_response?enable=on".to_string())
+        );
+        assert!(meta_command(":events maybe").is_err());
+        assert_eq!(meta_command(":help"), Err(HELP.to_string()));
+    }
+}
@@ -0,0 +1,441 @@
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use heos_api::record::Recorder;
+use heos_api::types::browse::BroseSourceItem;
+use heos_api::types::event::HeosEvent;
+use heos_api::types::player::{PlayState, PlayerInfo};
+use heos_api::types::{Level, OnOrOff, Range};
+use heos_api::{discover_heos_devices, find_heos_devices, Connection, HeosApi};
+
+use rust_hall::HalClient;
+
+use crate::cli::{Cli, Command, GroupCommand};
+use crate::error::{CliError, CliResult};
+use crate::repl;
+use crate::select::find_player;
+
+const HEOS_PORT: u16 = 1255;
+
+/// Prints either json or text.
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce(&T) -> String) {
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(value).expect("values are always valid json")
+            );
+        } else {
+            let text = text(value);
+            if !text.is_empty() {
+                println!("{}", text);
+            }
+        }
+    }
+
+    // one line per value, so `events --follow --json` is json lines.
+    fn print_line<T: Serialize>(&self, value: &T, text: impl FnOnce(&T) -> String) {
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string(value).expect("values are always valid json")
+            );
+        } else {
+            println!("{}", text(value));
+        }
+    }
+}
+
+pub async fn run(cli: Cli) -> CliResult<()> {
+    let out = Output { json: cli.json };
+    if let Command::Discover { timeout } = cli.command {
+        let devices = discover_heos_devices(Duration::from_secs(timeout)).await?;
+        out.print(&devices, |devices| lines(devices, |ip| ip.to_string()));
+        return Ok(());
+    }
+    if let Command::Api {
+        url,
+        rels,
+        submit,
+        body,
+    } = cli.command
+    {
+        return api(&url, &rels, submit.as_deref(), &body).await;
+    }
+    if let Command::Raw {
+        command,
+        prettify,
+        events,
+    } = cli.command
+    {
+        let mut connection = Connection::connect((device(cli.host).await?, HEOS_PORT)).await?;
+        if let Some(path) = &cli.record {
+            connection.record(&Recorder::create(path)?);
+        }
+        if prettify {
+            connection
+                .execute_command("system/prettify_json_response?enable=on")
+                .await?;
+        }
+        if events {
+            connection
+                .execute_command("system/register_for_change_events?enable=on")
+                .await?;
+        }
+        return match command {
+            Some(command) => repl::passthrough(&mut connection, &command, events, cli.json).await,
+            None => repl::repl(connection, cli.json).await,
+        };
+    }
+    let api = connect(cli.host, cli.record.as_deref()).await?;
+    match cli.command {
+        Command::Discover { .. } | Command::Api { .. } | Command::Raw { .. } => {
+            unreachable!("handled above")
+        }
+        Command::Players => {
+            let players = api.get_player_infos().await?;
+            out.print(&players, |players| {
+                lines(players, |player| {
+                    format!(
+                        "{}\t{}\t{}",
+                        player.pid,
+                        player.name,
+                        player.model.as_deref().unwrap_or_default()
+                    )
+                })
+            });
+        }
+        Command::Groups => {
+            let groups = api.get_groups().await?;
+            out.print(&groups, |groups| {
+                lines(groups, |group| {
+                    let members: Vec<&str> = group
+                        .players
+                        .iter()
+                        .map(|member| member.name.as_str())
+                        .collect();
+                    format!("{}\t{}\t{}", group.gid, group.name, members.join(", "))
+                })
+            });
+        }
+        Command::Play { player } => set_play_state(&api, &out, &player, PlayState::Play).await?,
+        Command::Pause { player } => set_play_state(&api, &out, &player, PlayState::Pause).await?,
+        Command::Stop { player } => set_play_state(&api, &out, &player, PlayState::Stop).await?,
+        Command::Next { player } => {
+            let player = select(&api, &player).await?;
+            api.play_next(player.pid).await?;
+        }
+        Command::Prev { player } => {
+            let player = select(&api, &player).await?;
+            api.play_previous(player.pid).await?;
+        }
+        Command::Volume { player, level } => {
+            let player = select(&api, &player).await?;
+            let mut volume = api.get_volume(&player.pid).await?;
+            if let Some(level) = level {
+                let level = parse_level(&level, volume.level)?;
+                volume = api.set_volume(player.pid, level).await?;
+            }
+            out.print(&volume, |volume| {
+                format!("{}: {}", player.name, volume.level)
+            });
+        }
+        Command::Mute { player, state } => {
+            let player = select(&api, &player).await?;
+            let mut mute = api.get_mute(player.pid).await?;
+            let state = match state.as_deref() {
+                Some("on") => Some(OnOrOff::On),
+                Some("off") => Some(OnOrOff::Off),
+                Some(_) if mute.state == OnOrOff::On => Some(OnOrOff::Off),
+                Some(_) => Some(OnOrOff::On),
+                None => None,
+            };
+            if let Some(state) = state {
+                mute = api.set_mute(player.pid, state).await?;
+            }
+            out.print(&mute, |mute| format!("{}: {}", player.name, mute.state));
+        }
+        Command::Group(GroupCommand::Set { leader, members }) => {
+            let players = api.get_player_infos().await?;
+            let mut pids = vec![find_player(&players, &leader)?.pid];
+            for member in &members {
+                pids.push(find_player(&players, member)?.pid);
+            }
+            api.set_group(pids).await?;
+        }
+        Command::Group(GroupCommand::Dissolve { leader }) => {
+            let leader = select(&api, &leader).await?;
+            api.set_group(vec![leader.pid]).await?;
+        }
+        Command::Browse {
+            sid,
+            cid,
+            start,
+            end,
+        } => match (sid, cid) {
+            (None, _) => {
+                let sources = api.get_music_sources().await?;
+                out.print(&sources, |sources| {
+                    lines(sources, |source| {
+                        format!("{}\t{}\t{}", source.sid, source.name, source.source_type)
+                    })
+                });
+            }
+            (Some(sid), None) => {
+                let items = api.browse_music_sources(sid).await?;
+                out.print(&items, |items| lines(items, browse_item));
+            }
+            (Some(sid), Some(cid)) => {
+                let page = api
+                    .browse_music_containers(&sid, &cid, &Range { start, end })
+                    .await?;
+                out.print(&page, |page| {
+                    let items: Vec<String> = page
+                        .items
+                        .iter()
+                        .map(|item| browse_item(&BroseSourceItem::BrowsableMedia(item.clone())))
+                        .collect();
+                    format!(
+                        "{}\n{}-{} of {}",
+                        items.join("\n"),
+                        page.range.start,
+                        // the range is inclusive.
+                        (page.range.start as usize + page.returned).saturating_sub(1),
+                        page.count
+                    )
+                });
+            }
+        },
+        Command::Search {
+            sid,
+            text,
+            criteria,
+            list_criteria,
+            start,
+            end,
+        } => {
+            let criterias = api.get_search_criteria(sid).await?;
+            if list_criteria {
+                out.print(&criterias, |criterias| {
+                    lines(criterias, |criteria| {
+                        format!("{}\t{}", criteria.scid, criteria.name)
+                    })
+                });
+                return Ok(());
+            }
+            let scid = match criteria.or_else(|| criterias.first().map(|c| c.scid)) {
+                Some(scid) => scid,
+                None => {
+                    return Err(CliError::InvalidArgument(format!(
+                        "source {} can't be searched",
+                        sid
+                    )))
+                }
+            };
+            let text = text.unwrap_or_default();
+            let results = api.search(sid, scid, &text, &Range { start, end }).await?;
+            out.print(&results, |results| {
+                format!(
+                    "{}\n{} of {}",
+                    lines(&results.items, browse_item),
+                    results.returned,
+                    results.count
+                )
+            });
+        }
+        Command::Queue { player, start, end } => {
+            let player = select(&api, &player).await?;
+            let queue = api.get_queue(player.pid, Range { start, end }).await?;
+            out.print(&queue, |queue| {
+                lines(queue, |entry| {
+                    format!("{}\t{} - {}", entry.qid, entry.artist, entry.song)
+                })
+            });
+        }
+        Command::Events { follow } => {
+            let mut events = api.events().await?;
+            while let Some(event) = events.recv().await {
+                out.print_line(&event, event_text);
+                if !follow {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn device(host: Option<IpAddr>) -> CliResult<IpAddr> {
+    match host {
+        Some(host) => Ok(host),
+        None => Ok(find_heos_devices().await?),
+    }
+}
+
+async fn connect(host: Option<IpAddr>, record: Option<&Path>) -> CliResult<HeosApi> {
+    let addr = (device(host).await?, HEOS_PORT);
+    match record {
+        Some(path) => Ok(HeosApi::record(addr, Recorder::create(path)?).await?),
+        None => Ok(HeosApi::connect(addr).await?),
+    }
+}
+
+// the HAL api of heosd, navigated by rels only.
+async fn api(url: &str, rels: &[String], submit: Option<&str>, body: &str) -> CliResult<()> {
+    let client = HalClient::new();
+    let rels: Vec<&str> = rels.iter().map(String::as_str).collect();
+    let mut resource = client.traverse(url, &rels).await?;
+    if let Some(name) = submit {
+        let body: Value = serde_json::from_str(body)
+            .map_err(|err| CliError::InvalidArgument(format!("invalid body: {}", err)))?;
+        match client.submit(&resource, name, &body).await? {
+            Some(response) => resource = response,
+            None => return Ok(()),
+        }
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&resource).expect("values are always valid json")
+    );
+    Ok(())
+}
+
+async fn select(api: &HeosApi, query: &str) -> CliResult<PlayerInfo> {
+    let players = api.get_player_infos().await?;
+    find_player(&players, query).cloned()
+}
+
+async fn set_play_state(
+    api: &HeosApi,
+    out: &Output,
+    player: &str,
+    state: PlayState,
+) -> CliResult<()> {
+    let player = select(api, player).await?;
+    let state = api.set_play_state(player.pid, state).await?;
+    out.print(&state, |state| format!("{}: {}", player.name, state.state));
+    Ok(())
+}
+
+/// `30` sets, `+5` and `-5` change the volume, always within 0 - 100.
+fn parse_level(level: &str, current: Level) -> CliResult<Level> {
+    let invalid = || CliError::InvalidArgument(format!("invalid volume '{}'", level));
+    let level = level.trim();
+    let target = if let Some(up) = level.strip_prefix('+') {
+        (current as i32).saturating_add(up.parse::<i32>().map_err(|_| invalid())?)
+    } else if let Some(down) = level.strip_prefix('-') {
+        (current as i32).saturating_sub(down.parse::<i32>().map_err(|_| invalid())?)
+    } else {
+        level.parse::<i32>().map_err(|_| invalid())?
+    };
+    Ok(target.clamp(0, 100) as Level)
+}
+
+fn lines<T>(values: &[T], line: impl Fn(&T) -> String) -> String {
+    values.iter().map(line).collect::<Vec<_>>().join("\n")
+}
+
+// the serde name of an enum value, the one HEOS uses.
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn browse_item(item: &BroseSourceItem) -> String {
+    match item {
+        BroseSourceItem::HeosService(service) => {
+            format!(
+                "sid={}\t{}\t{}",
+                service.sid, service.name, service.server_type
+            )
+        }
+        BroseSourceItem::BrowsableMedia(media) => {
+            let id = match (&media.container_id, &media.mid) {
+                (Some(cid), _) => format!("cid={}", cid),
+                (None, Some(mid)) => format!("mid={}", mid),
+                (None, None) => String::new(),
+            };
+            let artist = media
+                .artist
+                .as_ref()
+                .map(|artist| format!(" ({})", artist))
+                .unwrap_or_default();
+            format!(
+                "{}\t{}\t{}{}",
+                id,
+                serde_name(&media.media_type),
+                media.name,
+                artist
+            )
+        }
+    }
+}
+
+/// `player_volume_changed level=20 mute=off pid=1`
+fn event_text(event: &HeosEvent) -> String {
+    let mut text = event.name().to_string();
+    let params = match event {
+        HeosEvent::Unknown { params, .. } => json!(params),
+        event => match serde_json::to_value(event) {
+            Ok(Value::Object(map)) => map
+                .into_iter()
+                .next()
+                .map(|(_, params)| params)
+                .unwrap_or_default(),
+            _ => Value::Null,
+        },
+    };
+    if let Value::Object(params) = params {
+        for (key, value) in params {
+            match value {
+                Value::Null => {}
+                Value::String(value) => text.push_str(&format!(" {}={}", key, value)),
+                value => text.push_str(&format!(" {}={}", key, value)),
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::event::HeosEvent;
+    use heos_api::types::PlayerId;
+
+    use super::*;
+
+    #[test]
+    pub fn test_parse_level() {
+        assert_eq!(parse_level("30", 10).unwrap(), 30);
+        assert_eq!(parse_level("+5", 10).unwrap(), 15);
+        assert_eq!(parse_level("-15", 10).unwrap(), 0);
+        assert_eq!(parse_level("250", 10).unwrap(), 100);
+        assert_eq!(parse_level("+2147483647", 10).unwrap(), 100);
+        assert_eq!(parse_level("-2147483647", 10).unwrap(), 0);
+        assert!(parse_level("loud", 10).is_err());
+    }
+
+    #[test]
+    pub fn test_event_text() {
+        let event = HeosEvent::PlayerVolumeChanged {
+            player_id: PlayerId(1),
+            level: 20,
+            mute: OnOrOff::Off,
+        };
+        assert_eq!(
+            event_text(&event),
+            "player_volume_changed level=20 mute=off pid=1"
+        );
+        assert_eq!(event_text(&HeosEvent::PlayersChanged), "players_changed");
+    }
+}
@@ -0,0 +1,76 @@
+use heos_api::types::player::PlayerInfo;
+use heos_api::types::PlayerId;
+
+use crate::error::{CliError, CliResult};
+
+/// Finds a player by pid, by name or by a unique prefix of its name, ignoring case.
+pub fn find_player<'a>(players: &'a [PlayerInfo], query: &str) -> CliResult<&'a PlayerInfo> {
+    if let Ok(pid) = query.parse::<PlayerId>() {
+        if let Some(player) = players.iter().find(|player| player.pid == pid) {
+            return Ok(player);
+        }
+    }
+    let needle = query.trim().to_lowercase();
+    if let Some(player) = players
+        .iter()
+        .find(|player| player.name.to_lowercase() == needle)
+    {
+        return Ok(player);
+    }
+    let matches: Vec<&PlayerInfo> = players
+        .iter()
+        .filter(|player| player.name.to_lowercase().starts_with(&needle))
+        .collect();
+    match matches.as_slice() {
+        [player] => Ok(player),
+        [] => Err(CliError::UnknownPlayer(query.to_string())),
+        matches => Err(CliError::AmbiguousPlayer(
+            query.to_string(),
+            matches.iter().map(|player| player.name.clone()).collect(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn player(pid: i64, name: &str) -> PlayerInfo {
+        PlayerInfo {
+            name: name.to_string(),
+            pid: PlayerId(pid),
+            lineout: None,
+            ip: None,
+            model: None,
+            network: None,
+            version: None,
+            gid: None,
+            control: None,
+        }
+    }
+
+    #[test]
+    pub fn test_find_player() {
+        let players = vec![
+            player(-1234, "Kitchen"),
+            player(42, "Bedroom"),
+            player(43, "Bedroom 2"),
+        ];
+        assert_eq!(find_player(&players, "-1234").unwrap().pid, PlayerId(-1234));
+        assert_eq!(
+            find_player(&players, "kitchen").unwrap().pid,
+            PlayerId(-1234)
+        );
+        assert_eq!(find_player(&players, "Kit").unwrap().pid, PlayerId(-1234));
+        // the exact name wins over the prefix
+        assert_eq!(find_player(&players, "bedroom").unwrap().pid, PlayerId(42));
+        assert!(matches!(
+            find_player(&players, "Bed"),
+            Err(CliError::AmbiguousPlayer(_, _))
+        ));
+        assert!(matches!(
+            find_player(&players, "Garage"),
+            Err(CliError::UnknownPlayer(_))
+        ));
+    }
+}
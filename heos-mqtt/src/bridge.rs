@@ -0,0 +1,231 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, ClientError, Event, LastWill, MqttOptions, Packet, QoS};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use heos_api::types::event::HeosEvent;
+use heos_api::types::player::HeosPlayer;
+use heos_api::types::{GroupId, PlayerId};
+use heos_api::HeosDriver;
+
+use crate::command::Command;
+use crate::config::Config;
+use crate::discovery::{configs, group_state, PlayerState};
+use crate::topics::Topics;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Connects to the broker and keeps player states and commands in sync until the process ends.
+pub async fn run(config: Config, driver: HeosDriver) -> anyhow::Result<()> {
+    let topics = Topics::new(&config.base_topic, &config.discovery_prefix);
+    let mut options = MqttOptions::new(&config.mqtt_client_id, &config.mqtt_host, config.mqtt_port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        topics.status(),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some((username, password)) = config.credentials() {
+        options.set_credentials(username, password);
+    }
+    let (client, mut eventloop) = AsyncClient::new(options, 64);
+    let bridge = Bridge::new(client, topics, driver);
+    tokio::spawn(bridge.clone().forward_events());
+
+    info!(
+        "Connecting to mqtt://{}:{}",
+        &config.mqtt_host, config.mqtt_port
+    );
+    loop {
+        match eventloop.poll().await {
+            // after every (re)connect, as the broker might have lost our subscription.
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Connected to the broker");
+                let bridge = bridge.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = bridge.announce().await {
+                        warn!("Failed to announce the players: {}", err);
+                    }
+                });
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                bridge.handle_command(&publish.topic, &publish.payload);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                warn!("MQTT connection failed: {}", err);
+                sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Bridge {
+    client: AsyncClient,
+    topics: Topics,
+    driver: HeosDriver,
+    // to clear the retained state of dissolved groups.
+    groups: Arc<Mutex<BTreeSet<GroupId>>>,
+}
+
+impl Bridge {
+    pub fn new(client: AsyncClient, topics: Topics, driver: HeosDriver) -> Self {
+        Bridge {
+            client,
+            topics,
+            driver,
+            groups: Arc::new(Mutex::new(BTreeSet::new())),
+        }
+    }
+
+    /// Publishes the discovery configs and all states and subscribes to the commands.
+    pub async fn announce(&self) -> Result<(), ClientError> {
+        self.client
+            .publish(self.topics.status(), QoS::AtLeastOnce, true, "online")
+            .await?;
+        self.client
+            .subscribe(self.topics.commands_filter(), QoS::AtLeastOnce)
+            .await?;
+        self.publish_all().await
+    }
+
+    async fn publish_all(&self) -> Result<(), ClientError> {
+        for player in self.driver.players() {
+            for (topic, config) in configs(&self.topics, &player) {
+                self.client
+                    .publish(topic, QoS::AtLeastOnce, true, config.to_string())
+                    .await?;
+            }
+            self.publish_player(&player).await?;
+        }
+        self.publish_groups().await
+    }
+
+    async fn publish_player(&self, player: &HeosPlayer) -> Result<(), ClientError> {
+        publish_player_state(&self.client, &self.topics, &PlayerState::new(player)).await
+    }
+
+    async fn publish_player_by_id(&self, pid: PlayerId) -> Result<(), ClientError> {
+        match self.driver.players().iter().find(|p| p.player_id == pid) {
+            Some(player) => self.publish_player(player).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn publish_groups(&self) -> Result<(), ClientError> {
+        let groups = self.driver.groups();
+        let current: BTreeSet<GroupId> = groups.iter().map(|group| group.gid).collect();
+        let dissolved: Vec<GroupId> = {
+            let mut known = self.groups.lock().unwrap();
+            let dissolved = known.difference(&current).copied().collect();
+            *known = current;
+            dissolved
+        };
+        for gid in dissolved {
+            // an empty retained message removes the retained one.
+            self.client
+                .publish(self.topics.group(gid), QoS::AtLeastOnce, true, "")
+                .await?;
+        }
+        for group in groups {
+            self.client
+                .publish(
+                    self.topics.group(group.gid),
+                    QoS::AtLeastOnce,
+                    true,
+                    group_state(&group).to_string(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn forward_events(self) {
+        let mut events = self.driver.subscribe();
+        loop {
+            let result = match events.recv().await {
+                Ok(event) => self.handle_event(event).await,
+                Err(RecvError::Lagged(missed)) => {
+                    debug!("Missed {} events, publishing everything", missed);
+                    self.publish_all().await
+                }
+                Err(RecvError::Closed) => {
+                    warn!("The driver stopped sending events");
+                    return;
+                }
+            };
+            if let Err(err) = result {
+                warn!("Failed to publish: {}", err);
+            }
+        }
+    }
+
+    async fn handle_event(&self, event: HeosEvent) -> Result<(), ClientError> {
+        match event {
+            HeosEvent::PlayerVolumeChanged { player_id, .. }
+            | HeosEvent::PlayerStateChanged { player_id, .. }
+            | HeosEvent::PlayerNowPlayingChanged { player_id } => {
+                self.publish_player_by_id(player_id).await
+            }
+            HeosEvent::PlayersChanged => self.publish_all().await,
+            HeosEvent::GroupChanged | HeosEvent::GroupVolumeChanged { .. } => {
+                self.publish_groups().await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_command(&self, topic: &str, payload: &[u8]) {
+        let (pid, command) = match self.topics.parse_command(topic) {
+            Some(command) => command,
+            None => return,
+        };
+        let command = match Command::parse(command, payload) {
+            Some(command) => command,
+            None => {
+                warn!(
+                    "Ignoring {} with payload {:?}",
+                    topic,
+                    String::from_utf8_lossy(payload)
+                );
+                return;
+            }
+        };
+        debug!("Executing {:?} for {}", &command, pid);
+        let driver = self.driver.clone();
+        tokio::spawn(async move {
+            if let Err(err) = command.execute(&driver, pid).await {
+                warn!("Command for {} failed: {}", pid, err);
+            }
+        });
+    }
+}
+
+/// Publishes the json state and the single values of a player, all retained.
+pub async fn publish_player_state(
+    client: &AsyncClient,
+    topics: &Topics,
+    state: &PlayerState,
+) -> Result<(), ClientError> {
+    let json = serde_json::to_string(state).expect("player state is always valid json");
+    client
+        .publish(topics.player(state.pid), QoS::AtLeastOnce, true, json)
+        .await?;
+    for (attribute, value) in state.attributes() {
+        client
+            .publish(
+                topics.player_attribute(state.pid, attribute),
+                QoS::AtLeastOnce,
+                true,
+                value,
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use heos_api::types::player::PlayState;
+use heos_api::types::{Level, OnOrOff, PlayerId};
+use heos_api::{HeosDriver, HeosResult};
+
+/// A command received on `<base_topic>/players/<pid>/set/<command>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `play_state`: play, pause or stop
+    PlayState(PlayState),
+    /// `play_pause`: toggles between play and pause
+    PlayPause,
+    Next,
+    Previous,
+    /// `volume`: 0 - 100 as the number entity sends it, or `volume_level`:
+    /// 0.0 - 1.0 as the media player sends it
+    Volume(Level),
+    /// `mute`: on/off or true/false
+    Mute(OnOrOff),
+    /// `group`: the pids of the members, comma separated or as json array.
+    /// Nothing dissolves the group.
+    Group(Vec<PlayerId>),
+}
+
+impl Command {
+    pub fn parse(command: &str, payload: &[u8]) -> Option<Command> {
+        let payload = std::str::from_utf8(payload).ok()?.trim();
+        match command {
+            "play_state" => payload.parse().ok().map(Command::PlayState),
+            "play" => Some(Command::PlayState(PlayState::Play)),
+            "pause" => Some(Command::PlayState(PlayState::Pause)),
+            "stop" => Some(Command::PlayState(PlayState::Stop)),
+            "play_pause" => Some(Command::PlayPause),
+            "next" => Some(Command::Next),
+            "previous" => Some(Command::Previous),
+            "volume" => parse_volume(payload).map(Command::Volume),
+            "volume_level" => parse_volume_level(payload).map(Command::Volume),
+            "mute" => parse_on_off(payload).map(Command::Mute),
+            "group" => parse_members(payload).map(Command::Group),
+            _ => None,
+        }
+    }
+
+    pub async fn execute(self, driver: &HeosDriver, pid: PlayerId) -> HeosResult<()> {
+        match self {
+            Command::PlayState(state) => driver.set_play_state(pid, state).await,
+            Command::PlayPause => {
+                let playing = driver
+                    .players()
+                    .iter()
+                    .any(|player| player.player_id == pid && player.play_state == PlayState::Play);
+                let state = if playing {
+                    PlayState::Pause
+                } else {
+                    PlayState::Play
+                };
+                driver.set_play_state(pid, state).await
+            }
+            Command::Next => driver.play_next(pid).await,
+            Command::Previous => driver.play_previous(pid).await,
+            Command::Volume(level) => driver.set_volume(pid, level).await.map(|_| ()),
+            Command::Mute(state) => driver.set_mute(pid, state).await,
+            Command::Group(members) if members.is_empty() => driver.delete_group(pid).await,
+            Command::Group(members) => driver.create_group(pid, members).await,
+        }
+    }
+}
+
+// the number entity may send `35.0`.
+fn parse_volume(payload: &str) -> Option<Level> {
+    let level: f64 = payload.parse().ok()?;
+    Some(level.clamp(0.0, 100.0).round() as Level)
+}
+
+fn parse_volume_level(payload: &str) -> Option<Level> {
+    let level: f64 = payload.parse().ok()?;
+    Some((level.clamp(0.0, 1.0) * 100.0).round() as Level)
+}
+
+fn parse_on_off(payload: &str) -> Option<OnOrOff> {
+    match payload.to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" => Some(OnOrOff::On),
+        "off" | "false" | "0" => Some(OnOrOff::Off),
+        _ => None,
+    }
+}
+
+fn parse_members(payload: &str) -> Option<Vec<PlayerId>> {
+    if payload.starts_with('[') {
+        return serde_json::from_str(payload).ok();
+    }
+    payload
+        .split(',')
+        .map(str::trim)
+        .filter(|pid| !pid.is_empty())
+        .map(|pid| pid.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_parse_commands() {
+        assert_eq!(
+            Command::parse("play_state", b"pause"),
+            Some(Command::PlayState(PlayState::Pause))
+        );
+        assert_eq!(Command::parse("next", b""), Some(Command::Next));
+        assert_eq!(Command::parse("volume", b"35"), Some(Command::Volume(35)));
+        assert_eq!(Command::parse("volume", b"1"), Some(Command::Volume(1)));
+        assert_eq!(Command::parse("volume", b"35.0"), Some(Command::Volume(35)));
+        assert_eq!(Command::parse("volume", b"250"), Some(Command::Volume(100)));
+        assert_eq!(
+            Command::parse("volume_level", b"1"),
+            Some(Command::Volume(100))
+        );
+        assert_eq!(
+            Command::parse("volume_level", b"0.35"),
+            Some(Command::Volume(35))
+        );
+        assert_eq!(
+            Command::parse("mute", b"true"),
+            Some(Command::Mute(OnOrOff::On))
+        );
+        assert_eq!(Command::parse("mute", b"maybe"), None);
+        assert_eq!(Command::parse("rewind", b""), None);
+    }
+
+    #[test]
+    pub fn test_parse_group_members() {
+        assert_eq!(
+            Command::parse("group", b"1, 2"),
+            Some(Command::Group(vec![PlayerId(1), PlayerId(2)]))
+        );
+        assert_eq!(
+            Command::parse("group", b"[3,4]"),
+            Some(Command::Group(vec![PlayerId(3), PlayerId(4)]))
+        );
+        assert_eq!(Command::parse("group", b""), Some(Command::Group(vec![])));
+        assert_eq!(Command::parse("group", b"1,kitchen"), None);
+    }
+}
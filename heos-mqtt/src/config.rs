@@ -0,0 +1,45 @@
+use std::net::IpAddr;
+
+use clap::Parser;
+
+#[derive(Parser, Debug, Clone)]
+pub struct Config {
+    #[clap(long, env)]
+    pub rust_log: Option<String>,
+
+    #[clap(long, env)]
+    pub heos_device_addr: Option<IpAddr>,
+
+    #[clap(long, env, default_value = "localhost")]
+    pub mqtt_host: String,
+
+    #[clap(long, env, default_value_t = 1883)]
+    pub mqtt_port: u16,
+
+    #[clap(long, env)]
+    pub mqtt_username: Option<String>,
+
+    #[clap(long, env)]
+    pub mqtt_password: Option<String>,
+
+    #[clap(long, env, default_value = "heos-mqtt")]
+    pub mqtt_client_id: String,
+
+    /// all state and command topics are below this topic
+    #[clap(long, env, default_value = "heos")]
+    pub base_topic: String,
+
+    /// the discovery prefix configured in Home Assistant
+    #[clap(long, env, default_value = "homeassistant")]
+    pub discovery_prefix: String,
+}
+
+impl Config {
+    pub fn credentials(&self) -> Option<(String, String)> {
+        match (&self.mqtt_username, &self.mqtt_password) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            (Some(username), None) => Some((username.clone(), String::new())),
+            _ => None,
+        }
+    }
+}
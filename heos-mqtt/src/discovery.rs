@@ -0,0 +1,224 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use heos_api::types::group::Group;
+use heos_api::types::player::{HeosPlayer, PlayState};
+use heos_api::types::{GroupId, Level, OnOrOff, PlayerId};
+
+use crate::topics::Topics;
+
+/// The retained state of a player, published as json on `<base_topic>/players/<pid>`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlayerState {
+    pub pid: PlayerId,
+    pub name: String,
+    pub state: PlayState,
+    pub volume: Level,
+    pub mute: OnOrOff,
+    pub group: Option<GroupId>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub station: Option<String>,
+    pub image_url: Option<String>,
+}
+
+impl PlayerState {
+    pub fn new(player: &HeosPlayer) -> Self {
+        let media = player.now_playing.as_ref();
+        PlayerState {
+            pid: player.player_id,
+            name: player.name.clone(),
+            state: player.play_state,
+            volume: player.volume,
+            mute: player.mute,
+            group: player.in_group,
+            title: media.map(|media| media.song.clone()),
+            artist: media.map(|media| media.artist.clone()),
+            album: media.map(|media| media.album.clone()),
+            station: media.and_then(|media| media.station.clone()),
+            image_url: media.map(|media| media.image_url.clone()),
+        }
+    }
+
+    /// The single values, each on its own topic. Home Assistant wants them that way.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        let state = match self.state {
+            PlayState::Play => "playing",
+            PlayState::Pause => "paused",
+            PlayState::Stop => "idle",
+        };
+        vec![
+            ("state", state.to_string()),
+            ("volume", self.volume.to_string()),
+            // the media player wants 0.0 - 1.0
+            ("volume_level", format!("{:.2}", self.volume as f64 / 100.0)),
+            ("mute", self.mute.to_string()),
+            ("title", self.title.clone().unwrap_or_default()),
+            ("artist", self.artist.clone().unwrap_or_default()),
+            ("album", self.album.clone().unwrap_or_default()),
+            ("image_url", self.image_url.clone().unwrap_or_default()),
+        ]
+    }
+}
+
+/// The retained state of a group, published as json on `<base_topic>/groups/<gid>`.
+pub fn group_state(group: &Group) -> Value {
+    json!({
+        "gid": group.gid,
+        "name": group.name,
+        "volume": group.volume,
+        "leader": group.leader().map(|leader| leader.pid),
+        "members": group.players.iter().map(|member| member.pid).collect::<Vec<_>>(),
+    })
+}
+
+fn device(player: &HeosPlayer) -> Value {
+    json!({
+        "identifiers": [format!("heos_{}", player.player_id)],
+        "name": player.name,
+        "manufacturer": "Denon/Marantz",
+        "model": "HEOS",
+    })
+}
+
+/// The config of a `media_player`, using the keys of the `mqtt_media_player`
+/// custom integration, as Home Assistant has no MQTT media player of its own.
+pub fn media_player_config(topics: &Topics, player: &HeosPlayer) -> Value {
+    let pid = player.player_id;
+    json!({
+        "name": player.name,
+        "unique_id": format!("heos_{}", pid),
+        "availability_topic": topics.status(),
+        "json_attributes_topic": topics.player(pid),
+        "state_state_topic": topics.player_attribute(pid, "state"),
+        "state_title_topic": topics.player_attribute(pid, "title"),
+        "state_artist_topic": topics.player_attribute(pid, "artist"),
+        "state_album_topic": topics.player_attribute(pid, "album"),
+        "state_volume_topic": topics.player_attribute(pid, "volume_level"),
+        "state_albumart_topic": topics.player_attribute(pid, "image_url"),
+        "command_volume_topic": topics.command(pid, "volume_level"),
+        "command_play_topic": topics.command(pid, "play"),
+        "command_pause_topic": topics.command(pid, "pause"),
+        "command_playpause_topic": topics.command(pid, "play_pause"),
+        "command_next_topic": topics.command(pid, "next"),
+        "command_previous_topic": topics.command(pid, "previous"),
+        "device": device(player),
+    })
+}
+
+/// A `number` entity for the volume, which works without custom integrations.
+pub fn volume_config(topics: &Topics, player: &HeosPlayer) -> Value {
+    let pid = player.player_id;
+    json!({
+        "name": format!("{} Volume", player.name),
+        "unique_id": format!("heos_{}_volume", pid),
+        "availability_topic": topics.status(),
+        "state_topic": topics.player_attribute(pid, "volume"),
+        "command_topic": topics.command(pid, "volume"),
+        "min": 0,
+        "max": 100,
+        "icon": "mdi:volume-high",
+        "device": device(player),
+    })
+}
+
+/// A `switch` entity to mute the player.
+pub fn mute_config(topics: &Topics, player: &HeosPlayer) -> Value {
+    let pid = player.player_id;
+    json!({
+        "name": format!("{} Mute", player.name),
+        "unique_id": format!("heos_{}_mute", pid),
+        "availability_topic": topics.status(),
+        "state_topic": topics.player_attribute(pid, "mute"),
+        "command_topic": topics.command(pid, "mute"),
+        "payload_on": "on",
+        "payload_off": "off",
+        "icon": "mdi:volume-off",
+        "device": device(player),
+    })
+}
+
+/// All discovery configs of a player with their topics.
+pub fn configs(topics: &Topics, player: &HeosPlayer) -> Vec<(String, Value)> {
+    let pid = player.player_id;
+    vec![
+        (
+            topics.discovery("media_player", pid),
+            media_player_config(topics, player),
+        ),
+        (
+            topics.discovery("number", pid),
+            volume_config(topics, player),
+        ),
+        (topics.discovery("switch", pid), mute_config(topics, player)),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kitchen() -> HeosPlayer {
+        HeosPlayer {
+            volume: 20,
+            play_state: PlayState::Pause,
+            ..HeosPlayer::test_player(42, "Kitchen")
+        }
+    }
+
+    #[test]
+    pub fn test_topics() {
+        let topics = Topics::new("heos/", "homeassistant");
+        assert_eq!(topics.player(PlayerId(42)), "heos/players/42");
+        assert_eq!(
+            topics.command(PlayerId(42), "volume"),
+            "heos/players/42/set/volume"
+        );
+        assert_eq!(
+            topics.discovery("number", PlayerId(42)),
+            "homeassistant/number/heos_42/config"
+        );
+        assert_eq!(
+            topics.parse_command("heos/players/42/set/volume"),
+            Some((PlayerId(42), "volume"))
+        );
+        assert_eq!(topics.parse_command("heos/players/42/volume"), None);
+        assert_eq!(topics.parse_command("other/players/42/set/volume"), None);
+    }
+
+    #[test]
+    pub fn test_discovery_configs() {
+        let topics = Topics::new("heos", "homeassistant");
+        let configs = configs(&topics, &kitchen());
+        assert_eq!(configs.len(), 3);
+        let (topic, media_player) = &configs[0];
+        assert_eq!(topic, "homeassistant/media_player/heos_42/config");
+        assert_eq!(media_player["unique_id"], "heos_42");
+        assert_eq!(media_player["availability_topic"], "heos/status");
+        assert_eq!(
+            media_player["command_playpause_topic"],
+            "heos/players/42/set/play_pause"
+        );
+        // the media player counts the volume from 0.0 to 1.0, the number from 0 to 100
+        assert_eq!(
+            media_player["command_volume_topic"],
+            "heos/players/42/set/volume_level"
+        );
+        let (_, volume) = &configs[1];
+        assert_eq!(volume["state_topic"], "heos/players/42/volume");
+        assert_eq!(volume["command_topic"], "heos/players/42/set/volume");
+        assert_eq!(volume["device"]["identifiers"][0], "heos_42");
+    }
+
+    #[test]
+    pub fn test_player_state() {
+        let state = PlayerState::new(&kitchen());
+        let attributes = state.attributes();
+        assert!(attributes.contains(&("state", "paused".to_string())));
+        assert!(attributes.contains(&("volume", "20".to_string())));
+        assert!(attributes.contains(&("volume_level", "0.20".to_string())));
+        assert!(attributes.contains(&("mute", "off".to_string())));
+        assert_eq!(serde_json::to_value(&state).unwrap()["state"], "pause");
+    }
+}
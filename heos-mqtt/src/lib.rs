@@ -0,0 +1,11 @@
+//! A bridge between HEOS and MQTT.
+//!
+//! The state of every player is published as retained messages below
+//! `<base_topic>/players/<pid>`, commands are read from
+//! `<base_topic>/players/<pid>/set/<command>`. Home Assistant finds the
+//! players through the discovery configs below `<discovery_prefix>`.
+pub mod bridge;
+pub mod command;
+pub mod config;
+pub mod discovery;
+pub mod topics;
@@ -0,0 +1,26 @@
+use clap::Parser;
+use tracing::info;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use heos_api::HeosDriver;
+use heos_mqtt::bridge;
+use heos_mqtt::config::Config;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let config = Config::parse();
+    let rust_log = config
+        .rust_log
+        .clone()
+        .unwrap_or_else(|| "heos_mqtt=debug,heos_api=info".into());
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(rust_log))
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+    info!("Starting ...");
+    let driver = match config.heos_device_addr {
+        Some(addr) => HeosDriver::new((addr, 1255)).await?,
+        None => heos_api::find_driver().await?,
+    };
+    bridge::run(config, driver).await
+}
@@ -0,0 +1,62 @@
+use heos_api::types::{GroupId, PlayerId};
+
+/// Builds the topic names from the configured prefixes.
+#[derive(Debug, Clone)]
+pub struct Topics {
+    base: String,
+    discovery_prefix: String,
+}
+
+impl Topics {
+    pub fn new<B: Into<String>, D: Into<String>>(base: B, discovery_prefix: D) -> Self {
+        Topics {
+            base: base.into().trim_end_matches('/').to_string(),
+            discovery_prefix: discovery_prefix.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// `online` while the bridge runs, `offline` (the last will) otherwise.
+    pub fn status(&self) -> String {
+        format!("{}/status", self.base)
+    }
+
+    /// The whole state of the player as json.
+    pub fn player(&self, pid: PlayerId) -> String {
+        format!("{}/players/{}", self.base, pid)
+    }
+
+    /// A single value of the player, e.g. `volume`.
+    pub fn player_attribute(&self, pid: PlayerId, attribute: &str) -> String {
+        format!("{}/players/{}/{}", self.base, pid, attribute)
+    }
+
+    pub fn command(&self, pid: PlayerId, command: &str) -> String {
+        format!("{}/players/{}/set/{}", self.base, pid, command)
+    }
+
+    /// Subscribes to the commands of all players.
+    pub fn commands_filter(&self) -> String {
+        format!("{}/players/+/set/+", self.base)
+    }
+
+    pub fn group(&self, gid: GroupId) -> String {
+        format!("{}/groups/{}", self.base, gid)
+    }
+
+    pub fn discovery(&self, component: &str, pid: PlayerId) -> String {
+        format!(
+            "{}/{}/heos_{}/config",
+            self.discovery_prefix, component, pid
+        )
+    }
+
+    /// The player and command of a command topic.
+    pub fn parse_command<'a>(&self, topic: &'a str) -> Option<(PlayerId, &'a str)> {
+        let rest = topic.strip_prefix(&self.base)?.strip_prefix("/players/")?;
+        let (pid, command) = rest.split_once("/set/")?;
+        if command.is_empty() || command.contains('/') {
+            return None;
+        }
+        Some((pid.parse().ok()?, command))
+    }
+}
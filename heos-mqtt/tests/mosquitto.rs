@@ -0,0 +1,60 @@
+// Needs a broker, e.g. `mosquitto -p 1883`. Run with
+// `MQTT_TEST_BROKER=localhost:1883 cargo test -p heos-mqtt -- --ignored`
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use tokio::time::timeout;
+
+use heos_api::types::player::{HeosPlayer, PlayState};
+use heos_api::types::PlayerId;
+use heos_mqtt::bridge::publish_player_state;
+use heos_mqtt::discovery::PlayerState;
+use heos_mqtt::topics::Topics;
+
+fn broker() -> (String, u16) {
+    let broker = std::env::var("MQTT_TEST_BROKER").unwrap_or_else(|_| "localhost:1883".into());
+    let (host, port) = broker
+        .split_once(':')
+        .expect("MQTT_TEST_BROKER is host:port");
+    (host.to_string(), port.parse().expect("invalid port"))
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_player_state_is_retained() {
+    let (host, port) = broker();
+    let topics = Topics::new("heos-test", "homeassistant-test");
+    let player = HeosPlayer {
+        volume: 12,
+        play_state: PlayState::Play,
+        ..HeosPlayer::test_player(7, "Bedroom")
+    };
+
+    let (publisher, mut publisher_loop) =
+        AsyncClient::new(MqttOptions::new("heos-test-publisher", &host, port), 16);
+    let publishing = tokio::spawn(async move { while publisher_loop.poll().await.is_ok() {} });
+    publish_player_state(&publisher, &topics, &PlayerState::new(&player))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // a late subscriber still gets the state.
+    let (subscriber, mut subscriber_loop) =
+        AsyncClient::new(MqttOptions::new("heos-test-subscriber", &host, port), 16);
+    subscriber
+        .subscribe(topics.player_attribute(PlayerId(7), "volume"), QoS::AtLeastOnce)
+        .await
+        .unwrap();
+    let payload = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Event::Incoming(Packet::Publish(publish)) = subscriber_loop.poll().await.unwrap()
+            {
+                return publish.payload;
+            }
+        }
+    })
+    .await
+    .expect("no retained volume");
+    assert_eq!(&payload[..], b"12");
+    publishing.abort();
+}
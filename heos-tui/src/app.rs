@@ -0,0 +1,648 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use heos_api::types::browse::{BroseSourceItem, BrowsableMedia};
+use heos_api::types::event::HeosEvent;
+use heos_api::types::group::Group;
+use heos_api::types::player::{HeosPlayer, NowPlayingMedia, PlayState};
+use heos_api::types::{ContainerId, Level, Milliseconds, OnOrOff, PlayerId, Range, SourceId};
+use heos_api::{HeosDriver, HeosResult};
+
+pub const PAGE_SIZE: u16 = 20;
+pub const VOLUME_STEP: Level = 5;
+
+/// A group, or a player in no group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    /// the player, or the leader of the group
+    pub pid: PlayerId,
+    pub name: String,
+    pub group: bool,
+    pub members: Vec<PlayerId>,
+    pub volume: Level,
+    /// of the player, or of the leader of the group
+    pub mute: OnOrOff,
+    pub play_state: PlayState,
+    pub now_playing: Option<NowPlayingMedia>,
+}
+
+pub fn zones(players: &[HeosPlayer], groups: &[Group]) -> Vec<Zone> {
+    let mut zones = vec![];
+    for group in groups {
+        if let Some(leader) = players
+            .iter()
+            .find(|player| player.player_id == group.gid.leader())
+        {
+            zones.push(Zone {
+                pid: group.gid.leader(),
+                name: group.name.clone(),
+                group: true,
+                members: group.players.iter().map(|member| member.pid).collect(),
+                volume: group.volume,
+                mute: leader.mute,
+                play_state: leader.play_state,
+                now_playing: leader.now_playing.clone(),
+            });
+        }
+    }
+    for player in players {
+        let grouped = groups.iter().any(|group| {
+            group
+                .players
+                .iter()
+                .any(|member| member.pid == player.player_id)
+        });
+        if !grouped {
+            zones.push(Zone {
+                pid: player.player_id,
+                name: player.name.clone(),
+                group: false,
+                members: vec![player.player_id],
+                volume: player.volume,
+                mute: player.mute,
+                play_state: player.play_state,
+                now_playing: player.now_playing.clone(),
+            });
+        }
+    }
+    zones
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Sources,
+    Source(SourceId),
+    Container {
+        sid: SourceId,
+        cid: ContainerId,
+        name: String,
+    },
+    Search {
+        sid: SourceId,
+        text: String,
+    },
+}
+
+impl Location {
+    pub fn sid(&self) -> Option<SourceId> {
+        match self {
+            Location::Sources => None,
+            Location::Source(sid) => Some(*sid),
+            Location::Container { sid, .. } | Location::Search { sid, .. } => Some(*sid),
+        }
+    }
+
+    fn paged(&self) -> bool {
+        matches!(self, Location::Container { .. } | Location::Search { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub detail: String,
+    /// where enter leads to, if anywhere
+    pub open: Option<Location>,
+}
+
+/// The browse/search pane.
+#[derive(Debug, Clone)]
+pub struct Browser {
+    /// the last one is shown
+    pub path: Vec<Location>,
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+    /// the index of the first entry
+    pub start: u16,
+    /// the number of entries in all pages
+    pub count: usize,
+}
+
+impl Browser {
+    pub fn location(&self) -> &Location {
+        self.path.last().unwrap_or(&Location::Sources)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Zones,
+    Browse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    /// choosing the members of the group led by `leader`
+    EditGroup {
+        leader: PlayerId,
+        members: BTreeSet<PlayerId>,
+        selected: usize,
+    },
+    /// typing a search
+    Search {
+        input: String,
+    },
+}
+
+/// What a key press asks for, executed by [`App::execute`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    PlayState(PlayerId, PlayState),
+    Next(PlayerId),
+    Previous(PlayerId),
+    Volume {
+        pid: PlayerId,
+        group: bool,
+        level: Level,
+    },
+    Mute {
+        pid: PlayerId,
+        group: bool,
+        state: OnOrOff,
+    },
+    /// without members the group is dissolved
+    SetGroup {
+        leader: PlayerId,
+        members: Vec<PlayerId>,
+    },
+    /// shows a page of `location`, on top of the current one if `push`
+    Browse {
+        location: Location,
+        start: u16,
+        push: bool,
+    },
+}
+
+pub struct App {
+    driver: HeosDriver,
+    pub zones: Vec<Zone>,
+    pub selected: usize,
+    pub focus: Focus,
+    pub mode: Mode,
+    pub browser: Browser,
+    /// position and duration of the current track, from the progress events
+    pub progress: BTreeMap<PlayerId, (Milliseconds, Option<Milliseconds>)>,
+    /// the last error
+    pub status: Option<String>,
+}
+
+impl App {
+    pub fn new(driver: HeosDriver) -> Self {
+        let mut app = App {
+            driver,
+            zones: vec![],
+            selected: 0,
+            focus: Focus::Zones,
+            mode: Mode::Normal,
+            browser: Browser {
+                path: vec![Location::Sources],
+                entries: vec![],
+                selected: 0,
+                start: 0,
+                count: 0,
+            },
+            progress: BTreeMap::new(),
+            status: None,
+        };
+        app.refresh();
+        app
+    }
+
+    pub fn driver(&self) -> &HeosDriver {
+        &self.driver
+    }
+
+    pub fn selected_zone(&self) -> Option<&Zone> {
+        self.zones.get(self.selected)
+    }
+
+    /// Takes the zones from the driver, which keeps them up to date.
+    pub fn refresh(&mut self) {
+        self.zones = zones(&self.driver.players(), &self.driver.groups());
+        self.selected = self.selected.min(self.zones.len().saturating_sub(1));
+        if self.browser.location() == &Location::Sources {
+            self.browser.entries = self
+                .driver
+                .music_sources()
+                .into_iter()
+                .map(|source| Entry {
+                    name: source.name,
+                    detail: source.source_type,
+                    open: Some(Location::Source(source.sid)),
+                })
+                .collect();
+            self.browser.count = self.browser.entries.len();
+        }
+    }
+
+    pub fn on_event(&mut self, event: HeosEvent) {
+        match event {
+            HeosEvent::PlayerNowPlayingProgress {
+                player_id,
+                cur_pos,
+                duration,
+            } => {
+                self.progress.insert(player_id, (cur_pos, duration));
+            }
+            HeosEvent::PlayerNowPlayingChanged { player_id } => {
+                self.progress.remove(&player_id);
+            }
+            _ => {}
+        }
+        self.refresh();
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<Command> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Some(Command::Quit);
+        }
+        match self.mode.clone() {
+            Mode::EditGroup {
+                leader,
+                members,
+                selected,
+            } => self.on_group_key(key, leader, members, selected),
+            Mode::Search { input } => self.on_search_key(key, input),
+            Mode::Normal => match (key.code, self.focus) {
+                (KeyCode::Char('q'), _) => Some(Command::Quit),
+                (KeyCode::Tab, Focus::Zones) => {
+                    self.focus = Focus::Browse;
+                    None
+                }
+                (KeyCode::Tab, Focus::Browse) => {
+                    self.focus = Focus::Zones;
+                    None
+                }
+                (_, Focus::Zones) => self.on_zone_key(key),
+                (_, Focus::Browse) => self.on_browse_key(key),
+            },
+        }
+    }
+
+    fn on_zone_key(&mut self, key: KeyEvent) -> Option<Command> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                return None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.zones.len().saturating_sub(1));
+                return None;
+            }
+            _ => {}
+        }
+        let zone = self.selected_zone()?.clone();
+        match key.code {
+            KeyCode::Char(' ') => {
+                let state = if zone.play_state == PlayState::Play {
+                    PlayState::Pause
+                } else {
+                    PlayState::Play
+                };
+                Some(Command::PlayState(zone.pid, state))
+            }
+            KeyCode::Char('s') => Some(Command::PlayState(zone.pid, PlayState::Stop)),
+            KeyCode::Char('n') => Some(Command::Next(zone.pid)),
+            KeyCode::Char('p') => Some(Command::Previous(zone.pid)),
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Right => Some(Command::Volume {
+                pid: zone.pid,
+                group: zone.group,
+                level: zone.volume.saturating_add(VOLUME_STEP).min(100),
+            }),
+            KeyCode::Char('-') | KeyCode::Left => Some(Command::Volume {
+                pid: zone.pid,
+                group: zone.group,
+                level: zone.volume.saturating_sub(VOLUME_STEP),
+            }),
+            KeyCode::Char('m') => {
+                let state = match zone.mute {
+                    OnOrOff::On => OnOrOff::Off,
+                    OnOrOff::Off => OnOrOff::On,
+                };
+                Some(Command::Mute {
+                    pid: zone.pid,
+                    group: zone.group,
+                    state,
+                })
+            }
+            KeyCode::Char('g') => {
+                self.mode = Mode::EditGroup {
+                    leader: zone.pid,
+                    members: zone
+                        .members
+                        .iter()
+                        .copied()
+                        .filter(|pid| *pid != zone.pid)
+                        .collect(),
+                    selected: 0,
+                };
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// The players that can join the group of `leader`.
+    pub fn group_candidates(&self, leader: PlayerId) -> Vec<HeosPlayer> {
+        self.driver
+            .players()
+            .into_iter()
+            .filter(|player| player.player_id != leader)
+            .collect()
+    }
+
+    fn on_group_key(
+        &mut self,
+        key: KeyEvent,
+        leader: PlayerId,
+        mut members: BTreeSet<PlayerId>,
+        mut selected: usize,
+    ) -> Option<Command> {
+        let candidates = self.group_candidates(leader);
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                return None;
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                return Some(Command::SetGroup {
+                    leader,
+                    members: members.into_iter().collect(),
+                });
+            }
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(candidates.len().saturating_sub(1))
+            }
+            KeyCode::Char(' ') => {
+                if let Some(player) = candidates.get(selected) {
+                    if !members.remove(&player.player_id) {
+                        members.insert(player.player_id);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.mode = Mode::EditGroup {
+            leader,
+            members,
+            selected,
+        };
+        None
+    }
+
+    fn on_browse_key(&mut self, key: KeyEvent) -> Option<Command> {
+        let browser = &mut self.browser;
+        let location = browser.location().clone();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                browser.selected = browser.selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                browser.selected =
+                    (browser.selected + 1).min(browser.entries.len().saturating_sub(1));
+                None
+            }
+            KeyCode::Enter | KeyCode::Right => {
+                let location = browser.entries.get(browser.selected)?.open.clone()?;
+                Some(Command::Browse {
+                    location,
+                    start: 0,
+                    push: true,
+                })
+            }
+            KeyCode::Backspace | KeyCode::Left if browser.path.len() > 1 => {
+                browser.path.pop();
+                Some(Command::Browse {
+                    location: browser.location().clone(),
+                    start: 0,
+                    push: false,
+                })
+            }
+            KeyCode::PageDown | KeyCode::Char(']')
+                if location.paged() && ((browser.start + PAGE_SIZE) as usize) < browser.count =>
+            {
+                Some(Command::Browse {
+                    location,
+                    start: browser.start + PAGE_SIZE,
+                    push: false,
+                })
+            }
+            KeyCode::PageUp | KeyCode::Char('[') if location.paged() && browser.start > 0 => {
+                Some(Command::Browse {
+                    location,
+                    start: browser.start.saturating_sub(PAGE_SIZE),
+                    push: false,
+                })
+            }
+            KeyCode::Char('/') if location.sid().is_some() => {
+                self.mode = Mode::Search {
+                    input: String::new(),
+                };
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn on_search_key(&mut self, key: KeyEvent, mut input: String) -> Option<Command> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                return None;
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                let sid = self.browser.location().sid()?;
+                if input.is_empty() {
+                    return None;
+                }
+                return Some(Command::Browse {
+                    location: Location::Search { sid, text: input },
+                    start: 0,
+                    push: true,
+                });
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Search { input };
+        None
+    }
+
+    /// Executes a command, returns false to quit.
+    pub async fn execute(&mut self, command: Command) -> bool {
+        let result = match command {
+            Command::Quit => return false,
+            Command::PlayState(pid, state) => self.driver.set_play_state(pid, state).await,
+            Command::Next(pid) => self.driver.play_next(pid).await,
+            Command::Previous(pid) => self.driver.play_previous(pid).await,
+            Command::Volume {
+                pid,
+                group: false,
+                level,
+            } => self.driver.set_volume(pid, level).await.map(|_| ()),
+            Command::Volume {
+                pid,
+                group: true,
+                level,
+            } => self
+                .driver
+                .set_group_volume(pid.group(), level)
+                .await
+                .map(|_| ()),
+            Command::Mute {
+                pid,
+                group: false,
+                state,
+            } => self.driver.set_mute(pid, state).await,
+            Command::Mute {
+                pid,
+                group: true,
+                state,
+            } => self.driver.set_group_mute(pid.group(), state).await,
+            Command::SetGroup { leader, members } => {
+                self.driver.create_group(leader, members).await
+            }
+            Command::Browse {
+                location,
+                start,
+                push,
+            } => self.browse(location, start, push).await,
+        };
+        self.status = result.err().map(|err| err.to_string());
+        self.refresh();
+        true
+    }
+
+    async fn browse(&mut self, location: Location, start: u16, push: bool) -> HeosResult<()> {
+        let range = Range {
+            start,
+            end: start + PAGE_SIZE - 1,
+        };
+        let (entries, count) = match &location {
+            Location::Sources => (vec![], 0),
+            Location::Source(sid) => {
+                let entries: Vec<Entry> = self
+                    .driver
+                    .browse(*sid)
+                    .await?
+                    .into_iter()
+                    .map(|item| item_entry(*sid, item))
+                    .collect();
+                let count = entries.len();
+                (entries, count)
+            }
+            Location::Container { sid, cid, .. } => {
+                let page = self
+                    .driver
+                    .browse_music_containers(sid, cid, &range)
+                    .await?;
+                let entries = page
+                    .items
+                    .into_iter()
+                    .map(|media| media_entry(*sid, media))
+                    .collect();
+                (entries, page.count)
+            }
+            Location::Search { sid, text } => {
+                let criteria = self.driver.get_search_criteria(*sid).await?;
+                let scid = match criteria.first() {
+                    Some(criteria) => criteria.scid,
+                    None => return Ok(()),
+                };
+                let results = self.driver.search(*sid, scid, text, &range).await?;
+                let entries = results
+                    .items
+                    .into_iter()
+                    .map(|item| item_entry(*sid, item))
+                    .collect();
+                (entries, results.count)
+            }
+        };
+        if push {
+            self.browser.path.push(location);
+        }
+        self.browser.entries = entries;
+        self.browser.count = count;
+        self.browser.start = start;
+        self.browser.selected = 0;
+        Ok(())
+    }
+}
+
+fn item_entry(sid: SourceId, item: BroseSourceItem) -> Entry {
+    match item {
+        BroseSourceItem::HeosService(service) => Entry {
+            name: service.name,
+            detail: service.server_type,
+            open: Some(Location::Source(service.sid)),
+        },
+        BroseSourceItem::BrowsableMedia(media) => media_entry(sid, media),
+    }
+}
+
+fn media_entry(sid: SourceId, media: BrowsableMedia) -> Entry {
+    let open = media.container_id.clone().map(|cid| Location::Container {
+        sid,
+        cid,
+        name: media.name.clone(),
+    });
+    Entry {
+        detail: media.artist.unwrap_or_default(),
+        name: media.name,
+        open,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use heos_api::types::group::{GroupMember, GroupRole};
+    use heos_api::types::GroupId;
+
+    use super::*;
+
+    #[test]
+    pub fn test_zones() {
+        let players = vec![
+            HeosPlayer::test_player(1, "Living Room"),
+            HeosPlayer::test_player(2, "Kitchen"),
+            HeosPlayer::test_player(3, "Bath"),
+        ];
+        let groups = vec![Group {
+            name: "Living Room + Kitchen".to_string(),
+            gid: GroupId(1),
+            volume: 30,
+            players: vec![
+                GroupMember {
+                    name: "Living Room".to_string(),
+                    pid: PlayerId(1),
+                    role: GroupRole::Leader,
+                },
+                GroupMember {
+                    name: "Kitchen".to_string(),
+                    pid: PlayerId(2),
+                    role: GroupRole::Member,
+                },
+            ],
+        }];
+        let zones = zones(&players, &groups);
+        assert_eq!(zones.len(), 2);
+        assert_eq!(zones[0].name, "Living Room + Kitchen");
+        assert_eq!(zones[0].volume, 30);
+        assert!(zones[0].group);
+        assert_eq!(zones[1].name, "Bath");
+        assert_eq!(zones[1].members, vec![PlayerId(3)]);
+    }
+}
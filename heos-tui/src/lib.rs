@@ -0,0 +1,7 @@
+//! A terminal controller for HEOS speakers.
+//!
+//! [`app::App`] holds the state and turns keys into commands, [`ui::draw`] renders it.
+//! Both work without a terminal, which is how the tests drive them.
+
+pub mod app;
+pub mod ui;
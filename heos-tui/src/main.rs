@@ -0,0 +1,78 @@
+use std::io;
+use std::net::IpAddr;
+
+use clap::Parser;
+use crossterm::event::{Event, EventStream};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use futures::StreamExt;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::Terminal;
+use tokio::sync::broadcast::error::RecvError;
+
+use heos_api::HeosDriver;
+use heos_tui::app::App;
+use heos_tui::ui;
+
+/// Control HEOS speakers from the terminal.
+#[derive(Parser, Debug)]
+#[command(name = "heos-tui", version, about)]
+struct Config {
+    /// The HEOS device to talk to, discovered if missing.
+    #[arg(long, env = "HEOS_DEVICE_ADDR")]
+    heos_device_addr: Option<IpAddr>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let config = Config::parse();
+    // connect first, so errors are printed to a normal terminal.
+    let driver = match config.heos_device_addr {
+        Some(addr) => HeosDriver::new((addr, 1255)).await?,
+        None => heos_api::find_driver().await?,
+    };
+    let mut app = App::new(driver);
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run(&mut terminal, &mut app).await;
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+/// Redraws after every key and every event of the driver, there is no polling.
+async fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> anyhow::Result<()> {
+    let mut keys = EventStream::new();
+    let mut events = app.driver().subscribe();
+    loop {
+        terminal.draw(|f| ui::draw(f, app))?;
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(Event::Key(key))) => {
+                    if let Some(command) = app.on_key(key) {
+                        if !app.execute(command).await {
+                            return Ok(());
+                        }
+                    }
+                }
+                // e.g. a resize, redrawn above
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+                None => return Ok(()),
+            },
+            event = events.recv() => match event {
+                Ok(event) => app.on_event(event),
+                Err(RecvError::Lagged(_)) => app.refresh(),
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
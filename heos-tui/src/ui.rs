@@ -0,0 +1,194 @@
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Span, Spans};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use heos_api::types::player::PlayState;
+use heos_api::types::{Milliseconds, OnOrOff};
+
+use crate::app::{App, Focus, Location, Mode, Zone};
+
+const HELP: &str = "q quit  tab pane  space play/pause  n/p next/prev  +/- volume  m mute  g group  enter open  backspace up  [/] page  / search";
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.size());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(rows[0]);
+    draw_zones(f, app, panes[0]);
+    draw_browser(f, app, panes[1]);
+
+    let status = match &app.status {
+        Some(error) => Paragraph::new(error.as_str()).style(Style::default().fg(Color::Red)),
+        None => Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+    };
+    f.render_widget(status, rows[1]);
+
+    if let Mode::EditGroup {
+        leader,
+        members,
+        selected,
+    } = &app.mode
+    {
+        let items: Vec<ListItem> = app
+            .group_candidates(*leader)
+            .into_iter()
+            .map(|player| {
+                let mark = if members.contains(&player.player_id) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                ListItem::new(format!("{} {}", mark, player.name))
+            })
+            .collect();
+        let area = centered(panes[0], items.len() as u16 + 2);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Group: space toggles, enter applies"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        state.select(Some(*selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+fn draw_zones<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let items: Vec<ListItem> = app.zones.iter().map(zone_item).collect();
+    let list = List::new(items)
+        .block(pane("Zones", app.focus == Focus::Zones))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(app.selected_zone().map(|_| app.selected));
+    f.render_stateful_widget(list, parts[0], &mut state);
+
+    let (ratio, label) = match app
+        .selected_zone()
+        .and_then(|zone| app.progress.get(&zone.pid))
+    {
+        Some((cur_pos, Some(duration))) if *duration > 0 => (
+            (*cur_pos as f64 / *duration as f64).min(1.0),
+            format!("{} / {}", time(*cur_pos), time(*duration)),
+        ),
+        Some((cur_pos, _)) => (0.0, time(*cur_pos)),
+        None => (0.0, String::new()),
+    };
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title("Progress"))
+        .gauge_style(Style::default().fg(Color::Cyan))
+        .ratio(ratio)
+        .label(label);
+    f.render_widget(gauge, parts[1]);
+}
+
+fn zone_item<'a>(zone: &Zone) -> ListItem<'a> {
+    let state = match zone.play_state {
+        PlayState::Play => "▶",
+        PlayState::Pause => "⏸",
+        PlayState::Stop => "■",
+    };
+    let mute = match zone.mute {
+        OnOrOff::On => " (muted)",
+        OnOrOff::Off => "",
+    };
+    let mut lines = vec![Spans::from(vec![
+        Span::raw(format!("{} ", state)),
+        Span::styled(
+            zone.name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!("  vol {}{}", zone.volume, mute)),
+    ])];
+    if let Some(media) = &zone.now_playing {
+        lines.push(Spans::from(format!("  {} - {}", media.artist, media.song)));
+    }
+    ListItem::new(lines)
+}
+
+fn draw_browser<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let browser = &app.browser;
+    let title = match (&app.mode, browser.location()) {
+        (Mode::Search { input }, _) => format!("Search: {}_", input),
+        (_, Location::Sources) => "Sources".to_string(),
+        (_, Location::Source(sid)) => format!("Source {}", sid),
+        (_, Location::Container { name, .. }) => name.clone(),
+        (_, Location::Search { text, .. }) => format!("Results for '{}'", text),
+    };
+    let title = if browser.count > browser.entries.len() {
+        format!(
+            "{} ({}-{} of {})",
+            title,
+            browser.start + 1,
+            browser.start as usize + browser.entries.len(),
+            browser.count
+        )
+    } else {
+        title
+    };
+    let items: Vec<ListItem> = browser
+        .entries
+        .iter()
+        .map(|entry| {
+            let marker = if entry.open.is_some() { "+ " } else { "  " };
+            ListItem::new(Spans::from(vec![
+                Span::raw(format!("{}{}", marker, entry.name)),
+                Span::styled(
+                    format!("  {}", entry.detail),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(pane(&title, app.focus == Focus::Browse))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    if !browser.entries.is_empty() {
+        state.select(Some(browser.selected));
+    }
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn pane(title: &str, focused: bool) -> Block<'static> {
+    let style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(title.to_string())
+}
+
+fn centered(area: Rect, height: u16) -> Rect {
+    let height = height.min(area.height);
+    let width = area.width.saturating_sub(4);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// `m:ss`
+pub fn time(ms: Milliseconds) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
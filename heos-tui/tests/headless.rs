@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use tokio::sync::broadcast::Receiver;
+use tokio::time::timeout;
+
+use heos_api::simulator::SimulatedDevice;
+use heos_api::types::event::HeosEvent;
+use heos_api::types::PlayerId;
+use heos_api::HeosDriver;
+use heos_tui::app::App;
+use heos_tui::ui;
+
+async fn press(app: &mut App, code: KeyCode) {
+    if let Some(command) = app.on_key(KeyEvent::new(code, KeyModifiers::NONE)) {
+        assert!(app.execute(command).await);
+    }
+}
+
+// hands the events to the app until one matches.
+async fn wait_for<F: Fn(&HeosEvent) -> bool>(
+    events: &mut Receiver<HeosEvent>,
+    app: &mut App,
+    matches: F,
+) {
+    loop {
+        let event = timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("no event in time")
+            .expect("events closed");
+        let done = matches(&event);
+        app.on_event(event);
+        if done {
+            return;
+        }
+    }
+}
+
+fn screen(terminal: &mut Terminal<TestBackend>, app: &App) -> String {
+    terminal.draw(|f| ui::draw(f, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..buffer.area.height {
+        for x in 0..buffer.area.width {
+            screen.push_str(&buffer.get(x, y).symbol);
+        }
+        screen.push('\n');
+    }
+    screen
+}
+
+#[tokio::test]
+async fn test_headless() {
+    let device = SimulatedDevice::start().await.unwrap();
+    let driver = HeosDriver::new(device.addr()).await.unwrap();
+    let mut events = driver.subscribe();
+    let mut app = App::new(driver);
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+
+    let text = screen(&mut terminal, &app);
+    assert!(text.contains("Living Room"), "{}", text);
+    assert!(text.contains("Fleetwood Mac - Dreams"), "{}", text);
+    assert!(text.contains("Local Music"), "{}", text);
+
+    press(&mut app, KeyCode::Char('+')).await;
+    assert_eq!(device.state().players[&PlayerId(1)].volume, 25);
+    wait_for(&mut events, &mut app, |event| {
+        matches!(event, HeosEvent::PlayerVolumeChanged { .. })
+    })
+    .await;
+    assert!(screen(&mut terminal, &app).contains("vol 25"));
+
+    device.progress(PlayerId(1), 60_000, 200_000);
+    wait_for(&mut events, &mut app, |event| {
+        matches!(event, HeosEvent::PlayerNowPlayingProgress { .. })
+    })
+    .await;
+    assert!(screen(&mut terminal, &app).contains("1:00 / 3:20"));
+
+    // open Local Music, then the first album
+    press(&mut app, KeyCode::Tab).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(screen(&mut terminal, &app).contains("Kind of Blue"));
+    press(&mut app, KeyCode::Enter).await;
+    assert!(screen(&mut terminal, &app).contains("The Chain"));
+
+    press(&mut app, KeyCode::Tab).await;
+    press(&mut app, KeyCode::Char('g')).await;
+    assert!(screen(&mut terminal, &app).contains("[ ] Kitchen"));
+    press(&mut app, KeyCode::Char(' ')).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(device
+        .state()
+        .commands
+        .contains(&"group/set_group?pid=1,2".to_string()));
+    assert!(screen(&mut terminal, &app).contains("Living Room + Kitchen"));
+}
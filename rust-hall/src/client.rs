@@ -0,0 +1,233 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Method, StatusCode, Url};
+
+use crate::{HalList, HalResource, Link, TemplateError, Variables};
+
+const ACCEPT_HAL: &str = "application/hal+json, application/json;q=0.9";
+
+/// Fetches HAL resources and navigates between them by rel.
+///
+/// ```ignore
+/// let client = HalClient::new();
+/// let zones = client.get("http://localhost:8080/api/zones").await?;
+/// let zone = &zones.embedded_resources("zones")[0];
+/// client.submit(zone, "volume", &json!({ "level": 20 })).await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HalClient {
+    http: reqwest::Client,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Http(reqwest::Error),
+    Status { status: StatusCode, body: String },
+    InvalidUrl(String),
+    Template(TemplateError),
+    Json(serde_json::Error),
+    NoSuchRel(String),
+    NoSuchTemplate(String),
+}
+
+impl HalClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_client(http: reqwest::Client) -> Self {
+        HalClient { http }
+    }
+
+    pub async fn get(&self, url: &str) -> Result<HalResource, ClientError> {
+        let url = Url::parse(url).map_err(|_| ClientError::InvalidUrl(url.to_string()))?;
+        let response = self.http.get(url).header(ACCEPT, ACCEPT_HAL).send().await?;
+        let response = check_status(response).await?;
+        let fetched_from = response.url().clone();
+        let mut resource = response.json().await?;
+        absolute_self_links(&mut resource, &fetched_from);
+        Ok(resource)
+    }
+
+    /// Fetches the first link of `rel`. Templated links are expanded with `variables`.
+    pub async fn follow(
+        &self,
+        resource: &HalResource,
+        rel: &str,
+        variables: &Variables,
+    ) -> Result<HalResource, ClientError> {
+        let link = resource
+            .link(rel)
+            .ok_or_else(|| ClientError::NoSuchRel(rel.to_string()))?;
+        let url = resolve(resource, &link.expand(variables)?)?;
+        self.get(url.as_str()).await
+    }
+
+    /// Starts at `url` and follows the rels one after another.
+    pub async fn traverse(&self, url: &str, rels: &[&str]) -> Result<HalResource, ClientError> {
+        let mut resource = self.get(url).await?;
+        for rel in rels {
+            resource = self.follow(&resource, rel, &Variables::new()).await?;
+        }
+        Ok(resource)
+    }
+
+    /// Sends `body` as described by the HAL-FORMS template `name` of the resource.
+    ///
+    /// Returns the resource in the response, if there is one.
+    pub async fn submit<B: serde::Serialize>(
+        &self,
+        resource: &HalResource,
+        name: &str,
+        body: &B,
+    ) -> Result<Option<HalResource>, ClientError> {
+        let form = resource
+            .template(name)
+            .ok_or_else(|| ClientError::NoSuchTemplate(name.to_string()))?;
+        // without a target the form is sent to the resource itself.
+        let target = match &form.target {
+            Some(target) => target.as_str(),
+            None => resource
+                .self_link()
+                .map(|link| link.get_href())
+                .ok_or_else(|| ClientError::NoSuchRel("self".to_string()))?,
+        };
+        let url = resolve(resource, target)?;
+        let method = Method::from_bytes(form.method.as_bytes())
+            .map_err(|_| ClientError::NoSuchTemplate(name.to_string()))?;
+        let body = serde_json::to_vec(body).map_err(ClientError::Json)?;
+        let response = self
+            .http
+            .request(method, url)
+            .header(ACCEPT, ACCEPT_HAL)
+            .header(CONTENT_TYPE, form.content_type())
+            .body(body)
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        let fetched_from = response.url().clone();
+        let bytes = response.bytes().await?;
+        let mut resource = serde_json::from_slice(&bytes).ok();
+        if let Some(resource) = &mut resource {
+            absolute_self_links(resource, &fetched_from);
+        }
+        Ok(resource)
+    }
+}
+
+// Makes the self links of a fetched resource and of its embedded resources absolute.
+// A relative self link is relative to the url the resource was fetched from, a
+// resource without self link gets that url as its self link.
+fn absolute_self_links(resource: &mut HalResource, fetched_from: &Url) {
+    let base = match resource.self_link() {
+        Some(link) => fetched_from
+            .join(link.get_href())
+            .unwrap_or_else(|_| fetched_from.clone()),
+        None => fetched_from.clone(),
+    };
+    match resource
+        .links
+        .get_mut("self")
+        .and_then(|links| links.contents.first_mut())
+    {
+        Some(link) => link.href = base.to_string(),
+        None => {
+            resource
+                .links
+                .insert("self".to_string(), HalList::new().with(Link::href(&base)));
+        }
+    }
+    for embedded in resource.nested.values_mut() {
+        for nested in embedded.contents.iter_mut() {
+            if nested.self_link().is_some() {
+                absolute_self_links(nested, &base);
+            }
+        }
+    }
+}
+
+// relative hrefs are relative to the self link of the resource.
+fn resolve(resource: &HalResource, href: &str) -> Result<Url, ClientError> {
+    let invalid = || ClientError::InvalidUrl(href.to_string());
+    if let Ok(url) = Url::parse(href) {
+        return Ok(url);
+    }
+    let base = resource
+        .self_link()
+        .and_then(|link| Url::parse(link.get_href()).ok())
+        .ok_or_else(invalid)?;
+    base.join(href).map_err(|_| invalid())
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(ClientError::Status { status, body })
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+impl From<TemplateError> for ClientError {
+    fn from(err: TemplateError) -> Self {
+        ClientError::Template(err)
+    }
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "request failed: {}", err),
+            ClientError::Status { status, body } => write!(f, "{}: {}", status, body),
+            ClientError::InvalidUrl(url) => write!(f, "invalid url {}", url),
+            ClientError::Template(err) => write!(f, "{}", err),
+            ClientError::Json(err) => write!(f, "{}", err),
+            ClientError::NoSuchRel(rel) => write!(f, "the resource has no link {}", rel),
+            ClientError::NoSuchTemplate(name) => write!(f, "the resource has no template {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_relative_self_links_are_resolved_against_the_fetched_url() {
+        let fetched_from = Url::parse("http://localhost:8000/api/zones").unwrap();
+        let mut zones: HalResource = serde_json::from_value(serde_json::json!({
+            "_embedded": {
+                "zones": [{ "_links": { "self": { "href": "zones/1" } } }]
+            }
+        }))
+        .unwrap();
+        absolute_self_links(&mut zones, &fetched_from);
+        assert_eq!(
+            zones.self_link().unwrap().get_href(),
+            "http://localhost:8000/api/zones"
+        );
+        let zone = &zones.embedded_resources("zones")[0];
+        assert_eq!(
+            zone.self_link().unwrap().get_href(),
+            "http://localhost:8000/api/zones/1"
+        );
+        assert_eq!(
+            resolve(zone, "/api/zones/1/volume").unwrap().as_str(),
+            "http://localhost:8000/api/zones/1/volume"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::{HalContext, Link, TemplateError, UriTemplate, Variables};
+
+/// Named uri templates, e.g. the routes of an application.
+///
+/// ```ignore
+/// let templates = LinkTemplates::new().with_template("zone", "/api/zones/{zone_id}")?;
+/// let (rel, link) = templates.create_link("zone", [42])?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LinkTemplates {
+    templates: BTreeMap<String, UriTemplate>,
+}
+
+impl LinkTemplates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_template<N: Display>(
+        mut self,
+        name: N,
+        template: &str,
+    ) -> Result<Self, TemplateError> {
+        self.templates
+            .insert(name.to_string(), UriTemplate::parse(template)?);
+        Ok(self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&UriTemplate> {
+        self.templates.get(name)
+    }
+
+    /// The template itself as link, for clients that fill in the variables.
+    pub fn templated_link(&self, name: &str) -> Result<Link, TemplateError> {
+        self.get(name)
+            .map(Link::templated)
+            .ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))
+    }
+}
+
+impl HalContext for LinkTemplates {
+    fn create_link<I, A>(&self, name: &str, parameters: A) -> Result<(&str, Link), TemplateError>
+    where
+        A: IntoIterator<Item = I>,
+        I: Display,
+    {
+        let (name, template) = self
+            .templates
+            .get_key_value(name)
+            .ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))?;
+        let variables = template
+            .variable_names()
+            .into_iter()
+            .zip(parameters)
+            .fold(Variables::new(), |variables, (variable, parameter)| {
+                variables.with(variable, parameter.to_string())
+            });
+        Ok((name.as_str(), Link::href(template.expand(&variables))))
+    }
+}
@@ -0,0 +1,191 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+
+use crate::Link;
+
+/// A HAL-FORMS template, see https://rwcbook.github.io/hal-forms/
+///
+/// Templates are added to a resource below `_templates` and tell a client
+/// which requests it can make. Without a `target` the request goes to the
+/// `self` link of the resource.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HalForm {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub method: String,
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+impl HalForm {
+    pub fn new<M: Display>(method: M) -> Self {
+        HalForm {
+            method: method.to_string().to_uppercase(),
+            ..Default::default()
+        }
+    }
+    pub fn with_title<T: Display>(mut self, title: T) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+    pub fn with_target<T: Display>(mut self, target: T) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+    pub fn with_content_type<T: Display>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+    pub fn with_property(mut self, property: Property) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// The spec says the content type defaults to `application/json`.
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_deref().unwrap_or("application/json")
+    }
+}
+
+/// A field of a [HalForm]. Only `name` is mandatory, everything else is a hint for the client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Property {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(rename = "readOnly", skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    // the value is a uri template.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<i64>,
+    #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u32>,
+    #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cols: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<PropertyOptions>,
+}
+
+impl Property {
+    pub fn new<N: Display>(name: N) -> Self {
+        Property {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+    pub fn required(mut self) -> Self {
+        self.required = Some(true);
+        self
+    }
+    pub fn read_only(mut self) -> Self {
+        self.read_only = Some(true);
+        self
+    }
+    pub fn with_prompt<P: Display>(mut self, prompt: P) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+    pub fn with_value<V: serde::Serialize>(mut self, value: V) -> Self {
+        self.value = serde_json::to_value(value).ok();
+        self
+    }
+    pub fn with_regex<R: Display>(mut self, regex: R) -> Self {
+        self.regex = Some(regex.to_string());
+        self
+    }
+    pub fn with_type<T: Display>(mut self, input_type: T) -> Self {
+        self.input_type = Some(input_type.to_string());
+        self
+    }
+    pub fn with_range(mut self, min: i64, max: i64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+    /// The property has to be one of the given values.
+    pub fn with_inline_options<I, V>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: serde::Serialize,
+    {
+        self.options = Some(PropertyOptions {
+            inline: values
+                .into_iter()
+                .filter_map(|v| serde_json::to_value(v).ok())
+                .collect(),
+            ..Default::default()
+        });
+        self
+    }
+    /// The possible values are fetched from `link`.
+    pub fn with_options_link<L: Into<Link>>(mut self, link: L) -> Self {
+        self.options = Some(PropertyOptions {
+            link: Some(link.into()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required.unwrap_or(false)
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.unwrap_or(false)
+    }
+}
+
+/// The allowed values of a property, either `inline` or behind a `link`.
+///
+/// Inline options are either plain values or objects with `prompt` and `value`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PropertyOptions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inline: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<Link>,
+    #[serde(rename = "maxItems", skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u32>,
+    #[serde(rename = "minItems", skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<u32>,
+    #[serde(rename = "promptField", skip_serializing_if = "Option::is_none")]
+    pub prompt_field: Option<String>,
+    #[serde(rename = "valueField", skip_serializing_if = "Option::is_none")]
+    pub value_field: Option<String>,
+    #[serde(
+        rename = "selectedValues",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub selected_values: Vec<Value>,
+}
@@ -5,9 +5,24 @@ use serde::de::Error;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 
+#[cfg(feature = "client")]
+mod client;
+mod context;
+mod forms;
+pub mod negotiate;
+mod uri_template;
+
+#[cfg(feature = "client")]
+pub use client::{ClientError, HalClient};
+pub use context::LinkTemplates;
+pub use forms::{HalForm, Property, PropertyOptions};
+pub use uri_template::{TemplateError, TemplateValue, UriTemplate, Variables};
+
 #[derive(Clone, Debug)]
 pub struct HalList<A> {
     contents: Vec<A>,
+    // serialize a single value as array, e.g. for `curies`.
+    array: bool,
 }
 
 impl<A> HalList<A>
@@ -17,6 +32,13 @@ where
     pub fn new() -> Self {
         Self {
             contents: Vec::new(),
+            array: false,
+        }
+    }
+    pub fn new_array() -> Self {
+        Self {
+            contents: Vec::new(),
+            array: true,
         }
     }
     pub fn with(mut self, value: A) -> Self {
@@ -26,11 +48,17 @@ where
     pub fn push(&mut self, value: A) {
         self.contents.push(value);
     }
+    pub fn as_slice(&self) -> &[A] {
+        &self.contents
+    }
 }
 
 impl<A> Into<HalList<A>> for Vec<A> {
     fn into(self) -> HalList<A> {
-        HalList { contents: self }
+        HalList {
+            contents: self,
+            array: true,
+        }
     }
 }
 
@@ -42,7 +70,9 @@ where
     where
         S: serde::Serializer,
     {
-        if self.contents.is_empty() {
+        if self.array {
+            self.contents.serialize(serializer)
+        } else if self.contents.is_empty() {
             ().serialize(serializer)
         } else if self.contents.len() == 1 {
             self.contents.first().serialize(serializer)
@@ -79,8 +109,18 @@ pub struct HalResource {
     links: BTreeMap<String, HalList<Link>>,
     #[serde(flatten)]
     values: BTreeMap<String, Value>,
-    #[serde(rename = "_nested")]
+    #[serde(
+        rename = "_embedded",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
     nested: BTreeMap<String, HalList<HalResource>>,
+    #[serde(
+        rename = "_templates",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    templates: BTreeMap<String, HalForm>,
 }
 
 impl HalResource {
@@ -90,10 +130,17 @@ impl HalResource {
             values: BTreeMap::default(),
             links: BTreeMap::default(),
             nested: BTreeMap::default(),
+            templates: BTreeMap::default(),
         }
         .add_link("self", link)
     }
 
+    /// Adds a HAL-FORMS template. The first template should be called `default`.
+    pub fn with_template<D: Display>(mut self, name: D, form: HalForm) -> Self {
+        self.templates.insert(name.to_string(), form);
+        self
+    }
+
     // adds the objects properties.
     pub fn add_object<V>(mut self, value: V) -> Self
     where
@@ -131,6 +178,95 @@ impl HalResource {
         self
     }
 
+    /// Adds a CURIE, `href` has to be a uri template with a `rel` variable.
+    ///
+    /// With `with_curie("heos", "https://example.org/rels/{rel}")` the link `heos:zone`
+    /// stands for the relation `https://example.org/rels/zone`.
+    pub fn with_curie<N: Display, H: Display>(mut self, name: N, href: H) -> Self {
+        let curie = Link::templated(href).with_name(name);
+        self.links
+            .entry("curies".to_string())
+            .or_insert_with(HalList::new_array)
+            .push(curie);
+        self
+    }
+
+    /// Expands a compact rel like `acme:widgets` with the matching CURIE.
+    /// Other rels are returned unchanged.
+    pub fn expand_rel(&self, rel: &str) -> String {
+        rel.split_once(':')
+            .and_then(|(prefix, reference)| {
+                let curie = self
+                    .links
+                    .get("curies")?
+                    .contents
+                    .iter()
+                    .find(|curie| curie.name.as_deref() == Some(prefix))?;
+                curie.expand(&Variables::new().with("rel", reference)).ok()
+            })
+            .unwrap_or_else(|| rel.to_string())
+    }
+
+    /// The links of a relation, given either as compact or as full rel.
+    pub fn links(&self, rel: &str) -> &[Link] {
+        if let Some(links) = self.links.get(rel) {
+            return &links.contents;
+        }
+        let rel = self.expand_rel(rel);
+        self.links
+            .iter()
+            .find(|(name, _)| self.expand_rel(name) == rel)
+            .map(|(_, links)| links.contents.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The first link of a relation, see [HalResource::links].
+    pub fn link(&self, rel: &str) -> Option<&Link> {
+        self.links(rel).first()
+    }
+
+    pub fn self_link(&self) -> Option<&Link> {
+        self.link("self")
+    }
+
+    /// The properties of the resource, without `_links`, `_embedded` and `_templates`.
+    pub fn state<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        let values = self
+            .values
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        serde_json::from_value(Value::Object(values))
+    }
+
+    pub fn embedded_resources(&self, rel: &str) -> &[HalResource] {
+        self.nested
+            .get(rel)
+            .map(|resources| resources.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The state of all resources embedded as `rel`.
+    pub fn embedded<T: serde::de::DeserializeOwned>(
+        &self,
+        rel: &str,
+    ) -> Result<Vec<T>, serde_json::Error> {
+        self.embedded_resources(rel)
+            .iter()
+            .map(|resource| resource.state())
+            .collect()
+    }
+
+    pub fn template(&self, name: &str) -> Option<&HalForm> {
+        self.templates.get(name)
+    }
+
+    pub fn templates(&self) -> impl Iterator<Item = (&str, &HalForm)> {
+        self.templates
+            .iter()
+            .map(|(name, form)| (name.as_str(), form))
+    }
+
     pub fn with_embedded<A: Into<HalResource>, D: Display>(mut self, name: D, value: A) -> Self {
         let resources = self
             .nested
@@ -164,16 +300,23 @@ fn merge(a: &mut Value, b: &Value) {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub struct Link {
     href: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     templated: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(alias = "type")]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     context_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hreflang: Option<String>,
 }
 
 impl Link {
@@ -183,6 +326,52 @@ impl Link {
             ..Default::default()
         }
     }
+
+    /// A link whose href is a RFC 6570 uri template.
+    pub fn templated<A: Display>(href: A) -> Self {
+        Link {
+            href: format!("{}", href),
+            templated: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_name<A: Display>(mut self, name: A) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_title<A: Display>(mut self, title: A) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn with_type<A: Display>(mut self, context_type: A) -> Self {
+        self.context_type = Some(context_type.to_string());
+        self
+    }
+
+    pub fn get_href(&self) -> &str {
+        &self.href
+    }
+
+    pub fn is_templated(&self) -> bool {
+        self.templated
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The href with the template variables filled in. Links that are not
+    /// templated are returned as they are.
+    pub fn expand(&self, variables: &Variables) -> Result<String, TemplateError> {
+        if self.templated {
+            Ok(UriTemplate::parse(&self.href)?.expand(variables))
+        } else {
+            Ok(self.href.clone())
+        }
+    }
 }
 
 impl<A: Display> From<A> for Link {
@@ -194,8 +383,9 @@ impl<A: Display> From<A> for Link {
     }
 }
 
+/// Creates links by name, the parameters fill the variables of the link in order.
 pub trait HalContext {
-    fn create_link<I, A>(&self, name: &str, parameters: A) -> (&str, Link)
+    fn create_link<I, A>(&self, name: &str, parameters: A) -> Result<(&str, Link), TemplateError>
     where
         A: IntoIterator<Item = I>,
         I: Display;
@@ -205,3 +395,207 @@ pub trait ToResource {
     fn get_links<C: HalContext>(&self, context: C) -> Vec<(&str, Link)>;
     fn to_resource<C: HalContext>(self, context: C) -> HalResource;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(example: Value) {
+        let resource: HalResource = serde_json::from_value(example.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&resource).unwrap(), example);
+    }
+
+    // the example of draft-kelly-json-hal section 6
+    #[test]
+    pub fn test_hal_spec_example() {
+        let example = json!({
+            "_links": {
+                "self": { "href": "/orders" },
+                "next": { "href": "/orders?page=2" },
+                "find": { "href": "/orders{?id}", "templated": true }
+            },
+            "_embedded": {
+                "orders": [{
+                    "_links": {
+                        "self": { "href": "/orders/123" },
+                        "basket": { "href": "/baskets/98712" },
+                        "customer": { "href": "/customers/7809" }
+                    },
+                    "total": 30.00,
+                    "currency": "USD",
+                    "status": "shipped"
+                }, {
+                    "_links": {
+                        "self": { "href": "/orders/124" },
+                        "basket": { "href": "/baskets/97213" },
+                        "customer": { "href": "/customers/12369" }
+                    },
+                    "total": 20.00,
+                    "currency": "USD",
+                    "status": "processing"
+                }]
+            },
+            "currentlyProcessing": 14,
+            "shippedToday": 20
+        });
+        round_trip(example.clone());
+
+        let resource: HalResource = serde_json::from_value(example).unwrap();
+        let find = &resource.links("find")[0];
+        assert_eq!(
+            find.expand(&Variables::new().with("id", 123)).unwrap(),
+            "/orders?id=123"
+        );
+        assert!(resource.links("missing").is_empty());
+    }
+
+    #[test]
+    pub fn test_curies() {
+        let example = json!({
+            "_links": {
+                "self": { "href": "/orders" },
+                "curies": [{
+                    "name": "acme",
+                    "href": "http://docs.acme.com/relations/{rel}",
+                    "templated": true
+                }],
+                "acme:widgets": { "href": "/widgets" }
+            }
+        });
+        round_trip(example.clone());
+
+        let resource: HalResource = serde_json::from_value(example.clone()).unwrap();
+        assert_eq!(
+            resource.expand_rel("acme:widgets"),
+            "http://docs.acme.com/relations/widgets"
+        );
+        assert_eq!(resource.expand_rel("next"), "next");
+        let widgets = resource.links("http://docs.acme.com/relations/widgets");
+        assert_eq!(widgets[0].get_href(), "/widgets");
+
+        let built = HalResource::with_self("/orders")
+            .with_curie("acme", "http://docs.acme.com/relations/{rel}")
+            .add_link("acme:widgets", "/widgets");
+        assert_eq!(serde_json::to_value(&built).unwrap(), example);
+    }
+
+    // the example of the HAL-FORMS spec
+    #[test]
+    pub fn test_hal_forms_example() {
+        let example = json!({
+            "_links": {
+                "self": { "href": "http://api.example.org/rels/create" }
+            },
+            "_templates": {
+                "default": {
+                    "title": "Create",
+                    "method": "POST",
+                    "contentType": "application/json",
+                    "properties": [
+                        {"name": "title", "required": true, "value": "", "prompt": "Title", "regex": "", "templated": false},
+                        {"name": "completed", "required": false, "value": "false", "prompt": "Completed", "regex": ""}
+                    ]
+                }
+            }
+        });
+        round_trip(example.clone());
+
+        let resource: HalResource = serde_json::from_value(example).unwrap();
+        let form = &resource.templates["default"];
+        assert_eq!(form.method, "POST");
+        assert!(form.property("title").unwrap().is_required());
+        assert!(!form.property("completed").unwrap().is_required());
+    }
+
+    #[test]
+    pub fn test_hal_forms_options() {
+        let example = json!({
+            "_links": { "self": { "href": "/zones/1" } },
+            "_templates": {
+                "default": {
+                    "method": "PUT",
+                    "target": "/zones/1/play_state",
+                    "properties": [{
+                        "name": "state",
+                        "required": true,
+                        "options": {
+                            "inline": ["play", "pause", "stop"],
+                            "maxItems": 1,
+                            "selectedValues": ["play"]
+                        }
+                    }, {
+                        "name": "source",
+                        "options": {
+                            "link": { "href": "/sources{?q}", "templated": true, "type": "application/json" },
+                            "promptField": "name",
+                            "valueField": "sid"
+                        }
+                    }]
+                }
+            }
+        });
+        round_trip(example);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Order {
+        total: f64,
+        currency: String,
+        status: String,
+    }
+
+    #[test]
+    pub fn test_typed_access() {
+        let resource = HalResource::with_self("/orders")
+            .add_state("currentlyProcessing", 14)
+            .with_resources(
+                "orders",
+                vec![
+                    HalResource::with_self("/orders/123").add_object(json!({
+                        "total": 30.0, "currency": "USD", "status": "shipped"
+                    })),
+                    HalResource::with_self("/orders/124").add_object(json!({
+                        "total": 20.0, "currency": "USD", "status": "processing"
+                    })),
+                ],
+            );
+        let resource: HalResource =
+            serde_json::from_value(serde_json::to_value(resource).unwrap()).unwrap();
+
+        let state: BTreeMap<String, i64> = resource.state().unwrap();
+        assert_eq!(state["currentlyProcessing"], 14);
+        let orders: Vec<Order> = resource.embedded("orders").unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[1].status, "processing");
+        assert_eq!(
+            resource.embedded_resources("orders")[0]
+                .self_link()
+                .unwrap()
+                .get_href(),
+            "/orders/123"
+        );
+        assert!(resource.embedded::<Order>("missing").unwrap().is_empty());
+        assert!(resource.link("next").is_none());
+    }
+
+    #[test]
+    pub fn test_create_link() {
+        let templates = LinkTemplates::new()
+            .with_template("container", "/api/browse/{sid}/{cid}{?start,end}")
+            .unwrap();
+        let (rel, link) = templates.create_link("container", [1, 42]).unwrap();
+        assert_eq!(rel, "container");
+        assert_eq!(link.get_href(), "/api/browse/1/42");
+        let (_, link) = templates.create_link("container", [1, 42, 0, 49]).unwrap();
+        assert_eq!(link.get_href(), "/api/browse/1/42?start=0&end=49");
+        assert!(templates
+            .templated_link("container")
+            .unwrap()
+            .is_templated());
+        assert_eq!(
+            templates.create_link("zone", [1]).unwrap_err(),
+            TemplateError::UnknownTemplate("zone".to_string())
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Content negotiation between HAL, plain JSON, HTML pages and htmx fragments.
+//!
+//! This only looks at header values, so it works with any web framework.
+
+/// The representations a handler can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    HalJson,
+    Json,
+    Html,
+    // a part of a page, requested by htmx with the `HX-Request` header.
+    HtmxFragment,
+}
+
+pub const HAL_JSON: &str = "application/hal+json";
+
+impl Representation {
+    /// All representations. On equal quality the earlier one wins, so
+    /// browsers and `*/*` get HTML.
+    pub const ALL: [Representation; 4] = [
+        Representation::Html,
+        Representation::HtmxFragment,
+        Representation::HalJson,
+        Representation::Json,
+    ];
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Representation::HalJson => HAL_JSON,
+            Representation::Json => "application/json",
+            Representation::Html | Representation::HtmxFragment => "text/html",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct MediaRange {
+    main_type: String,
+    sub_type: String,
+    quality: f32,
+}
+
+impl MediaRange {
+    // exact matches are more specific than `text/*`, which is more specific than `*/*`.
+    fn specificity(&self, media_type: &str) -> Option<u8> {
+        let (main_type, sub_type) = media_type.split_once('/')?;
+        match (self.main_type.as_str(), self.sub_type.as_str()) {
+            ("*", "*") => Some(1),
+            (m, "*") if m == main_type => Some(2),
+            (m, s) if m == main_type && s == sub_type => Some(3),
+            _ => None,
+        }
+    }
+}
+
+fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next()?.to_ascii_lowercase();
+            // some clients send a plain `*`
+            let (main_type, sub_type) = match media_type.as_str() {
+                "*" => ("*", "*"),
+                media_type => media_type.split_once('/')?,
+            };
+            let quality = parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, q)| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+            Some(MediaRange {
+                main_type: main_type.to_string(),
+                sub_type: sub_type.to_string(),
+                quality,
+            })
+        })
+        .collect()
+}
+
+/// The quality of the most specific range matching `media_type`.
+fn quality(ranges: &[MediaRange], media_type: &str) -> Option<f32> {
+    ranges
+        .iter()
+        .filter_map(|range| Some((range.specificity(media_type)?, range.quality)))
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, quality)| quality)
+}
+
+/// Picks the representation for a request.
+///
+/// `accept` is the `Accept` header, `htmx` whether the `HX-Request` header is set.
+/// htmx requests get a fragment instead of a page if the handler has one.
+/// Returns `None` if the client accepts none of `available`, that's a 406.
+pub fn negotiate(
+    accept: Option<&str>,
+    htmx: bool,
+    available: &[Representation],
+) -> Option<Representation> {
+    let ranges = match accept.map(str::trim) {
+        Some(accept) if !accept.is_empty() => parse_accept(accept),
+        _ => parse_accept("*/*"),
+    };
+    let has_fragment = available.contains(&Representation::HtmxFragment);
+    let mut best: Option<(Representation, f32)> = None;
+    for representation in available {
+        let candidate = match representation {
+            Representation::HtmxFragment => htmx,
+            Representation::Html => !(htmx && has_fragment),
+            _ => true,
+        };
+        if !candidate {
+            continue;
+        }
+        let quality = match quality(&ranges, representation.media_type()) {
+            Some(quality) if quality > 0.0 => quality,
+            _ => continue,
+        };
+        if best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((*representation, quality));
+        }
+    }
+    best.map(|(representation, _)| representation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Representation::*;
+
+    const BROWSER: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+    #[test]
+    pub fn test_browsers_and_curl_get_html() {
+        assert_eq!(
+            negotiate(Some(BROWSER), false, &Representation::ALL),
+            Some(Html)
+        );
+        assert_eq!(
+            negotiate(Some("*/*"), false, &Representation::ALL),
+            Some(Html)
+        );
+        assert_eq!(negotiate(None, false, &Representation::ALL), Some(Html));
+        assert_eq!(negotiate(Some(BROWSER), false, &[Json]), Some(Json));
+    }
+
+    #[test]
+    pub fn test_json_clients() {
+        assert_eq!(
+            negotiate(Some("application/json"), false, &Representation::ALL),
+            Some(Json)
+        );
+        assert_eq!(
+            negotiate(Some("application/hal+json"), false, &Representation::ALL),
+            Some(HalJson)
+        );
+        assert_eq!(
+            negotiate(
+                Some("application/json;q=0.5, application/hal+json"),
+                false,
+                &Representation::ALL
+            ),
+            Some(HalJson)
+        );
+        assert_eq!(
+            negotiate(
+                Some("application/*;q=0.9, text/html;q=0.1"),
+                false,
+                &Representation::ALL
+            ),
+            Some(HalJson)
+        );
+        assert_eq!(
+            negotiate(Some("Application/JSON"), false, &[Html, Json]),
+            Some(Json)
+        );
+    }
+
+    #[test]
+    pub fn test_htmx_gets_fragments() {
+        assert_eq!(
+            negotiate(Some("*/*"), true, &Representation::ALL),
+            Some(HtmxFragment)
+        );
+        assert_eq!(
+            negotiate(Some("text/html"), true, &[Html, Json]),
+            Some(Html)
+        );
+        assert_eq!(
+            negotiate(Some("application/json"), true, &Representation::ALL),
+            Some(Json)
+        );
+    }
+
+    #[test]
+    pub fn test_not_acceptable() {
+        assert_eq!(
+            negotiate(Some("image/png"), false, &Representation::ALL),
+            None
+        );
+        assert_eq!(negotiate(Some("text/html"), false, &[Json, HalJson]), None);
+        assert_eq!(
+            negotiate(Some("application/json;q=0, */*;q=0"), false, &[Json]),
+            None
+        );
+        assert_eq!(
+            negotiate(Some("*/*, application/json;q=0"), false, &[Json]),
+            None
+        );
+    }
+}
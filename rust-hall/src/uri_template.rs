@@ -0,0 +1,538 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A URI template as described in RFC 6570, up to level 4.
+///
+/// ```ignore
+/// let template = UriTemplate::parse("/orders{?id}")?;
+/// assert_eq!(template.expand(&Variables::new().with("id", 123)), "/orders?id=123");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+/// A value for a template variable. Empty lists and maps count as undefined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateValue {
+    String(String),
+    List(Vec<String>),
+    // the order of the keys is kept, it ends up in the uri.
+    Map(Vec<(String, String)>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables(BTreeMap<String, TemplateValue>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    UnclosedExpression(usize),
+    InvalidOperator(char),
+    InvalidVariable(String),
+    UnknownTemplate(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Expression(Operator, Vec<VarSpec>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VarSpec {
+    name: String,
+    modifier: Modifier,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Modifier {
+    None,
+    Prefix(usize),
+    Explode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    Path,
+    PathParameter,
+    Query,
+    QueryContinuation,
+}
+
+impl Operator {
+    fn parse(c: char) -> Result<Option<Operator>, TemplateError> {
+        Ok(Some(match c {
+            '+' => Operator::Reserved,
+            '#' => Operator::Fragment,
+            '.' => Operator::Label,
+            '/' => Operator::Path,
+            ';' => Operator::PathParameter,
+            '?' => Operator::Query,
+            '&' => Operator::QueryContinuation,
+            // reserved for future extensions of the RFC
+            '=' | ',' | '!' | '@' | '|' => return Err(TemplateError::InvalidOperator(c)),
+            _ => return Ok(None),
+        }))
+    }
+
+    // the table in appendix A of the RFC.
+    fn first(&self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved => "",
+            Operator::Fragment => "#",
+            Operator::Label => ".",
+            Operator::Path => "/",
+            Operator::PathParameter => ";",
+            Operator::Query => "?",
+            Operator::QueryContinuation => "&",
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved | Operator::Fragment => ",",
+            Operator::Label => ".",
+            Operator::Path => "/",
+            Operator::PathParameter => ";",
+            Operator::Query | Operator::QueryContinuation => "&",
+        }
+    }
+
+    fn named(&self) -> bool {
+        matches!(
+            self,
+            Operator::PathParameter | Operator::Query | Operator::QueryContinuation
+        )
+    }
+
+    fn if_empty(&self) -> &'static str {
+        match self {
+            Operator::Query | Operator::QueryContinuation => "=",
+            _ => "",
+        }
+    }
+
+    fn allow_reserved(&self) -> bool {
+        matches!(self, Operator::Reserved | Operator::Fragment)
+    }
+}
+
+impl UriTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            let offset = template.len() - rest.len();
+            match rest.find('{') {
+                Some(0) => {
+                    let end = rest
+                        .find('}')
+                        .ok_or(TemplateError::UnclosedExpression(offset))?;
+                    parts.push(parse_expression(&rest[1..end])?);
+                    rest = &rest[end + 1..];
+                }
+                Some(start) => {
+                    parts.push(Part::Literal(rest[..start].to_string()));
+                    rest = &rest[start..];
+                }
+                None => {
+                    parts.push(Part::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+        Ok(UriTemplate {
+            source: template.to_string(),
+            parts,
+        })
+    }
+
+    /// The names of all variables in the order they appear in the template.
+    pub fn variable_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for part in &self.parts {
+            if let Part::Expression(_, variables) = part {
+                for variable in variables {
+                    if !names.contains(&variable.name.as_str()) {
+                        names.push(&variable.name);
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    pub fn expand(&self, variables: &Variables) -> String {
+        let mut uri = String::with_capacity(self.source.len());
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => uri.push_str(&encode(literal, true)),
+                Part::Expression(operator, specs) => {
+                    expand_expression(&mut uri, *operator, specs, variables)
+                }
+            }
+        }
+        uri
+    }
+}
+
+fn parse_expression(expression: &str) -> Result<Part, TemplateError> {
+    let mut chars = expression.chars();
+    let operator = match chars.next().map(Operator::parse).transpose()? {
+        Some(Some(operator)) => operator,
+        _ => {
+            chars = expression.chars();
+            Operator::Simple
+        }
+    };
+    let specs = chars
+        .as_str()
+        .split(',')
+        .map(parse_varspec)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Part::Expression(operator, specs))
+}
+
+fn parse_varspec(spec: &str) -> Result<VarSpec, TemplateError> {
+    let invalid = || TemplateError::InvalidVariable(spec.to_string());
+    let (name, modifier) = if let Some(name) = spec.strip_suffix('*') {
+        (name, Modifier::Explode)
+    } else if let Some((name, length)) = spec.split_once(':') {
+        let length: usize = length.parse().map_err(|_| invalid())?;
+        if length == 0 || length >= 10000 {
+            return Err(invalid());
+        }
+        (name, Modifier::Prefix(length))
+    } else {
+        (spec, Modifier::None)
+    };
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '%');
+    if !valid_name {
+        return Err(invalid());
+    }
+    Ok(VarSpec {
+        name: name.to_string(),
+        modifier,
+    })
+}
+
+fn expand_expression(
+    uri: &mut String,
+    operator: Operator,
+    specs: &[VarSpec],
+    variables: &Variables,
+) {
+    let allow_reserved = operator.allow_reserved();
+    let mut first = true;
+    for spec in specs {
+        let value = match variables.get(&spec.name) {
+            Some(value) if value.is_defined() => value,
+            _ => continue,
+        };
+        uri.push_str(if first {
+            operator.first()
+        } else {
+            operator.separator()
+        });
+        first = false;
+
+        match value {
+            TemplateValue::String(value) => {
+                let value = match spec.modifier {
+                    Modifier::Prefix(length) => value.chars().take(length).collect(),
+                    _ => value.clone(),
+                };
+                if operator.named() {
+                    uri.push_str(&encode(&spec.name, true));
+                    if value.is_empty() {
+                        uri.push_str(operator.if_empty());
+                        continue;
+                    }
+                    uri.push('=');
+                }
+                uri.push_str(&encode(&value, allow_reserved));
+            }
+            TemplateValue::List(items) if spec.modifier == Modifier::Explode => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        uri.push_str(operator.separator());
+                    }
+                    if operator.named() {
+                        uri.push_str(&encode(&spec.name, true));
+                        if item.is_empty() {
+                            uri.push_str(operator.if_empty());
+                            continue;
+                        }
+                        uri.push('=');
+                    }
+                    uri.push_str(&encode(item, allow_reserved));
+                }
+            }
+            TemplateValue::List(items) => {
+                if operator.named() {
+                    uri.push_str(&encode(&spec.name, true));
+                    uri.push('=');
+                }
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| encode(item, allow_reserved))
+                    .collect();
+                uri.push_str(&items.join(","));
+            }
+            TemplateValue::Map(pairs) if spec.modifier == Modifier::Explode => {
+                for (index, (key, value)) in pairs.iter().enumerate() {
+                    if index > 0 {
+                        uri.push_str(operator.separator());
+                    }
+                    uri.push_str(&encode(key, allow_reserved));
+                    if value.is_empty() {
+                        uri.push_str(operator.if_empty());
+                    } else {
+                        uri.push('=');
+                        uri.push_str(&encode(value, allow_reserved));
+                    }
+                }
+            }
+            TemplateValue::Map(pairs) => {
+                if operator.named() {
+                    uri.push_str(&encode(&spec.name, true));
+                    uri.push('=');
+                }
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{},{}",
+                            encode(key, allow_reserved),
+                            encode(value, allow_reserved)
+                        )
+                    })
+                    .collect();
+                uri.push_str(&pairs.join(","));
+            }
+        }
+    }
+}
+
+// percent encodes everything but the unreserved characters. With `allow_reserved`
+// the reserved characters and existing percent encoded triplets are kept as well.
+fn encode(value: &str, allow_reserved: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+    for (index, byte) in bytes.iter().enumerate() {
+        let c = *byte as char;
+        let keep = byte.is_ascii_alphanumeric()
+            || matches!(c, '-' | '.' | '_' | '~')
+            || (allow_reserved && is_reserved(c))
+            || (allow_reserved && c == '%' && is_triplet(&bytes[index..]));
+        if keep {
+            encoded.push(c);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn is_reserved(c: char) -> bool {
+    ":/?#[]@!$&'()*+,;=".contains(c)
+}
+
+fn is_triplet(bytes: &[u8]) -> bool {
+    bytes.len() >= 3 && bytes[1].is_ascii_hexdigit() && bytes[2].is_ascii_hexdigit()
+}
+
+impl TemplateValue {
+    fn is_defined(&self) -> bool {
+        match self {
+            TemplateValue::String(_) => true,
+            TemplateValue::List(items) => !items.is_empty(),
+            TemplateValue::Map(pairs) => !pairs.is_empty(),
+        }
+    }
+}
+
+impl From<&str> for TemplateValue {
+    fn from(value: &str) -> Self {
+        TemplateValue::String(value.to_string())
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(value: String) -> Self {
+        TemplateValue::String(value)
+    }
+}
+
+impl From<i64> for TemplateValue {
+    fn from(value: i64) -> Self {
+        TemplateValue::String(value.to_string())
+    }
+}
+
+impl From<Vec<&str>> for TemplateValue {
+    fn from(values: Vec<&str>) -> Self {
+        TemplateValue::List(values.into_iter().map(String::from).collect())
+    }
+}
+
+impl From<Vec<String>> for TemplateValue {
+    fn from(values: Vec<String>) -> Self {
+        TemplateValue::List(values)
+    }
+}
+
+impl From<Vec<(&str, &str)>> for TemplateValue {
+    fn from(pairs: Vec<(&str, &str)>) -> Self {
+        TemplateValue::Map(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl From<Vec<(String, String)>> for TemplateValue {
+    fn from(pairs: Vec<(String, String)>) -> Self {
+        TemplateValue::Map(pairs)
+    }
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<N: Display, V: Into<TemplateValue>>(mut self, name: N, value: V) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set<N: Display, V: Into<TemplateValue>>(&mut self, name: N, value: V) {
+        self.0.insert(name.to_string(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TemplateValue> {
+        self.0.get(name)
+    }
+}
+
+impl FromStr for UriTemplate {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        UriTemplate::parse(template)
+    }
+}
+
+impl Display for UriTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedExpression(offset) => {
+                write!(f, "expression at {} is not closed", offset)
+            }
+            TemplateError::InvalidOperator(operator) => {
+                write!(f, "operator {} is reserved", operator)
+            }
+            TemplateError::InvalidVariable(spec) => write!(f, "invalid variable {}", spec),
+            TemplateError::UnknownTemplate(name) => write!(f, "no template named {}", name),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the example variables of RFC 6570 section 3.2
+    fn variables() -> Variables {
+        Variables::new()
+            .with("var", "value")
+            .with("hello", "Hello World!")
+            .with("path", "/foo/bar")
+            .with("empty", "")
+            .with("x", 1024)
+            .with("y", 768)
+            .with("list", vec!["red", "green", "blue"])
+            .with("keys", vec![("semi", ";"), ("dot", "."), ("comma", ",")])
+    }
+
+    fn expand(template: &str) -> String {
+        UriTemplate::parse(template).unwrap().expand(&variables())
+    }
+
+    #[test]
+    pub fn test_simple_and_reserved_expansion() {
+        assert_eq!(expand("{var}"), "value");
+        assert_eq!(expand("{hello}"), "Hello%20World%21");
+        assert_eq!(expand("{+hello}"), "Hello%20World!");
+        assert_eq!(expand("{+path}/here"), "/foo/bar/here");
+        assert_eq!(expand("map?{x,y}"), "map?1024,768");
+        assert_eq!(expand("{x,hello,y}"), "1024,Hello%20World%21,768");
+        assert_eq!(expand("{var:3}"), "val");
+        assert_eq!(expand("{undef}"), "");
+        assert_eq!(expand("{#var}"), "#value");
+        assert_eq!(expand("{#hello}"), "#Hello%20World!");
+    }
+
+    #[test]
+    pub fn test_lists_and_maps() {
+        assert_eq!(expand("{list}"), "red,green,blue");
+        assert_eq!(expand("{list*}"), "red,green,blue");
+        assert_eq!(expand("{keys}"), "semi,%3B,dot,.,comma,%2C");
+        assert_eq!(expand("{keys*}"), "semi=%3B,dot=.,comma=%2C");
+        assert_eq!(expand("{/list*,path:4}"), "/red/green/blue/%2Ffoo");
+        assert_eq!(expand("{;list*}"), ";list=red;list=green;list=blue");
+        assert_eq!(expand("{?list*}"), "?list=red&list=green&list=blue");
+        assert_eq!(expand("{?keys*}"), "?semi=%3B&dot=.&comma=%2C");
+    }
+
+    #[test]
+    pub fn test_path_and_query_expansion() {
+        assert_eq!(expand("X{.var}"), "X.value");
+        assert_eq!(expand("{/var,x}/here"), "/value/1024/here");
+        assert_eq!(expand("{;x,y,empty}"), ";x=1024;y=768;empty");
+        assert_eq!(expand("{?x,y,empty}"), "?x=1024&y=768&empty=");
+        assert_eq!(expand("{?x,y,undef}"), "?x=1024&y=768");
+        assert_eq!(expand("?fixed=yes{&x}"), "?fixed=yes&x=1024");
+    }
+
+    #[test]
+    pub fn test_invalid_templates() {
+        assert_eq!(
+            UriTemplate::parse("/orders{?id"),
+            Err(TemplateError::UnclosedExpression(7))
+        );
+        assert_eq!(
+            UriTemplate::parse("{=var}"),
+            Err(TemplateError::InvalidOperator('='))
+        );
+        assert!(UriTemplate::parse("{var:x}").is_err());
+        assert!(UriTemplate::parse("{}").is_err());
+    }
+
+    #[test]
+    pub fn test_variable_names() {
+        let template = UriTemplate::parse("/zones/{zone_id}/{cid}{?page,zone_id}").unwrap();
+        assert_eq!(template.variable_names(), vec!["zone_id", "cid", "page"]);
+    }
+}